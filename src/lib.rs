@@ -43,9 +43,69 @@ pub mod solutions {
     }
 }
 
+pub mod params;
+pub mod render;
+pub mod viz;
+
+use params::{ParamSpec, Params};
 use solutions::*;
+use viz::Recorder;
+
+/// Returns the tunable parameters declared by a solver.
+pub fn param_specs(year: u32, day: u32) -> &'static [ParamSpec] {
+    match (year, day) {
+        (2024, 6) => y2024::day06::PARAMS,
+        (2024, 9) => y2024::day09::PARAMS,
+        (2024, 11) => y2024::day11::PARAMS,
+        (2024, 12) => y2024::day12::PARAMS,
+        (2024, 13) => y2024::day13::PARAMS,
+        (2024, 14) => y2024::day14::PARAMS,
+        (2024, 15) => y2024::day15::PARAMS,
+        (2024, 17) => y2024::day17::PARAMS,
+        (2024, 18) => y2024::day18::PARAMS,
+        (2024, 20) => y2024::day20::PARAMS,
+        (2024, 21) => y2024::day21::PARAMS,
+        (2024, 22) => y2024::day22::PARAMS,
+        (2024, 24) => y2024::day24::PARAMS,
+        (2025, 1) => y2025::day01::PARAMS,
+        (2025, 2) => y2025::day02::PARAMS,
+        (2025, 3) => y2025::day03::PARAMS,
+        (2025, 6) => y2025::day06::PARAMS,
+        (2025, 7) => y2025::day07::PARAMS,
+        (2025, 8) => y2025::day08::PARAMS,
+        (2025, 10) => y2025::day10::PARAMS,
+        (2025, 12) => y2025::day12::PARAMS,
+        _ => &[],
+    }
+}
+
+/// Runs a simulation solver, sending its frames to `recorder`.
+///
+/// Returns the answer of the part, or `None` if the puzzle has no visualization.
+pub fn run_visualization(
+    (year, day, part): (u32, u32, u32),
+    input: &str,
+    params: &Params,
+    recorder: &mut dyn Recorder,
+) -> Option<String> {
+    match (year, day) {
+        (2024, 6) => Some(y2024::day06::visualize(input, recorder)),
+        (2024, 14) => Some(y2024::day14::visualize(input, part, params, recorder)),
+        (2024, 15) => Some(y2024::day15::visualize(input, part, params, recorder)),
+        (2025, 7) => Some(y2025::day07::visualize(input, recorder)),
+        _ => None,
+    }
+}
+
+pub fn run_solution(key: (u32, u32, u32), input: &str) -> String {
+    run_solution_with_params(key, input, &Params::default())
+}
 
-pub fn run_solution((year, day, part): (u32, u32, u32), input: &str) -> String {
+pub fn run_solution_with_params(
+    (year, day, part): (u32, u32, u32),
+    input: &str,
+    params: &Params,
+) -> String {
     match year {
         2024 => match (day, part) {
             (1, 1) => y2024::day01::calculate_total_distance(input),
@@ -59,71 +119,77 @@ pub fn run_solution((year, day, part): (u32, u32, u32), input: &str) -> String {
             (5, 1) => y2024::day05::sum_middle_pages_correctly_ordered(input),
             (5, 2) => y2024::day05::sum_middle_pages_after_fixing_order(input),
             (6, 1) => y2024::day06::count_distinct_positions(input),
-            (6, 2) => y2024::day06::count_trapping_obstruction_positions(input),
+            (6, 2) => y2024::day06::count_trapping_obstruction_positions(input, params),
             (7, 1) => y2024::day07::sum_valid_equations(input),
             (7, 2) => y2024::day07::sum_valid_equations_with_concat(input),
             (8, 1) => y2024::day08::calculate_antinodes(input),
             (8, 2) => y2024::day08::calculate_with_harmonics(input),
-            (9, 1) => y2024::day09::calculate_checksum(input),
-            (9, 2) => y2024::day09::calculate_checksum_fragmentation(input),
+            (9, 1) => y2024::day09::calculate_checksum(input, params),
+            (9, 2) => y2024::day09::calculate_checksum_fragmentation(input, params),
             (10, 1) => y2024::day10::sum_trailhead_scores(input),
             (10, 2) => y2024::day10::sum_trailhead_ratings(input),
-            (11, 1) => y2024::day11::count_stones_after_blinks(input, 25),
-            (11, 2) => y2024::day11::count_stones_after_blinks(input, 75),
-            (12, 1) => y2024::day12::calculate_total_fence_price(input),
-            (12, 2) => y2024::day12::calculate_total_fence_price_with_sides(input),
-            (13, 1) => y2024::day13::solve_claw_contraption_part1(input),
-            (13, 2) => y2024::day13::solve_claw_contraption_part2(input),
-            (14, 1) => y2024::day14::solve_part1(input),
-            (14, 2) => y2024::day14::solve_part2(input),
-            (15, 1) => y2024::day15::solve_part1(input),
-            (15, 2) => y2024::day15::solve_part2(input),
+            (11, 1) => y2024::day11::count_stones_after_blinks(
+                input,
+                params.value(&y2024::day11::BLINKS_PART1),
+            ),
+            (11, 2) => y2024::day11::count_stones_after_blinks(
+                input,
+                params.value(&y2024::day11::BLINKS_PART2),
+            ),
+            (12, 1) => y2024::day12::calculate_total_fence_price(input, params),
+            (12, 2) => y2024::day12::calculate_total_fence_price_with_sides(input, params),
+            (13, 1) => y2024::day13::solve_claw_contraption_part1(input, params),
+            (13, 2) => y2024::day13::solve_claw_contraption_part2(input, params),
+            (14, 1) => y2024::day14::solve_part1(input, params),
+            (14, 2) => y2024::day14::solve_part2(input, params),
+            (15, 1) => y2024::day15::solve_part1(input, params),
+            (15, 2) => y2024::day15::solve_part2(input, params),
             (16, 1) => y2024::day16::solve_part1(input),
             (16, 2) => y2024::day16::solve_part2(input),
-            (17, 1) => y2024::day17::solve_part1(input),
+            (17, 1) => y2024::day17::solve_part1(input, params),
             (17, 2) => y2024::day17::solve_part2(input),
-            (18, 1) => y2024::day18::solve_part1(input),
-            (18, 2) => y2024::day18::solve_part2(input),
+            (18, 1) => y2024::day18::solve_part1(input, params),
+            (18, 2) => y2024::day18::solve_part2(input, params),
             (19, 1) => y2024::day19::solve_part1(input),
             (19, 2) => y2024::day19::solve_part2(input),
-            (20, 1) => y2024::day20::solve_part1(input),
-            (20, 2) => y2024::day20::solve_part2(input),
-            (21, 1) => y2024::day21::solve_part1(input),
-            (21, 2) => y2024::day21::solve_part2(input),
-            (22, 1) => y2024::day22::solve_part1(input),
-            (22, 2) => y2024::day22::solve_part2(input),
+            (20, 1) => y2024::day20::solve_part1(input, params),
+            (20, 2) => y2024::day20::solve_part2(input, params),
+            (21, 1) => y2024::day21::solve_part1(input, params),
+            (21, 2) => y2024::day21::solve_part2(input, params),
+            (22, 1) => y2024::day22::solve_part1(input, params),
+            (22, 2) => y2024::day22::solve_part2(input, params),
             (23, 1) => y2024::day23::solve_part1(input),
             (23, 2) => y2024::day23::solve_part2(input),
-            (24, 1) => y2024::day24::solve_part1(input),
-            (24, 2) => y2024::day24::solve_part2(input),
+            (24, 1) => y2024::day24::solve_part1(input, params),
+            (24, 2) => y2024::day24::solve_part2(input, params),
             (25, 1) => y2024::day25::valid_pairs(input),
             _ => "Invalid option".to_string(),
         },
         2025 => match (day, part) {
-            (1, 1) => y2025::day01::count_zero_visits(input),
-            (1, 2) => y2025::day01::count_zero_visits_during_rotations(input),
-            (2, 1) => y2025::day02::sum_invalid_ids(input),
-            (2, 2) => y2025::day02::sum_invalid_ids_part2(input),
-            (3, 1) => y2025::day03::sum_max_joltage(input),
-            (3, 2) => y2025::day03::sum_max_joltage_part2(input),
+            (1, 1) => y2025::day01::count_zero_visits(input, params),
+            (1, 2) => y2025::day01::count_zero_visits_during_rotations(input, params),
+            (2, 1) => y2025::day02::sum_invalid_ids(input, params),
+            (2, 2) => y2025::day02::sum_invalid_ids_part2(input, params),
+            (3, 1) => y2025::day03::sum_max_joltage(input, params),
+            (3, 2) => y2025::day03::sum_max_joltage_part2(input, params),
             (4, 1) => y2025::day04::count_accessible_rolls(input),
             (4, 2) => y2025::day04::count_accessible_rolls_part2(input),
             (5, 1) => y2025::day05::count_fresh_ingredients(input),
             (5, 2) => y2025::day05::count_fresh_ingredients_part2(input),
-            (6, 1) => y2025::day06::calculate_grand_total(input),
-            (6, 2) => y2025::day06::calculate_grand_total_part2(input),
-            (7, 1) => y2025::day07::count_beam_splits(input),
-            (7, 2) => y2025::day07::count_beam_splits_part2(input),
-            (8, 1) => y2025::day08::multiply_largest_circuits(input),
+            (6, 1) => y2025::day06::calculate_grand_total(input, params),
+            (6, 2) => y2025::day06::calculate_grand_total_part2(input, params),
+            (7, 1) => y2025::day07::count_beam_splits(input, params),
+            (7, 2) => y2025::day07::count_beam_splits_part2(input, params),
+            (8, 1) => y2025::day08::multiply_largest_circuits(input, params),
             (8, 2) => y2025::day08::multiply_largest_circuits_part2(input),
             (9, 1) => y2025::day09::find_largest_rectangle(input),
             (9, 2) => y2025::day09::find_largest_rectangle_part2(input),
-            (10, 1) => y2025::day10::sum_minimum_button_presses(input),
-            (10, 2) => y2025::day10::sum_minimum_button_presses_part2(input),
+            (10, 1) => y2025::day10::sum_minimum_button_presses(input, params),
+            (10, 2) => y2025::day10::sum_minimum_button_presses_part2(input, params),
             (11, 1) => y2025::day11::count_paths_to_out(input),
             (11, 2) => y2025::day11::count_paths_to_out_part2(input),
             (12, 1) => y2025::day12::count_fittable_regions(input),
-            (12, 2) => y2025::day12::count_fittable_regions_part2(input),
+            (12, 2) => y2025::day12::count_fittable_regions_part2(input, params),
             _ => "Invalid option".to_string(),
         },
         _ => "Not implemented yet".to_string(),
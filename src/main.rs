@@ -1,21 +1,214 @@
-use aoc::run_solution;
-use clap::Parser;
+use aoc::params::{parse_param, Params};
+use aoc::render::{parse_color, ImageExport, ImageFormat, Palette, Rgb};
+use aoc::viz::{Every, Player, TextDump};
+use aoc::{param_specs, run_solution_with_params, run_visualization};
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    year: Option<u32>,
+    #[arg(required = true)]
+    day: Option<u32>,
+    #[arg(required = true)]
+    part: Option<u32>,
+    /// Puzzle parameter override, e.g. `--param width=11` (repeatable)
+    #[arg(long = "param", value_name = "KEY=VALUE", value_parser = parse_param)]
+    params: Vec<(String, String)>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists the tunable parameters of a puzzle
+    Params { year: u32, day: u32 },
+    /// Plays a simulation step by step in the terminal
+    Viz {
+        year: u32,
+        day: u32,
+        #[arg(long, default_value_t = 1)]
+        part: u32,
+        /// Frames per second while playing
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        fps: u32,
+        /// Start paused, stepping one frame per empty line
+        #[arg(long)]
+        paused: bool,
+        /// Only show every n-th frame
+        #[arg(long, default_value_t = 1, value_parser = positive_usize())]
+        every: usize,
+        /// Write the frames as text files to this directory instead of playing them
+        #[arg(long, value_name = "DIR")]
+        dump: Option<String>,
+        /// Puzzle parameter override, e.g. `--param width=11` (repeatable)
+        #[arg(long = "param", value_name = "KEY=VALUE", value_parser = parse_param)]
+        params: Vec<(String, String)>,
+    },
+    /// Exports the frames of a simulation as PNG, GIF or SVG images
+    Render {
+        year: u32,
+        day: u32,
+        #[arg(long, default_value_t = 1)]
+        part: u32,
+        /// Image format: png (one file per frame), gif (animation) or svg (one file per frame)
+        #[arg(long, default_value = "gif")]
+        format: ImageFormat,
+        /// Output directory
+        #[arg(long, value_name = "DIR", default_value = "frames")]
+        out: String,
+        /// Pixels per grid cell
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        scale: u32,
+        /// GIF frame delay in hundredths of a second
+        #[arg(long, default_value_t = 10)]
+        delay: u16,
+        /// Only export every n-th frame
+        #[arg(long, default_value_t = 1, value_parser = positive_usize())]
+        every: usize,
+        /// Color of a cell character, e.g. `--color '#=404040'` (repeatable)
+        #[arg(long = "color", value_name = "C=RRGGBB", value_parser = parse_color)]
+        colors: Vec<(char, Rgb)>,
+        /// Puzzle parameter override, e.g. `--param width=11` (repeatable)
+        #[arg(long = "param", value_name = "KEY=VALUE", value_parser = parse_param)]
+        params: Vec<(String, String)>,
+    },
+}
+
+/// Parser for frame counts, which must be at least one.
+fn positive_usize() -> clap::builder::RangedU64ValueParser<usize> {
+    clap::builder::RangedU64ValueParser::new().range(1..)
+}
+
+fn list_params(year: u32, day: u32) {
+    let specs = param_specs(year, day);
+    if specs.is_empty() {
+        println!("{year} day {day} has no parameters");
+        return;
+    }
+
+    let width = specs.iter().map(|spec| spec.key.len()).max().unwrap();
+    for spec in specs {
+        let part = spec
+            .part
+            .map_or_else(String::new, |part| format!(" (part {part})"));
+        println!(
+            "{:width$}  {:>6}  {}{part}",
+            spec.key, spec.default, spec.description
+        );
+    }
+}
+
+/// Collects the `--param` overrides, rejecting keys the puzzle part does not
+/// declare and values its specs do not accept.
+fn collect_params(
     year: u32,
     day: u32,
     part: u32,
+    params: Vec<(String, String)>,
+) -> anyhow::Result<Params> {
+    let specs = param_specs(year, day);
+    for (key, value) in &params {
+        let Some(spec) = specs
+            .iter()
+            .find(|spec| spec.key == *key && spec.part.is_none_or(|p| p == part))
+        else {
+            anyhow::bail!(
+                "unknown parameter '{key}' for {year} day {day} part {part}, \
+                 see `aoc params {year} {day}`"
+            );
+        };
+        spec.check(value).map_err(anyhow::Error::msg)?;
+    }
+
+    Ok(params.into_iter().collect())
+}
+
+fn read_input(year: u32, day: u32) -> anyhow::Result<String> {
+    Ok(std::fs::read_to_string(format!(
+        "src/input/y{year}/day{day:02}.txt"
+    ))?)
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let key = (args.year, args.day, args.part);
 
-    let input =
-        std::fs::read_to_string(format!("src/input/y{}/day{:02}.txt", args.year, args.day))?;
+    match args.command {
+        Some(Command::Params { year, day }) => list_params(year, day),
+        Some(Command::Viz {
+            year,
+            day,
+            part,
+            fps,
+            paused,
+            every,
+            dump,
+            params,
+        }) => {
+            let params = collect_params(year, day, part, params)?;
+            let input = read_input(year, day)?;
+            let key = (year, day, part);
+
+            let answer = if let Some(dir) = dump {
+                let mut recorder = Every::new(TextDump::new(&dir)?, every);
+                let answer = run_visualization(key, &input, &params, &mut recorder);
+                let frames = recorder.into_inner().finish()?;
+                eprintln!("{frames} frames written to {dir}");
+                answer
+            } else {
+                let mut recorder = Every::new(Player::new(fps, paused), every);
+                run_visualization(key, &input, &params, &mut recorder)
+            };
+
+            match answer {
+                Some(answer) => println!("{answer}"),
+                None => anyhow::bail!("{year} day {day} has no visualization"),
+            }
+        }
+        Some(Command::Render {
+            year,
+            day,
+            part,
+            format,
+            out,
+            scale,
+            delay,
+            every,
+            colors,
+            params,
+        }) => {
+            let params = collect_params(year, day, part, params)?;
+            let input = read_input(year, day)?;
+
+            let mut palette = Palette::default();
+            for (c, rgb) in colors {
+                palette.set(c, rgb);
+            }
+
+            let export = ImageExport::new(&out, format, palette, scale)?.with_delay(delay);
+            let mut recorder = Every::new(export, every);
+            let Some(answer) = run_visualization((year, day, part), &input, &params, &mut recorder)
+            else {
+                anyhow::bail!("{year} day {day} has no visualization");
+            };
+            let frames = recorder.into_inner().finish()?;
+            eprintln!("{frames} frames written to {out}");
+            println!("{answer}");
+        }
+        None => {
+            let (Some(year), Some(day), Some(part)) = (args.year, args.day, args.part) else {
+                unreachable!("clap requires year, day and part without a subcommand");
+            };
+            let params = collect_params(year, day, part, args.params)?;
+            let input = read_input(year, day)?;
 
-    println!("{}", run_solution(key, &input));
+            println!(
+                "{}",
+                run_solution_with_params((year, day, part), &input, &params)
+            );
+        }
+    }
 
     Ok(())
 }
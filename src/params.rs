@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The values a parameter accepts, checked before any solver runs.
+#[derive(Clone, Copy, Debug)]
+pub enum Kind {
+    /// Free text, interpreted by the solver.
+    Text,
+    /// Anything that parses as the number type named by the second field.
+    Number(fn(&str) -> bool, fn() -> &'static str),
+    /// `true` or `false`.
+    Bool,
+    /// One of a fixed set of words.
+    Choice(&'static [&'static str]),
+}
+
+impl Kind {
+    /// # Errors
+    /// with what was expected, if `value` is not of this kind
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let (valid, expected) = match self {
+            Self::Text => (true, String::new()),
+            Self::Number(parses, name) => (parses(value), format!("a number ({})", name())),
+            Self::Bool => (value.parse::<bool>().is_ok(), "true or false".to_string()),
+            Self::Choice(choices) => (
+                choices.contains(&value),
+                format!("one of {}", choices.join(", ")),
+            ),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(expected)
+        }
+    }
+}
+
+fn parses<T: FromStr>(value: &str) -> bool {
+    value.parse::<T>().is_ok()
+}
+
+/// A tunable puzzle parameter declared by a solver.
+///
+/// The default matches the real puzzle input. A spec restricted to one part
+/// lets both parts share a key with different defaults (e.g. day 11 blinks).
+#[derive(Clone, Copy, Debug)]
+pub struct ParamSpec {
+    pub key: &'static str,
+    pub default: &'static str,
+    pub part: Option<u32>,
+    pub kind: Kind,
+    pub description: &'static str,
+}
+
+impl ParamSpec {
+    #[must_use]
+    pub const fn new(key: &'static str, default: &'static str, description: &'static str) -> Self {
+        Self {
+            key,
+            default,
+            part: None,
+            kind: Kind::Text,
+            description,
+        }
+    }
+
+    /// Restricts the spec to a single part of the puzzle.
+    #[must_use]
+    pub const fn part(mut self, part: u32) -> Self {
+        self.part = Some(part);
+        self
+    }
+
+    /// Accepts only values the solver can read as a `T`.
+    #[must_use]
+    pub const fn number<T: FromStr>(mut self) -> Self {
+        self.kind = Kind::Number(parses::<T>, std::any::type_name::<T>);
+        self
+    }
+
+    /// Accepts only `true` or `false`.
+    #[must_use]
+    pub const fn boolean(mut self) -> Self {
+        self.kind = Kind::Bool;
+        self
+    }
+
+    /// Accepts only one of `choices`.
+    #[must_use]
+    pub const fn choices(mut self, choices: &'static [&'static str]) -> Self {
+        self.kind = Kind::Choice(choices);
+        self
+    }
+
+    /// # Errors
+    /// if `value` is not accepted by the spec
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        self.kind.check(value).map_err(|expected| {
+            format!(
+                "invalid value '{value}' for parameter {}, expected {expected}",
+                self.key
+            )
+        })
+    }
+}
+
+/// Puzzle parameters given on the command line as `--param key=value`.
+///
+/// Solvers read them through their declared [`ParamSpec`]s, so the same code
+/// can run the smaller examples or variant inputs.
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    #[must_use]
+    pub fn with(mut self, key: &str, value: impl Display) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Returns the value of the parameter, or its declared default when it was not given.
+    ///
+    /// # Panics
+    /// if the value cannot be parsed as `T`
+    #[must_use]
+    pub fn value<T: FromStr>(&self, spec: &ParamSpec) -> T {
+        let value = self
+            .values
+            .get(spec.key)
+            .map_or(spec.default, String::as_str);
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid value for parameter {}: {value}", spec.key))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Parses a single `key=value` command line argument.
+///
+/// # Errors
+/// if the argument has no `=` or an empty key
+pub fn parse_param(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("expected key=value, got '{arg}'")),
+    }
+}
+
+impl FromIterator<(String, String)> for Params {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const WIDTH: ParamSpec = ParamSpec::new("width", "101", "Width");
+    const HEIGHT: ParamSpec = ParamSpec::new("height", "103", "Height");
+
+    #[test]
+    fn test_value_default() {
+        let params = Params::new().with("width", 11);
+
+        assert_eq!(params.value::<i32>(&WIDTH), 11);
+        assert_eq!(params.value::<i32>(&HEIGHT), 103);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for parameter width")]
+    fn test_value_invalid() {
+        let params = Params::new().with("width", "wide");
+        let _: i32 = params.value(&WIDTH);
+    }
+
+    #[test]
+    fn test_check() {
+        const SHOW: ParamSpec = ParamSpec::new("show", "a", "Show").choices(&["a", "b"]);
+        assert_eq!(SHOW.check("b"), Ok(()));
+        assert_eq!(
+            SHOW.check("c"),
+            Err("invalid value 'c' for parameter show, expected one of a, b".to_string())
+        );
+        assert_eq!(
+            WIDTH.number::<u32>().check("-1"),
+            Err("invalid value '-1' for parameter width, expected a number (u32)".to_string())
+        );
+        assert!(WIDTH.number::<i32>().check("-1").is_ok());
+        assert!(WIDTH.number::<i32>().check("3000000000").is_err());
+        assert!(WIDTH.boolean().check("yes").is_err());
+        assert!(WIDTH.check("anything").is_ok());
+
+        // Every declared default passes its own check.
+        for (year, day) in [2024, 2025]
+            .into_iter()
+            .flat_map(|y| (1..=25).map(move |d| (y, d)))
+        {
+            for spec in crate::param_specs(year, day) {
+                assert_eq!(spec.check(spec.default), Ok(()), "{year} day {day}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(
+            parse_param("size=7"),
+            Ok(("size".to_string(), "7".to_string()))
+        );
+        assert!(parse_param("size").is_err());
+        assert!(parse_param("=7").is_err());
+    }
+}
@@ -0,0 +1,428 @@
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::solutions::y2024::helpers::grid::Grid;
+use crate::viz::Recorder;
+
+pub type Rgb = [u8; 3];
+
+/// Colors of the cell characters, with a fallback for the others.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    colors: Vec<(char, Rgb)>,
+    default: Rgb,
+}
+
+impl Palette {
+    #[must_use]
+    pub const fn new(default: Rgb) -> Self {
+        Self {
+            colors: Vec::new(),
+            default,
+        }
+    }
+
+    /// Sets the color of a cell character, replacing any previous one.
+    ///
+    /// # Panics
+    /// if the palette already holds 255 colors, the GIF limit
+    pub fn set(&mut self, c: char, rgb: Rgb) {
+        if let Some(entry) = self.colors.iter_mut().find(|(k, _)| *k == c) {
+            entry.1 = rgb;
+        } else {
+            assert!(self.colors.len() < 255, "Too many palette colors");
+            self.colors.push((c, rgb));
+        }
+    }
+
+    #[must_use]
+    pub fn with(mut self, c: char, rgb: Rgb) -> Self {
+        self.set(c, rgb);
+        self
+    }
+
+    #[must_use]
+    pub fn color(&self, c: char) -> Rgb {
+        self.colors
+            .iter()
+            .find(|(k, _)| *k == c)
+            .map_or(self.default, |&(_, rgb)| rgb)
+    }
+
+    /// Index of the character in [`Self::table`].
+    fn index(&self, c: char) -> u8 {
+        self.colors
+            .iter()
+            .position(|(k, _)| *k == c)
+            .map_or(0, |i| u8::try_from(i + 1).unwrap())
+    }
+
+    /// Flat RGB color table, the default color first.
+    fn table(&self) -> Vec<u8> {
+        std::iter::once(self.default)
+            .chain(self.colors.iter().map(|&(_, rgb)| rgb))
+            .flatten()
+            .collect()
+    }
+}
+
+/// Same scheme as the terminal player.
+impl Default for Palette {
+    fn default() -> Self {
+        let mut palette = Self::new([0xcc, 0xcc, 0xcc])
+            .with(' ', [0x0f, 0x0f, 0x23])
+            .with('.', [0x0f, 0x0f, 0x23])
+            .with('#', [0x60, 0x60, 0x70])
+            .with('O', [0xff, 0xd7, 0x00])
+            .with('[', [0xff, 0xd7, 0x00])
+            .with(']', [0xff, 0xd7, 0x00])
+            .with('X', [0x00, 0xb7, 0xeb])
+            .with('|', [0x00, 0xb7, 0xeb])
+            .with('S', [0x00, 0xcc, 0x00])
+            .with('E', [0x00, 0xcc, 0x00]);
+        for c in ['@', '^', '>', 'v', '<'] {
+            palette.set(c, [0xff, 0x30, 0x30]);
+        }
+        for c in '1'..='9' {
+            palette.set(c, [0x00, 0xcc, 0x00]);
+        }
+        palette
+    }
+}
+
+/// Parses a `C=RRGGBB` palette entry.
+///
+/// # Errors
+/// if the entry is not a single character and a 6-digit hex color
+pub fn parse_color(arg: &str) -> Result<(char, Rgb), String> {
+    let error = || format!("expected C=RRGGBB, got '{arg}'");
+
+    let mut chars = arg.chars();
+    let (Some(c), Some('=')) = (chars.next(), chars.next()) else {
+        return Err(error());
+    };
+    let hex = chars.as_str().trim_start_matches('#');
+    if hex.len() != 6 {
+        return Err(error());
+    }
+
+    let mut rgb = [0; 3];
+    for (i, channel) in rgb.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+    }
+    Ok((c, rgb))
+}
+
+/// Image size in pixels of a grid drawn with `scale` pixels per cell.
+///
+/// # Panics
+/// if the grid is empty or the scale is zero
+fn image_size(grid: &Grid, scale: u32) -> (u32, u32) {
+    assert!(scale > 0, "Scale must be positive");
+    let width = u32::try_from(grid.width()).unwrap() * scale;
+    let height = u32::try_from(grid.height()).unwrap() * scale;
+    assert!(width > 0 && height > 0, "Cannot draw an empty grid");
+    (width, height)
+}
+
+/// Draws each cell as a `scale`x`scale` square of pixels computed by `pixel`.
+fn draw<T: Copy>(grid: &Grid, scale: u32, pixel: impl Fn(char) -> T) -> Vec<T> {
+    let scale = scale as usize;
+    let width = grid.width() as usize;
+
+    let mut row = Vec::with_capacity(width * scale);
+    let mut image = Vec::with_capacity(grid.iter().count() * scale * scale);
+    for (i, (_, &c)) in grid.iter().enumerate() {
+        row.extend(std::iter::repeat_n(pixel(c), scale));
+        if (i + 1).is_multiple_of(width) {
+            for _ in 0..scale {
+                image.extend_from_slice(&row);
+            }
+            row.clear();
+        }
+    }
+    image
+}
+
+/// Returns the RGB pixels of the grid, row by row.
+#[must_use]
+pub fn rgb_pixels(grid: &Grid, palette: &Palette, scale: u32) -> Vec<u8> {
+    draw(grid, scale, |c| palette.color(c))
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Encodes the grid as a PNG image.
+///
+/// # Errors
+/// if the image cannot be encoded
+pub fn encode_png(grid: &Grid, palette: &Palette, scale: u32, w: impl Write) -> io::Result<()> {
+    let (width, height) = image_size(grid, scale);
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer
+        .write_image_data(&rgb_pixels(grid, palette, scale))
+        .map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+/// Returns an SVG picture of the grid, merging runs of equal cells in a row.
+///
+/// # Panics
+/// if the grid is empty or the scale is zero
+#[must_use]
+pub fn to_svg(grid: &Grid, palette: &Palette, scale: u32, title: &str) -> String {
+    let (width, height) = image_size(grid, scale);
+    let hex = |[r, g, b]: Rgb| format!("#{r:02x}{g:02x}{b:02x}");
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\n<title>{title}</title>\n",
+        grid.width(),
+        grid.height()
+    );
+    let background = palette.color(' ');
+    let _ = writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        hex(background)
+    );
+
+    let cells: Vec<_> = grid
+        .iter()
+        .map(|(pos, &c)| (pos, palette.color(c)))
+        .collect();
+    for row in cells.chunks(grid.width() as usize) {
+        for run in row.chunk_by(|a, b| a.1 == b.1) {
+            let (start, rgb) = run[0];
+            if rgb != background {
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"{}\"/>",
+                    start.x,
+                    start.y,
+                    run.len(),
+                    hex(rgb)
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// One PNG file per frame
+    Png,
+    /// A single animated GIF
+    Gif,
+    /// One SVG file per frame
+    Svg,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "gif" => Ok(Self::Gif),
+            "svg" => Ok(Self::Svg),
+            _ => Err(format!(
+                "unknown image format '{s}', expected png, gif or svg"
+            )),
+        }
+    }
+}
+
+/// Writes recorded frames as images to an output directory.
+///
+/// PNG and SVG frames go to `frame_NNNNN.png`/`.svg`, GIF frames are
+/// appended to `animation.gif`.
+pub struct ImageExport {
+    dir: PathBuf,
+    format: ImageFormat,
+    palette: Palette,
+    scale: u32,
+    delay: u16,
+    count: usize,
+    gif: Option<gif::Encoder<BufWriter<File>>>,
+    error: Option<io::Error>,
+}
+
+impl ImageExport {
+    /// # Errors
+    /// if the output directory cannot be created
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        format: ImageFormat,
+        palette: Palette,
+        scale: u32,
+    ) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            format,
+            palette,
+            scale,
+            delay: 10,
+            count: 0,
+            gif: None,
+            error: None,
+        })
+    }
+
+    /// Sets the GIF frame delay, in hundredths of a second.
+    #[must_use]
+    pub const fn with_delay(mut self, delay: u16) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Completes the output and returns the number of frames written.
+    ///
+    /// # Errors
+    /// the first error met while writing frames
+    pub fn finish(mut self) -> io::Result<usize> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if let Some(encoder) = self.gif.take() {
+            encoder.into_inner()?.flush()?;
+        }
+        Ok(self.count)
+    }
+
+    fn write_frame(&mut self, label: &str, grid: &Grid) -> io::Result<()> {
+        let name = |ext| self.dir.join(format!("frame_{:05}.{ext}", self.count));
+        match self.format {
+            ImageFormat::Png => {
+                let file = BufWriter::new(File::create(name("png"))?);
+                encode_png(grid, &self.palette, self.scale, file)
+            }
+            ImageFormat::Svg => {
+                fs::write(name("svg"), to_svg(grid, &self.palette, self.scale, label))
+            }
+            ImageFormat::Gif => self.write_gif_frame(grid),
+        }
+    }
+
+    fn write_gif_frame(&mut self, grid: &Grid) -> io::Result<()> {
+        let (width, height) = image_size(grid, self.scale);
+        let too_large = || io::Error::other("Image too large for a GIF");
+        let width = u16::try_from(width).map_err(|_| too_large())?;
+        let height = u16::try_from(height).map_err(|_| too_large())?;
+
+        if self.gif.is_none() {
+            let file = BufWriter::new(File::create(self.dir.join("animation.gif"))?);
+            let mut encoder = gif::Encoder::new(file, width, height, &self.palette.table())
+                .map_err(io::Error::other)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            self.gif = Some(encoder);
+        }
+
+        let pixels = draw(grid, self.scale, |c| self.palette.index(c));
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, pixels, None);
+        frame.delay = self.delay;
+        self.gif
+            .as_mut()
+            .unwrap()
+            .write_frame(&frame)
+            .map_err(io::Error::other)
+    }
+}
+
+impl Recorder for ImageExport {
+    fn is_active(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn record(&mut self, label: &str, grid: &Grid) {
+        match self.write_frame(label, grid) {
+            Ok(()) => self.count += 1,
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#=ff8000"), Ok(('#', [255, 128, 0])));
+        assert_eq!(parse_color("O=#00FF10"), Ok(('O', [0, 255, 16])));
+        assert!(parse_color("#ff8000").is_err());
+        assert!(parse_color("#=fff").is_err());
+        assert!(parse_color("#=gg0000").is_err());
+    }
+
+    #[test]
+    fn test_rgb_pixels() {
+        let palette = Palette::new([0, 0, 0]).with('#', [1, 2, 3]);
+        let grid = Grid::parse("#.\n.#");
+
+        assert_eq!(
+            rgb_pixels(&grid, &palette, 1),
+            vec![1, 2, 3, 0, 0, 0, 0, 0, 0, 1, 2, 3]
+        );
+        assert_eq!(rgb_pixels(&grid, &palette, 2).len(), 4 * 4 * 3);
+        assert_eq!(
+            &rgb_pixels(&grid, &palette, 2)[..12],
+            &[1, 2, 3, 1, 2, 3, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_svg_merges_runs() {
+        let palette = Palette::new([9, 9, 9])
+            .with(' ', [0, 0, 0])
+            .with('#', [255, 0, 0]);
+        let svg = to_svg(&Grid::parse("###.\n #  "), &palette, 10, "a<b");
+
+        assert!(svg.contains("width=\"40\" height=\"20\""));
+        assert!(svg.contains("<title>a&lt;b</title>"));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"3\" height=\"1\" fill=\"#ff0000\"/>"));
+        assert!(svg.contains("<rect x=\"3\" y=\"0\" width=\"1\" height=\"1\" fill=\"#090909\"/>"));
+        assert_eq!(svg.matches("<rect").count(), 4);
+    }
+
+    #[test]
+    fn test_export_gif_and_png() {
+        let dir = std::env::temp_dir().join(format!("aoc-render-test-{}", std::process::id()));
+        let grid = Grid::parse("#@\n.O");
+
+        let mut gif = ImageExport::new(&dir, ImageFormat::Gif, Palette::default(), 4).unwrap();
+        gif.record("a", &grid);
+        gif.record("b", &grid);
+        assert_eq!(gif.finish().unwrap(), 2);
+        let bytes = fs::read(dir.join("animation.gif")).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert_eq!(bytes.last(), Some(&0x3b));
+
+        let mut png = ImageExport::new(&dir, ImageFormat::Png, Palette::default(), 4).unwrap();
+        png.record("a", &grid);
+        assert_eq!(png.finish().unwrap(), 1);
+        let bytes = fs::read(dir.join("frame_00000.png")).unwrap();
+        assert!(bytes.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
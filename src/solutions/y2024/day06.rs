@@ -1,4 +1,22 @@
 use std::collections::HashSet;
+use std::thread;
+
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::grid::{Coord, Grid};
+use crate::viz::{NoFrames, Recorder};
+
+const THREADS: ParamSpec = ParamSpec::new(
+    "threads",
+    "0",
+    "Worker threads for part 2, 0 for one per CPU",
+)
+.part(2)
+.number::<usize>();
+const SHOW: ParamSpec = ParamSpec::new("show", "count", "What to print: count or positions")
+    .part(2)
+    .choices(&["count", "positions"]);
+
+pub const PARAMS: &[ParamSpec] = &[THREADS, SHOW];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position {
@@ -16,6 +34,15 @@ impl Position {
 }
 
 pub fn count_distinct_positions(input: &str) -> String {
+    walk_guard(input, &mut NoFrames).to_string()
+}
+
+/// Walks the guard out of the lab, recording a frame for each step.
+pub fn visualize(input: &str, recorder: &mut dyn Recorder) -> String {
+    walk_guard(input, recorder).to_string()
+}
+
+fn walk_guard(input: &str, recorder: &mut dyn Recorder) -> usize {
     let grid = parse_input(input);
     let directions = [
         Position { x: 0, y: -1 }, // Up
@@ -27,9 +54,18 @@ pub fn count_distinct_positions(input: &str) -> String {
     let mut guard_position = find_guard_position(&grid);
     let mut visited_positions = HashSet::new();
     let mut current_direction = 0;
+    let mut frame = Grid::from_rows(&grid);
 
     while !is_border_location(&grid, &guard_position) {
         visited_positions.insert(guard_position);
+        record_guard(
+            recorder,
+            &mut frame,
+            guard_position,
+            current_direction,
+            visited_positions.len(),
+        );
+
         let next_position = guard_position.add(&directions[current_direction]);
 
         if !is_valid_position(&grid, &next_position)
@@ -42,101 +78,230 @@ pub fn count_distinct_positions(input: &str) -> String {
     }
 
     visited_positions.insert(guard_position);
-    visited_positions.len().to_string()
+    record_guard(
+        recorder,
+        &mut frame,
+        guard_position,
+        current_direction,
+        visited_positions.len(),
+    );
+    visited_positions.len()
 }
 
-pub fn count_trapping_obstruction_positions(input: &str) -> String {
-    let grid = parse_input(input);
-    let rows = grid.len();
-    let cols = grid[0].len();
+/// Records the lab with the guard drawn facing its direction, then marks its cell as visited.
+fn record_guard(
+    recorder: &mut dyn Recorder,
+    frame: &mut Grid,
+    guard: Position,
+    direction: usize,
+    visited: usize,
+) {
+    if recorder.is_active() {
+        let pos = Coord::new(guard.x as i32, guard.y as i32);
+        frame[pos] = ['^', '>', 'v', '<'][direction];
+        recorder.record(&format!("{visited} positions visited"), frame);
+        frame[pos] = 'X';
+    }
+}
 
-    let (start_row, start_col) = find_guard_start(&grid);
-    let directions = [
-        (-1, 0), // Up
-        (0, 1),  // Right
-        (1, 0),  // Down
-        (0, -1), // Left
-    ];
+/// Offsets of the four directions, in the order the guard turns through them.
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
 
-    let mut trapping_positions = 0;
-    let mut non_trapping_positions = HashSet::new();
+/// The lab with, for every cell and direction, the cell where a guard
+/// walking that way stops in front of an obstacle.
+pub struct Patrol {
+    width: i32,
+    height: i32,
+    walls: Vec<bool>,
+    start: usize,
+    /// Stop cell per direction and cell, `None` when the guard walks out.
+    jumps: [Vec<Option<usize>>; 4],
+}
 
-    for obs_row in 0..rows {
-        for obs_col in 0..cols {
-            if grid[obs_row][obs_col] != '.' || non_trapping_positions.contains(&(obs_row, obs_col))
-            {
-                continue; // Skip non-empty cells or already-checked non-trapping positions
-            }
+impl Patrol {
+    /// # Panics
+    /// if the input has no guard
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let grid = Grid::parse(input.trim());
+        let (width, height) = (grid.width(), grid.height());
+        let walls: Vec<bool> = grid.iter().map(|(_, &c)| c == '#').collect();
+        let start = grid
+            .iter()
+            .position(|(_, &c)| c == '^')
+            .expect("Guard starting position not found!");
 
-            if causes_guard_loop(
-                &grid,
-                (start_row, start_col),
-                (obs_row, obs_col),
-                &directions,
-            ) {
-                trapping_positions += 1;
-            } else {
-                non_trapping_positions.insert((obs_row, obs_col));
-            }
-        }
+        let mut patrol = Self {
+            width,
+            height,
+            walls,
+            start,
+            jumps: Default::default(),
+        };
+        patrol.jumps = std::array::from_fn(|dir| patrol.jump_table(dir));
+        patrol
     }
 
-    trapping_positions.to_string()
-}
+    fn coord(&self, cell: usize) -> Coord {
+        let cell = i32::try_from(cell).unwrap();
+        Coord::new(cell % self.width, cell / self.width)
+    }
+
+    fn cell(&self, pos: Coord) -> usize {
+        usize::try_from(pos.y * self.width + pos.x).unwrap()
+    }
+
+    /// Returns the cell one step ahead, if still inside the lab.
+    fn ahead(&self, cell: usize, dir: usize) -> Option<usize> {
+        let pos = self.coord(cell);
+        let (dx, dy) = DIRECTIONS[dir];
+        let next = Coord::new(pos.x + dx, pos.y + dy);
+        ((0..self.width).contains(&next.x) && (0..self.height).contains(&next.y))
+            .then(|| self.cell(next))
+    }
 
-fn causes_guard_loop(
-    grid: &[Vec<char>],
-    guard_start: (usize, usize),
-    obstacle: (usize, usize),
-    directions: &[(isize, isize)],
-) -> bool {
-    let mut visited_states = HashSet::new();
-    let mut position = guard_start;
-    let mut direction = 0; // 0=up, 1=right, 2=down, 3=left
-
-    loop {
-        let state = (position, direction);
-        if !visited_states.insert(state) {
-            return true; // Guard is in a loop
+    /// Fills the stops of one direction, visiting cells so that the cell ahead
+    /// is always done first.
+    fn jump_table(&self, dir: usize) -> Vec<Option<usize>> {
+        let mut jumps = vec![None; self.walls.len()];
+        let mut cells: Vec<usize> = (0..self.walls.len()).collect();
+        if DIRECTIONS[dir].0 + DIRECTIONS[dir].1 > 0 {
+            cells.reverse();
         }
+        for cell in cells {
+            jumps[cell] = match self.ahead(cell, dir) {
+                None => None,
+                Some(next) if self.walls[next] => Some(cell),
+                Some(next) => jumps[next],
+            };
+        }
+        jumps
+    }
 
-        // Calculate the next position
-        let next_r = position.0 as isize + directions[direction].0;
-        let next_c = position.1 as isize + directions[direction].1;
+    /// Returns each cell of the original patrol with the state the guard is
+    /// in just before entering it, in walking order. Stops early if the
+    /// patrol already loops without a new obstacle.
+    #[must_use]
+    pub fn path(&self) -> Vec<(usize, (usize, usize))> {
+        let mut seen = vec![false; self.walls.len()];
+        let mut turns = vec![false; self.walls.len() * 4];
+        let mut path = Vec::new();
+        let (mut cell, mut dir) = (self.start, 0);
+        seen[cell] = true;
 
-        // Check if the guard leaves the grid
-        if next_r < 0
-            || next_r >= grid.len() as isize
-            || next_c < 0
-            || next_c >= grid[0].len() as isize
-        {
-            return false; // Guard escapes
+        while let Some(next) = self.ahead(cell, dir) {
+            if self.walls[next] {
+                if std::mem::replace(&mut turns[cell * 4 + dir], true) {
+                    break;
+                }
+                dir = (dir + 1) % 4;
+                continue;
+            }
+            if !seen[next] {
+                seen[next] = true;
+                path.push((next, (cell, dir)));
+            }
+            cell = next;
         }
+        path
+    }
 
-        let next_position = (next_r as usize, next_c as usize);
+    /// Returns true if an obstacle at `obstacle` traps the guard starting
+    /// from `state`. `stamps` marks the stops already seen with `mark`.
+    fn loops(&self, obstacle: usize, state: (usize, usize), stamps: &mut [u32], mark: u32) -> bool {
+        let (mut cell, mut dir) = state;
+        let target = self.coord(obstacle);
 
-        // Check for obstacles
-        if grid[next_position.0][next_position.1] == '#' || next_position == obstacle {
-            direction = (direction + 1) % 4; // Turn right
-        } else {
-            position = next_position; // Move forward
+        loop {
+            let pos = self.coord(cell);
+            let (dx, dy) = DIRECTIONS[dir];
+            // Distance to the new obstacle if it lies ahead on this line.
+            let blocked = ((target.x - pos.x) * dy == 0 && (target.y - pos.y) * dx == 0)
+                .then(|| (target.x - pos.x) * dx + (target.y - pos.y) * dy)
+                .filter(|&d| d > 0);
+
+            let stop = match (self.jumps[dir][cell], blocked) {
+                (Some(stop), Some(d)) => {
+                    let p = self.coord(stop);
+                    let reach = (p.x - pos.x) * dx + (p.y - pos.y) * dy;
+                    if d <= reach {
+                        self.cell(Coord::new(target.x - dx, target.y - dy))
+                    } else {
+                        stop
+                    }
+                }
+                (Some(stop), None) => stop,
+                (None, Some(_)) => self.cell(Coord::new(target.x - dx, target.y - dy)),
+                (None, None) => return false,
+            };
+
+            let slot = stop * 4 + dir;
+            if stamps[slot] == mark {
+                return true;
+            }
+            stamps[slot] = mark;
+            cell = stop;
+            dir = (dir + 1) % 4;
         }
     }
+
+    /// Returns the cells where one new obstacle traps the guard in a loop,
+    /// checking the candidates of the original path over `threads` workers.
+    #[must_use]
+    pub fn obstructions(&self, threads: usize) -> Vec<Coord> {
+        let candidates = self.path();
+        let chunk = candidates.len().div_ceil(threads.max(1)).max(1);
+
+        let mut cells: Vec<usize> = thread::scope(|scope| {
+            let workers: Vec<_> = candidates
+                .chunks(chunk)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut stamps = vec![0; self.walls.len() * 4];
+                        (1..)
+                            .zip(chunk)
+                            .filter(|&(mark, &(cell, state))| {
+                                self.loops(cell, state, &mut stamps, mark)
+                            })
+                            .map(|(_, &(cell, _))| cell)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        cells.sort_unstable();
+        cells.into_iter().map(|cell| self.coord(cell)).collect()
+    }
 }
 
-fn parse_input(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect()).collect()
+fn threads(params: &Params) -> usize {
+    match params.value(&THREADS) {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        n => n,
+    }
 }
 
-fn find_guard_start(grid: &[Vec<char>]) -> (usize, usize) {
-    for (r, row) in grid.iter().enumerate() {
-        for (c, &cell) in row.iter().enumerate() {
-            if cell == '^' {
-                return (r, c);
-            }
-        }
+pub fn count_trapping_obstruction_positions(input: &str, params: &Params) -> String {
+    let positions = Patrol::parse(input).obstructions(threads(params));
+
+    if params.value::<String>(&SHOW) == "positions" {
+        positions
+            .iter()
+            .map(|pos| format!("{},{}", pos.x, pos.y))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        positions.len().to_string()
     }
-    panic!("Guard starting position not found!");
+}
+
+fn parse_input(input: &str) -> Vec<Vec<char>> {
+    input.lines().map(|line| line.chars().collect()).collect()
 }
 
 fn find_guard_position(grid: &[Vec<char>]) -> Position {
@@ -170,6 +335,7 @@ fn is_border_location(grid: &[Vec<char>], position: &Position) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::viz::FrameLog;
 
     #[test]
     fn test_count_distinct_positions() {
@@ -177,9 +343,47 @@ mod tests {
         assert_eq!(count_distinct_positions(input), "41");
     }
 
+    #[test]
+    fn test_visualize() {
+        let input = "....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...";
+        let mut log = FrameLog::default();
+
+        assert_eq!(visualize(input, &mut log), "41");
+        assert_eq!(log.frames[0].grid.to_string(), format!("{input}\n"));
+        let last = log.frames.last().unwrap();
+        assert_eq!(last.label, "41 positions visited");
+        assert_eq!(last.grid.iter().filter(|&(_, &c)| c == 'X').count(), 40);
+    }
+
     #[test]
     fn test_count_trapping_obstruction_positions() {
         let input = "....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...";
-        assert_eq!(count_trapping_obstruction_positions(input), "6");
+        assert_eq!(
+            count_trapping_obstruction_positions(input, &Params::default()),
+            "6"
+        );
+
+        let params = Params::new().with("show", "positions").with("threads", 1);
+        assert_eq!(
+            count_trapping_obstruction_positions(input, &params),
+            "3,6\n6,7\n7,7\n1,8\n3,8\n7,9"
+        );
+    }
+
+    #[test]
+    fn test_obstruction_edge_cases() {
+        // Blocking the first step turns the guard right and out of the lab.
+        assert!(Patrol::parse(".#.\n...\n.^.").obstructions(1).is_empty());
+
+        // A box with one gap: closing it traps the guard bouncing inside.
+        let input = ".#...\n....#\n.....\n.^...\n...#.";
+        let patrol = Patrol::parse(input);
+        let loops = patrol.obstructions(2);
+        assert_eq!(loops, patrol.obstructions(1));
+        assert_eq!(loops, vec![Coord::new(0, 3)]);
+
+        // A patrol that already loops still ends.
+        let looping = Patrol::parse(".#...\n....#\n.....\n#^...\n...#.");
+        assert_eq!(looping.path().len(), 7);
     }
 }
@@ -1,81 +1,279 @@
-use std::collections::VecDeque;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::str::FromStr;
 
-pub fn calculate_checksum(input: &str) -> String {
-    solve(input, false).to_string()
+use crate::params::{ParamSpec, Params};
+
+const STRATEGY_PART1: ParamSpec =
+    ParamSpec::new("strategy", "blocks", "Compaction strategy: blocks or files")
+        .part(1)
+        .choices(&["blocks", "files"]);
+const STRATEGY_PART2: ParamSpec =
+    ParamSpec::new("strategy", "files", "Compaction strategy: blocks or files")
+        .part(2)
+        .choices(&["blocks", "files"]);
+const REPORT: ParamSpec = ParamSpec::new("report", "checksum", "What to print: checksum or stats")
+    .choices(&["checksum", "stats"]);
+
+pub const PARAMS: &[ParamSpec] = &[STRATEGY_PART1, STRATEGY_PART2, REPORT];
+
+/// A run of consecutive blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub len: usize,
 }
 
-pub fn calculate_checksum_fragmentation(input: &str) -> String {
-    solve(input, true).to_string()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Moves single blocks from the end into the leftmost free block.
+    Blocks,
+    /// Moves whole files, highest id first, into the leftmost gap they fit.
+    Files,
 }
 
-fn solve(input: &str, part2: bool) -> i64 {
-    let mut files = VecDeque::new();
-    let mut spaces = VecDeque::new();
-    let mut final_array = Vec::new();
-    let mut file_id = 0;
-    let mut position = 0;
-
-    // Parse the input into files and spaces
-    for (i, c) in input.chars().enumerate() {
-        if let Some(count) = c.to_digit(10) {
-            let count = count as usize;
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blocks" => Ok(Self::Blocks),
+            "files" => Ok(Self::Files),
+            _ => Err(format!("unknown strategy '{s}', expected blocks or files")),
+        }
+    }
+}
+
+/// A disk as its map lays it out, one span per file, before compaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskMap {
+    files: Vec<Span>,
+}
+
+impl DiskMap {
+    /// Parses a disk map, ignoring characters other than digits.
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let mut files = Vec::new();
+        let mut position = 0;
+
+        for (i, len) in input.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+            let len = len as usize;
             if i % 2 == 0 {
-                // File blocks
-                if part2 {
-                    files.push_back((position, count, file_id));
-                }
-                for _ in 0..count {
-                    final_array.push(Some(file_id));
-                    if !part2 {
-                        files.push_back((position, 1, file_id));
-                    }
-                    position += 1;
-                }
-                file_id += 1;
-            } else {
-                // Space blocks
-                spaces.push_back((position, count));
-                for _ in 0..count {
-                    final_array.push(None);
-                    position += 1;
-                }
+                files.push(Span {
+                    start: position,
+                    len,
+                });
             }
-        } else {
-            // Ignore invalid characters or input
-            continue;
+            position += len;
+        }
+
+        Self { files }
+    }
+
+    #[must_use]
+    pub fn files(&self) -> &[Span] {
+        &self.files
+    }
+
+    /// The disk with every file where the map puts it.
+    #[must_use]
+    pub fn disk(&self) -> Disk {
+        Disk {
+            files: self.files.iter().map(|&span| vec![span]).collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn compact(&self, strategy: Strategy) -> Disk {
+        match strategy {
+            Strategy::Blocks => self.compact_blocks(),
+            Strategy::Files => self.compact_files(&mut 0),
         }
     }
 
-    // Compact the array
-    while let Some((file_pos, file_size, file_id)) = files.pop_back() {
-        let mut moved = false;
-        for (space_pos, space_size) in spaces.iter_mut() {
-            if *space_pos < file_pos && file_size <= *space_size {
-                // Move file into the space
-                for i in 0..file_size {
-                    assert_eq!(final_array[file_pos + i], Some(file_id));
-                    final_array[file_pos + i] = None;
-                    final_array[*space_pos + i] = Some(file_id);
+    /// Fills gaps from the left with the blocks of the last file, splitting
+    /// spans as needed.
+    fn compact_blocks(&self) -> Disk {
+        let mut files = self.disk().files;
+        let mut gaps = self.disk().gaps().into_iter().peekable();
+
+        for (id, &Span { start, mut len }) in self.files.iter().enumerate().rev() {
+            let mut moved = Vec::new();
+            while len > 0 {
+                let Some(gap) = gaps.peek_mut().filter(|gap| gap.start < start) else {
+                    // The rest stays in place, as does every file below.
+                    moved.push(Span { start, len });
+                    files[id] = moved;
+                    files[id].sort_unstable_by_key(|span| span.start);
+                    return Disk { files };
+                };
+                let take = gap.len.min(len);
+                moved.push(Span {
+                    start: gap.start,
+                    len: take,
+                });
+                gap.start += take;
+                gap.len -= take;
+                len -= take;
+                if gap.len == 0 {
+                    gaps.next();
                 }
-                // Update the space
-                *space_pos += file_size;
-                *space_size -= file_size;
-                moved = true;
-                break;
             }
+            if !moved.is_empty() {
+                files[id] = moved;
+            }
+        }
+
+        Disk { files }
+    }
+
+    /// Moves each file once, into the leftmost gap before it that fits,
+    /// counting the heap operations in `ops`.
+    ///
+    /// Gaps are kept in one min-heap of positions per size, so finding the
+    /// leftmost fitting gap only looks at the top of each heap. Sizes stay
+    /// below 10 unless empty files join gaps.
+    fn compact_files(&self, ops: &mut usize) -> Disk {
+        let gaps = self.disk().gaps();
+        let largest = gaps.iter().map(|gap| gap.len).max().unwrap_or(0);
+        let mut heaps: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); largest + 1];
+        for gap in gaps {
+            heaps[gap.len].push(Reverse(gap.start));
+            *ops += 1;
+        }
+
+        let mut files = self.files.clone();
+        for span in files.iter_mut().rev() {
+            if span.len == 0 {
+                continue;
+            }
+
+            *ops += heaps.len().saturating_sub(span.len);
+            let best = (span.len..heaps.len())
+                .filter_map(|size| heaps[size].peek().map(|&Reverse(pos)| (pos, size)))
+                .filter(|&(pos, _)| pos < span.start)
+                .min();
+            if let Some((pos, size)) = best {
+                heaps[size].pop();
+                heaps[size - span.len].push(Reverse(pos + span.len));
+                *ops += 2;
+                span.start = pos;
+            }
+        }
+
+        Disk {
+            files: files.into_iter().map(|span| vec![span]).collect(),
+        }
+    }
+}
+
+/// Files of a disk, each a list of spans indexed by file id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disk {
+    files: Vec<Vec<Span>>,
+}
+
+impl Disk {
+    #[must_use]
+    pub fn files(&self) -> &[Vec<Span>] {
+        &self.files
+    }
+
+    /// Free spans between the files, in disk order.
+    #[must_use]
+    pub fn gaps(&self) -> Vec<Span> {
+        let mut used: Vec<Span> = self.files.iter().flatten().copied().collect();
+        used.sort_unstable_by_key(|span| span.start);
+
+        let mut end = 0;
+        let mut gaps = Vec::new();
+        for span in used.into_iter().filter(|span| span.len > 0) {
+            if span.start > end {
+                gaps.push(Span {
+                    start: end,
+                    len: span.start - end,
+                });
+            }
+            end = span.start + span.len;
         }
+        gaps
+    }
 
-        if !moved {
-            continue; // Skip files that cannot be moved
+    /// Sum over every block of its position times its file id.
+    #[must_use]
+    pub fn checksum(&self) -> u64 {
+        self.files
+            .iter()
+            .enumerate()
+            .flat_map(|(id, spans)| spans.iter().map(move |span| (id as u64, span)))
+            .map(|(id, span)| {
+                let (start, len) = (span.start as u64, span.len as u64);
+                id * (start * len + len * len.saturating_sub(1) / 2)
+            })
+            .sum()
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        let gaps = self.gaps();
+        Stats {
+            files: self.files.len(),
+            fragments: self.files.iter().map(Vec::len).sum(),
+            fragmented_files: self.files.iter().filter(|spans| spans.len() > 1).count(),
+            gaps: gaps.len(),
+            free_blocks: gaps.iter().map(|gap| gap.len).sum(),
+            largest_gap: gaps.iter().map(|gap| gap.len).max().unwrap_or(0),
         }
     }
+}
 
-    // Calculate the checksum
-    final_array
-        .iter()
-        .enumerate()
-        .filter_map(|(i, &block)| block.map(|id| i as i64 * id as i64))
-        .sum()
+/// How scattered the files and free space of a disk are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub files: usize,
+    /// Spans over all files.
+    pub fragments: usize,
+    /// Files split over more than one span.
+    pub fragmented_files: usize,
+    /// Free spans before the last used block.
+    pub gaps: usize,
+    pub free_blocks: usize,
+    pub largest_gap: usize,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "files: {}", self.files)?;
+        writeln!(f, "fragments: {}", self.fragments)?;
+        writeln!(f, "fragmented files: {}", self.fragmented_files)?;
+        writeln!(f, "gaps: {}", self.gaps)?;
+        writeln!(f, "free blocks: {}", self.free_blocks)?;
+        write!(f, "largest gap: {}", self.largest_gap)
+    }
+}
+
+fn solve(input: &str, params: &Params, strategy: &ParamSpec) -> String {
+    let strategy: Strategy = match params.value::<String>(strategy).parse() {
+        Ok(strategy) => strategy,
+        Err(e) => return e,
+    };
+    let disk = DiskMap::parse(input).compact(strategy);
+
+    if params.value::<String>(&REPORT) == "stats" {
+        disk.stats().to_string()
+    } else {
+        disk.checksum().to_string()
+    }
+}
+
+pub fn calculate_checksum(input: &str, params: &Params) -> String {
+    solve(input, params, &STRATEGY_PART1)
+}
+
+pub fn calculate_checksum_fragmentation(input: &str, params: &Params) -> String {
+    solve(input, params, &STRATEGY_PART2)
 }
 
 #[cfg(test)]
@@ -85,19 +283,109 @@ mod tests {
     #[test]
     fn test_calculate_checksum() {
         let input = "2333133121414131402";
-        assert_eq!(calculate_checksum(input), "1928");
+        assert_eq!(calculate_checksum(input, &Params::default()), "1928");
     }
 
     #[test]
     fn test_calculate_checksum_fragmentation() {
         let input = "2333133121414131402";
-        assert_eq!(calculate_checksum_fragmentation(input), "2858");
+        assert_eq!(
+            calculate_checksum_fragmentation(input, &Params::default()),
+            "2858"
+        );
+    }
+
+    #[test]
+    fn test_unknown_strategy() {
+        let params = Params::new().with("strategy", "defrag");
+        assert_eq!(
+            calculate_checksum("12345", &params),
+            "unknown strategy 'defrag', expected blocks or files"
+        );
     }
 
     #[test]
     fn test_empty_input() {
         let input = "";
-        assert_eq!(calculate_checksum(input), "0");
-        assert_eq!(calculate_checksum_fragmentation(input), "0");
+        assert_eq!(calculate_checksum(input, &Params::default()), "0");
+        assert_eq!(
+            calculate_checksum_fragmentation(input, &Params::default()),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_empty_files() {
+        // The empty file 1 sits inside a five block gap and keeps its span.
+        let map = DiskMap::parse("120312");
+        for strategy in [Strategy::Blocks, Strategy::Files] {
+            let disk = map.compact(strategy);
+            assert_eq!(disk.files()[1], vec![Span { start: 3, len: 0 }]);
+            assert_eq!(disk.files()[2], vec![Span { start: 1, len: 1 }]);
+            assert_eq!(disk.checksum(), 2);
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let disk = DiskMap::parse("2333133121414131402").compact(Strategy::Files);
+        assert_eq!(
+            disk.stats(),
+            Stats {
+                files: 10,
+                fragments: 10,
+                fragmented_files: 0,
+                gaps: 5,
+                free_blocks: 12,
+                largest_gap: 5,
+            }
+        );
+
+        let params = Params::new().with("report", "stats");
+        assert_eq!(
+            calculate_checksum("12345", &params),
+            "files: 3\nfragments: 4\nfragmented files: 1\ngaps: 0\nfree blocks: 0\nlargest gap: 0"
+        );
+    }
+
+    #[test]
+    fn test_edge_disks() {
+        let checksum = |input: &str, strategy| DiskMap::parse(input).compact(strategy).checksum();
+
+        // Free space only at the end is not a gap.
+        for strategy in [Strategy::Blocks, Strategy::Files] {
+            assert_eq!(checksum("1013", strategy), 1);
+        }
+        // File 2 fills the gap exactly, so both strategies agree.
+        for strategy in [Strategy::Blocks, Strategy::Files] {
+            assert_eq!(checksum("12302", strategy), 2 * (1 + 2) + (3 + 4 + 5));
+        }
+        // File 1 no longer fits the gap file 2 left, so it stays.
+        assert_eq!(
+            checksum("13302", Strategy::Files),
+            2 * (1 + 2) + (4 + 5 + 6)
+        );
+        // Block by block the last file splits across both gaps, but whole
+        // it fits neither and only file 1 moves.
+        assert_eq!(checksum("11113", Strategy::Blocks), 2 + 2 * (1 + 3 + 4));
+        assert_eq!(checksum("11113", Strategy::Files), 1 + 2 * (4 + 5 + 6));
+    }
+
+    #[test]
+    fn test_large_disk() {
+        let input: String = (0..200_000_u32)
+            .map(|i| char::from(b'1' + (i * 7 % 9) as u8))
+            .collect();
+        let disk = DiskMap::parse(&input);
+
+        let blocks = disk.compact(Strategy::Blocks);
+        assert_eq!(blocks.stats().gaps, 0);
+
+        // With gaps below 10 blocks, each file costs at most nine heap peeks,
+        // a pop and a push, after one push per gap.
+        let mut ops = 0;
+        let files = disk.compact_files(&mut ops);
+        assert!(files.checksum() > 0);
+        assert!(ops <= 12 * disk.files().len(), "{ops} heap operations");
     }
 }
@@ -1,5 +1,15 @@
+use crate::params::ParamSpec;
 use std::collections::HashMap;
 
+pub const BLINKS_PART1: ParamSpec = ParamSpec::new("blinks", "25", "Number of blinks")
+    .part(1)
+    .number::<usize>();
+pub const BLINKS_PART2: ParamSpec = ParamSpec::new("blinks", "75", "Number of blinks")
+    .part(2)
+    .number::<usize>();
+
+pub const PARAMS: &[ParamSpec] = &[BLINKS_PART1, BLINKS_PART2];
+
 /// Simulates the evolution of stones for a given number of blinks.
 pub fn count_stones_after_blinks(input: &str, blinks: usize) -> String {
     let stones: Vec<u64> = input
@@ -1,5 +1,24 @@
 use std::str::FromStr;
 
+use crate::params::{ParamSpec, Params};
+
+const COST_A: ParamSpec =
+    ParamSpec::new("cost_a", "3", "Tokens per press of button A").number::<i64>();
+const COST_B: ParamSpec =
+    ParamSpec::new("cost_b", "1", "Tokens per press of button B").number::<i64>();
+const OFFSET_PART1: ParamSpec = ParamSpec::new("offset", "0", "Added to both prize coordinates")
+    .part(1)
+    .number::<i64>();
+const OFFSET_PART2: ParamSpec = ParamSpec::new(
+    "offset",
+    "10000000000000",
+    "Added to both prize coordinates",
+)
+.part(2)
+.number::<i64>();
+
+pub const PARAMS: &[ParamSpec] = &[COST_A, COST_B, OFFSET_PART1, OFFSET_PART2];
+
 #[derive(Debug)]
 struct Button {
     x: i64,
@@ -7,89 +26,143 @@ struct Button {
     cost: i64,
 }
 
-#[derive(Debug)]
-struct ArcadeMachine {
-    a: Button,
-    b: Button,
-    target_x: i64,
-    target_y: i64,
+/// Button presses that win a prize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Presses {
+    pub a: i64,
+    pub b: i64,
+    pub tokens: i64,
 }
 
-impl ArcadeMachine {
-    fn new_from_strs(
-        a_x: &str,
-        a_y: &str,
-        b_x: &str,
-        b_y: &str,
-        target_x: &str,
-        target_y: &str,
-    ) -> Result<ArcadeMachine, anyhow::Error> {
-        let a_x = a_x.parse()?;
-        let a_y = a_y.parse()?;
-        let b_x = b_x.parse()?;
-        let b_y = b_y.parse()?;
-        let target_x = target_x.parse()?;
-        let target_y = target_y.parse()?;
-        let a = Button {
-            x: a_x,
-            y: a_y,
-            cost: 3,
-        };
-        let b = Button {
-            x: b_x,
-            y: b_y,
-            cost: 1,
-        };
-        Ok(ArcadeMachine {
-            a,
-            b,
-            target_x,
-            target_y,
-        })
+/// Returns `(g, x, y)` with `g = gcd(a, b) = a * x + b * y` and `g >= 0`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return if a < 0 { (-a, -1, 0) } else { (a, 1, 0) };
     }
+    let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+    (g, y, x - a.div_euclid(b) * y)
+}
 
-    fn min_tokens(&self, offset: i64) -> Option<i64> {
-        let target_x = self.target_x.checked_add(offset)?;
-        let target_y = self.target_y.checked_add(offset)?;
-
-        // Solve the linear Diophantine equation
-        let b_multiplied = self
-            .a
-            .y
-            .checked_mul(target_x)?
-            .checked_sub(self.a.x.checked_mul(target_y)?)?;
-        let b_divisor = self
-            .a
-            .y
-            .checked_mul(self.b.x)?
-            .checked_sub(self.a.x.checked_mul(self.b.y)?)?;
-
-        if b_divisor == 0 {
-            return None; // Prevent division by zero
-        }
+fn floor_div(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
 
-        if b_multiplied % b_divisor != 0 {
-            return None; // No solution
-        }
+fn ceil_div(a: i128, b: i128) -> i128 {
+    -floor_div(-a, b)
+}
 
-        let b = b_multiplied / b_divisor;
+/// Minimizes `ca * a + cb * b` over `a, b >= 0` with `p * a + q * b = t`.
+///
+/// The solutions are `a0 + k * q / g` and `b0 - k * p / g` for the
+/// particular solution of the extended gcd, so the cost is linear in `k` and
+/// the best one sits at an end of the range of `k` keeping both counts
+/// non-negative. Ties go to the fewest A presses.
+fn min_cost_on_line(p: i128, q: i128, t: i128, ca: i128, cb: i128) -> Option<(i128, i128)> {
+    match (p, q) {
+        (0, 0) => return (t == 0).then_some((0, 0)),
+        (0, q) => return (t % q == 0 && t / q >= 0).then_some((0, t / q)),
+        (p, 0) => return (t % p == 0 && t / p >= 0).then_some((t / p, 0)),
+        _ => (),
+    }
 
-        let a_multiplied = target_x.checked_sub(b.checked_mul(self.b.x)?)?;
-        if self.a.x == 0 {
-            return None; // Prevent division by zero
-        }
+    let (g, x, y) = extended_gcd(p, q);
+    if t % g != 0 {
+        return None;
+    }
+    let (a0, b0) = (x * (t / g), y * (t / g));
+    let (u, v) = (q / g, p / g);
 
-        if a_multiplied % self.a.x != 0 {
-            return None; // No solution
+    // a0 + k * u >= 0 and b0 - k * v >= 0
+    let mut lo = None::<i128>;
+    let mut hi = None::<i128>;
+    let mut bound = |k: i128, lower: bool| {
+        let side = if lower { &mut lo } else { &mut hi };
+        *side = Some(side.map_or(k, |s| if lower { s.max(k) } else { s.min(k) }));
+    };
+    if u > 0 {
+        bound(ceil_div(-a0, u), true);
+    } else {
+        bound(floor_div(a0, -u), false);
+    }
+    if v > 0 {
+        bound(floor_div(b0, v), false);
+    } else {
+        bound(ceil_div(b0, v), true);
+    }
+    if let (Some(lo), Some(hi)) = (lo, hi) {
+        if lo > hi {
+            return None;
         }
+    }
+
+    let slope = ca * u - cb * v;
+    let k = match slope.cmp(&0) {
+        std::cmp::Ordering::Greater => lo?,
+        std::cmp::Ordering::Less => hi?,
+        std::cmp::Ordering::Equal if u > 0 => lo?,
+        std::cmp::Ordering::Equal => hi?,
+    };
+    Some((a0 + k * u, b0 - k * v))
+}
+
+#[derive(Debug)]
+struct ArcadeMachine {
+    a: Button,
+    b: Button,
+    target_x: i64,
+    target_y: i64,
+}
 
-        let a = a_multiplied / self.a.x;
+impl ArcadeMachine {
+    /// Finds the cheapest presses reaching the prize moved by `offset`, also
+    /// when the buttons move the claw along the same line.
+    fn solve(&self, offset: i64) -> Option<Presses> {
+        let (ax, ay) = (i128::from(self.a.x), i128::from(self.a.y));
+        let (bx, by) = (i128::from(self.b.x), i128::from(self.b.y));
+        let tx = i128::from(self.target_x) + i128::from(offset);
+        let ty = i128::from(self.target_y) + i128::from(offset);
+        let (ca, cb) = (i128::from(self.a.cost), i128::from(self.b.cost));
+
+        let det = ax * by - ay * bx;
+        let (a, b) = if det != 0 {
+            // Independent buttons: the only solution, by Cramer's rule.
+            let a = tx * by - ty * bx;
+            let b = ax * ty - ay * tx;
+            if a % det != 0 || b % det != 0 {
+                return None;
+            }
+            (a / det, b / det)
+        } else {
+            // Colinear buttons: the prize must lie on their line, then a
+            // single coordinate determines the position on it.
+            let (dx, dy) = if ax != 0 || ay != 0 {
+                (ax, ay)
+            } else {
+                (bx, by)
+            };
+            if dx * ty - dy * tx != 0 || (dx == 0 && dy == 0 && (tx != 0 || ty != 0)) {
+                return None;
+            }
+            if ax != 0 || bx != 0 {
+                min_cost_on_line(ax, bx, tx, ca, cb)?
+            } else {
+                min_cost_on_line(ay, by, ty, ca, cb)?
+            }
+        };
 
         if a < 0 || b < 0 {
-            return None; // Non-negative solutions only
+            return None;
         }
-
-        Some(a.checked_mul(self.a.cost)? + b.checked_mul(self.b.cost)?)
+        Some(Presses {
+            a: a.try_into().ok()?,
+            b: b.try_into().ok()?,
+            tokens: (a * ca + b * cb).try_into().ok()?,
+        })
     }
 }
 
@@ -116,23 +189,24 @@ impl FromStr for Arcade {
                         .next()
                         .unwrap_or(0)
                 };
+                let field = |line: usize, n: usize| {
+                    parse_number(lines[line].split_whitespace().nth(n).unwrap_or(""))
+                };
 
-                let ax = parse_number(lines[0].split_whitespace().nth(2).unwrap_or(""));
-                let ay = parse_number(lines[0].split_whitespace().nth(3).unwrap_or(""));
-                let bx = parse_number(lines[1].split_whitespace().nth(2).unwrap_or(""));
-                let by = parse_number(lines[1].split_whitespace().nth(3).unwrap_or(""));
-                let px = parse_number(lines[2].split_whitespace().nth(1).unwrap_or(""));
-                let py = parse_number(lines[2].split_whitespace().nth(2).unwrap_or(""));
-
-                ArcadeMachine::new_from_strs(
-                    &ax.to_string(),
-                    &ay.to_string(),
-                    &bx.to_string(),
-                    &by.to_string(),
-                    &px.to_string(),
-                    &py.to_string(),
-                )
-                .ok()
+                Some(ArcadeMachine {
+                    a: Button {
+                        x: field(0, 2),
+                        y: field(0, 3),
+                        cost: 3,
+                    },
+                    b: Button {
+                        x: field(1, 2),
+                        y: field(1, 3),
+                        cost: 1,
+                    },
+                    target_x: field(2, 1),
+                    target_y: field(2, 2),
+                })
             })
             .collect();
 
@@ -141,37 +215,68 @@ impl FromStr for Arcade {
 }
 
 impl Arcade {
-    fn solve_claw_contraption_part1(&self) -> String {
-        self.machines
-            .iter()
-            .filter_map(|m| m.min_tokens(0))
-            .sum::<i64>()
-            .to_string()
+    fn set_costs(&mut self, a: i64, b: i64) {
+        for machine in &mut self.machines {
+            machine.a.cost = a;
+            machine.b.cost = b;
+        }
+    }
+
+    /// Returns the presses of every machine, `None` for prizes out of reach.
+    fn presses(&self, offset: i64) -> Vec<Option<Presses>> {
+        self.machines.iter().map(|m| m.solve(offset)).collect()
     }
 
-    fn solve_claw_contraption_part2(&self) -> String {
-        self.machines
+    fn total_tokens(&self, offset: i64) -> String {
+        self.presses(offset)
             .iter()
-            .filter_map(|m| m.min_tokens(10_000_000_000_000))
+            .flatten()
+            .map(|presses| presses.tokens)
             .sum::<i64>()
             .to_string()
     }
 }
 
-pub fn solve_claw_contraption_part1(input: &str) -> String {
-    let arcade = Arcade::from_str(input).expect("Failed to parse input");
-    arcade.solve_claw_contraption_part1()
+fn solve(input: &str, params: &Params, offset: &ParamSpec) -> String {
+    let mut arcade = Arcade::from_str(input).expect("Failed to parse input");
+    arcade.set_costs(params.value(&COST_A), params.value(&COST_B));
+    arcade.total_tokens(params.value(offset))
+}
+
+pub fn solve_claw_contraption_part1(input: &str, params: &Params) -> String {
+    solve(input, params, &OFFSET_PART1)
 }
 
-pub fn solve_claw_contraption_part2(input: &str) -> String {
-    let arcade = Arcade::from_str(input).expect("Failed to parse input");
-    arcade.solve_claw_contraption_part2()
+pub fn solve_claw_contraption_part2(input: &str, params: &Params) -> String {
+    solve(input, params, &OFFSET_PART2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn machine(
+        a: (i64, i64),
+        b: (i64, i64),
+        prize: (i64, i64),
+        costs: (i64, i64),
+    ) -> ArcadeMachine {
+        ArcadeMachine {
+            a: Button {
+                x: a.0,
+                y: a.1,
+                cost: costs.0,
+            },
+            b: Button {
+                x: b.0,
+                y: b.1,
+                cost: costs.1,
+            },
+            target_x: prize.0,
+            target_y: prize.1,
+        }
+    }
+
     #[test]
     fn test_example() {
         let input = r"Button A: X+94, Y+34
@@ -182,10 +287,97 @@ Button A: X+26, Y+66
 Button B: X+67, Y+21
 Prize: X=12748, Y=12176";
 
-        let part1_result = solve_claw_contraption_part1(input);
-        let part2_result = solve_claw_contraption_part2(input);
+        let part1_result = solve_claw_contraption_part1(input, &Params::default());
+        let part2_result = solve_claw_contraption_part2(input, &Params::default());
 
         assert_eq!(part1_result, "280");
         assert_eq!(part2_result, "459236326669");
+
+        let arcade = Arcade::from_str(input).unwrap();
+        assert_eq!(
+            arcade.presses(0)[0],
+            Some(Presses {
+                a: 80,
+                b: 40,
+                tokens: 280
+            })
+        );
+        let params = Params::new().with("cost_a", 1).with("cost_b", 1);
+        assert_eq!(solve_claw_contraption_part1(input, &params), "120");
+    }
+
+    #[test]
+    fn test_colinear_buttons() {
+        // B is twice A: the cheapest mix uses B as much as possible.
+        let m = machine((1, 2), (2, 4), (7, 14), (3, 1));
+        assert_eq!(
+            m.solve(0),
+            Some(Presses {
+                a: 1,
+                b: 3,
+                tokens: 6
+            })
+        );
+
+        // With B costly, A alone is cheaper.
+        let m = machine((1, 2), (2, 4), (7, 14), (1, 3));
+        assert_eq!(
+            m.solve(0),
+            Some(Presses {
+                a: 7,
+                b: 0,
+                tokens: 7
+            })
+        );
+
+        // Off the line, or not reachable with whole presses.
+        assert_eq!(machine((1, 2), (2, 4), (7, 15), (3, 1)).solve(0), None);
+        assert_eq!(machine((2, 2), (4, 4), (7, 7), (3, 1)).solve(0), None);
+
+        // Vertical buttons and a button that does nothing.
+        let m = machine((0, 3), (0, 0), (0, 9), (3, 1));
+        assert_eq!(
+            m.solve(0),
+            Some(Presses {
+                a: 3,
+                b: 0,
+                tokens: 9
+            })
+        );
+        assert_eq!(
+            machine((0, 0), (0, 0), (0, 0), (3, 1))
+                .solve(0)
+                .map(|p| p.tokens),
+            Some(0)
+        );
+
+        // Large offsets on the line still fit in the extended gcd.
+        let m = machine((3, 3), (5, 5), (0, 0), (3, 1));
+        assert!(m.solve(10_000_000_000_000).is_some());
+    }
+
+    #[test]
+    fn test_independent_buttons() {
+        // The one solution needs a negative or a fractional press count.
+        assert_eq!(machine((2, 1), (1, 2), (1, 5), (3, 1)).solve(0), None);
+        assert_eq!(machine((2, 1), (1, 2), (1, 1), (3, 1)).solve(0), None);
+        assert_eq!(
+            machine((2, 1), (1, 2), (4, 5), (3, 1)).solve(0),
+            Some(Presses {
+                a: 1,
+                b: 2,
+                tokens: 5
+            })
+        );
+
+        // On a line, a remainder can force presses of the dearer button.
+        assert_eq!(
+            machine((2, 2), (3, 3), (7, 7), (3, 1)).solve(0),
+            Some(Presses {
+                a: 2,
+                b: 1,
+                tokens: 7
+            })
+        );
     }
 }
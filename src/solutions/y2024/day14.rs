@@ -1,6 +1,20 @@
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::grid::{Coord, Grid};
+use crate::viz::{NoFrames, Recorder};
 use regex::Regex;
 use std::collections::HashMap;
 
+const WIDTH: ParamSpec =
+    ParamSpec::new("width", "101", "Width of the space in tiles").number::<i32>();
+const HEIGHT: ParamSpec =
+    ParamSpec::new("height", "103", "Height of the space in tiles").number::<i32>();
+const SECONDS: ParamSpec =
+    ParamSpec::new("seconds", "100", "Seconds elapsed before the safety factor")
+        .part(1)
+        .number::<i32>();
+
+pub const PARAMS: &[ParamSpec] = &[WIDTH, HEIGHT, SECONDS];
+
 #[derive(Debug)]
 struct Robot {
     px: i32,
@@ -13,14 +27,16 @@ struct Puzzle {
     robots: Vec<Robot>,
     width: i32,
     height: i32,
+    seconds: i32,
 }
 
 impl Puzzle {
-    const fn new() -> Self {
+    fn new(params: &Params) -> Self {
         Self {
             robots: Vec::new(),
-            width: 101,
-            height: 103,
+            width: params.value(&WIDTH),
+            height: params.value(&HEIGHT),
+            seconds: params.value(&SECONDS),
         }
     }
 
@@ -39,19 +55,39 @@ impl Puzzle {
 
             self.robots.push(robot);
         }
+    }
 
-        if input.contains("test") {
-            self.width = 11;
-            self.height = 7;
+    /// Draws the robot counts per tile after the given number of seconds.
+    fn frame(&self, seconds: i32) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        grid.fill('.');
+
+        for robot in &self.robots {
+            let px = (robot.px + robot.vx * seconds).rem_euclid(self.width);
+            let py = (robot.py + robot.vy * seconds).rem_euclid(self.height);
+            let tile = &mut grid[Coord::new(px, py)];
+            *tile = match *tile {
+                '.' => '1',
+                '9' => '9',
+                c => (c as u8 + 1) as char,
+            };
         }
+
+        grid
     }
 
-    fn solve_part1(&self) -> u32 {
+    fn solve_part1(&self, recorder: &mut dyn Recorder) -> u32 {
+        if recorder.is_active() {
+            for seconds in 0..=self.seconds {
+                recorder.record(&format!("{seconds} seconds"), &self.frame(seconds));
+            }
+        }
+
         let mut quadrants = HashMap::new();
 
         for robot in &self.robots {
-            let px = (robot.px + robot.vx * 100).rem_euclid(self.width);
-            let py = (robot.py + robot.vy * 100).rem_euclid(self.height);
+            let px = (robot.px + robot.vx * self.seconds).rem_euclid(self.width);
+            let py = (robot.py + robot.vy * self.seconds).rem_euclid(self.height);
 
             if px == self.width / 2 || py == self.height / 2 {
                 continue;
@@ -64,8 +100,12 @@ impl Puzzle {
         quadrants.values().product::<u32>()
     }
 
-    fn solve_part2(&self) -> i32 {
+    fn solve_part2(&self, recorder: &mut dyn Recorder) -> i32 {
         'outer: for seconds in 0..100_000 {
+            if recorder.is_active() {
+                recorder.record(&format!("{seconds} seconds"), &self.frame(seconds));
+            }
+
             let mut grid: HashMap<(i32, i32), u32> = HashMap::new();
 
             for robot in &self.robots {
@@ -87,22 +127,34 @@ impl Puzzle {
 }
 
 /// Solves Part 1: Computes the safety factor of the robots.
-pub fn solve_part1(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    let mut puzzle = Puzzle::new(params);
     puzzle.configure(input);
-    puzzle.solve_part1().to_string()
+    puzzle.solve_part1(&mut NoFrames).to_string()
 }
 
 /// Solves Part 2: Finds the time at which robots align to form a pattern.
-pub fn solve_part2(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    let mut puzzle = Puzzle::new(params);
     puzzle.configure(input);
-    puzzle.solve_part2().to_string()
+    puzzle.solve_part2(&mut NoFrames).to_string()
+}
+
+/// Replays the robots second by second, up to the safety factor time or the Easter egg.
+pub fn visualize(input: &str, part: u32, params: &Params, recorder: &mut dyn Recorder) -> String {
+    let mut puzzle = Puzzle::new(params);
+    puzzle.configure(input);
+    if part == 2 {
+        puzzle.solve_part2(recorder).to_string()
+    } else {
+        puzzle.solve_part1(recorder).to_string()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::viz::FrameLog;
 
     #[test]
     fn test_part1() {
@@ -110,7 +162,7 @@ mod tests {
 p=2,2 v=1,-1
 p=3,3 v=-1,0
 p=4,4 v=0,1";
-        assert_eq!(solve_part1(input), "4");
+        assert_eq!(solve_part1(input, &Params::default()), "4");
     }
 
     #[test]
@@ -119,6 +171,36 @@ p=4,4 v=0,1";
 p=2,2 v=1,-1
 p=3,3 v=-1,0
 p=4,4 v=0,1";
-        assert_eq!(solve_part2(input), "0");
+        assert_eq!(solve_part2(input, &Params::default()), "0");
+    }
+
+    #[test]
+    fn test_example_dimensions() {
+        let input = r"p=0,4 v=3,-3
+p=6,3 v=-1,-3
+p=10,3 v=-1,2
+p=2,0 v=2,-1
+p=0,0 v=1,3
+p=3,0 v=-2,-2
+p=7,6 v=-1,-3
+p=3,0 v=-1,-2
+p=9,3 v=2,3
+p=7,3 v=-1,2
+p=2,4 v=2,-3
+p=9,5 v=-3,-3";
+        let params = Params::new().with("width", 11).with("height", 7);
+        assert_eq!(solve_part1(input, &params), "12");
+
+        let mut log = FrameLog::default();
+        assert_eq!(visualize(input, 1, &params, &mut log), "12");
+        assert_eq!(log.frames.len(), 101);
+        assert_eq!(
+            log.frames[0].grid.to_string(),
+            "1.12.......\n...........\n...........\n......11.11\n1.1........\n.........1.\n.......1...\n"
+        );
+        assert_eq!(
+            log.frames[100].grid.to_string(),
+            "......2..1.\n...........\n1..........\n.11........\n.....1.....\n...12......\n.1....1....\n"
+        );
     }
 }
@@ -1,177 +1,332 @@
-use std::collections::{HashSet, VecDeque};
-
-/// Parses the input and returns the grid and instructions.
-fn parse_input(input: &str) -> (Vec<Vec<char>>, Vec<char>) {
-    let mut parts = input.split("\n\n");
-    let grid: Vec<Vec<char>> = parts
-        .next()
-        .unwrap()
-        .lines()
-        .map(|line| line.chars().collect())
-        .collect();
-    let instructions: Vec<char> = parts
-        .next()
-        .unwrap()
-        .chars()
-        .filter(|&c| "^v<>".contains(c))
-        .collect();
-
-    (grid, instructions)
+use std::fmt;
+
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::grid::{Coord, Grid};
+use crate::viz::{NoFrames, Recorder};
+
+const BOX_WIDTH_PART1: ParamSpec = ParamSpec::new("box_width", "1", "Width of boxes and walls")
+    .part(1)
+    .number::<usize>();
+const BOX_WIDTH_PART2: ParamSpec = ParamSpec::new("box_width", "2", "Width of boxes and walls")
+    .part(2)
+    .number::<usize>();
+const STEPS: ParamSpec = ParamSpec::new(
+    "steps",
+    "all",
+    "Number of moves to apply before the GPS sum",
+);
+
+pub const PARAMS: &[ParamSpec] = &[BOX_WIDTH_PART1, BOX_WIDTH_PART2, STEPS];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WarehouseError {
+    /// The box width is zero or makes the map too wide.
+    BoxWidth(usize),
+    /// The steps parameter is neither a number nor `all`.
+    Steps(String),
 }
 
-/// Solves the warehouse simulation.
-fn solve(input: &str, part2: bool) -> String {
-    let (mut grid, instructions) = parse_input(input);
-
-    let cols = grid[0].len();
-
-    // Expand grid for part 2
-    if part2 {
-        let mut big_grid = vec![vec!['.'; cols * 2]; grid.len()];
-        for (r, row) in grid.iter().enumerate() {
-            for (c, &cell) in row.iter().enumerate() {
-                match cell {
-                    '#' => {
-                        big_grid[r][c * 2] = '#';
-                        big_grid[r][c * 2 + 1] = '#';
-                    }
-                    'O' => {
-                        big_grid[r][c * 2] = '[';
-                        big_grid[r][c * 2 + 1] = ']';
-                    }
-                    '@' => {
-                        big_grid[r][c * 2] = '@';
-                        big_grid[r][c * 2 + 1] = '.';
-                    }
-                    '.' => {
-                        big_grid[r][c * 2] = '.';
-                        big_grid[r][c * 2 + 1] = '.';
-                    }
-                    _ => (),
-                }
-            }
+impl fmt::Display for WarehouseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BoxWidth(width) => write!(f, "invalid box width {width}"),
+            Self::Steps(steps) => write!(f, "invalid steps '{steps}', expected a number or all"),
         }
-        grid = big_grid;
     }
+}
+
+/// One applied move of the robot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub direction: char,
+    /// False if the robot was blocked and nothing moved.
+    pub moved: bool,
+    /// Boxes pushed one cell along with the robot.
+    pub boxes: Vec<usize>,
+}
+
+/// A warehouse where the map is scaled horizontally by the box width.
+#[derive(Clone, Debug)]
+pub struct Warehouse {
+    walls: Grid,
+    box_width: i32,
+    /// Left cell of each box.
+    boxes: Vec<Coord>,
+    /// Box covering each cell, row by row.
+    cells: Vec<Option<usize>>,
+    robot: Coord,
+    log: Vec<Move>,
+    undone: Vec<Move>,
+}
+
+const fn offset(direction: char) -> Coord {
+    match direction {
+        '^' => Coord::UP,
+        'v' => Coord::DOWN,
+        '<' => Coord::LEFT,
+        _ => Coord::RIGHT,
+    }
+}
 
-    let mut robot_pos = (0, 0);
+impl Warehouse {
+    /// Parses a map, making walls and boxes `box_width` cells wide.
+    ///
+    /// # Errors
+    /// if `box_width` is zero or the scaled map does not fit a grid
+    pub fn parse(map: &str, box_width: usize) -> Result<Self, WarehouseError> {
+        let map = Grid::parse(map.trim());
+        let (scale, width) = i32::try_from(box_width)
+            .ok()
+            .filter(|&scale| scale > 0)
+            .and_then(|scale| Some((scale, map.width().checked_mul(scale)?)))
+            .ok_or(WarehouseError::BoxWidth(box_width))?;
 
-    // Find the initial robot position
-    for (r, row) in grid.iter_mut().enumerate() {
-        for (c, cell) in row.iter_mut().enumerate() {
-            if *cell == '@' {
-                robot_pos = (r, c);
-                *cell = '.';
+        let mut walls = Grid::new(width, map.height());
+        walls.fill('.');
+        let mut boxes = Vec::new();
+        let mut robot = Coord::new(0, 0);
+
+        for (pos, &c) in map.iter() {
+            let pos = Coord::new(pos.x * scale, pos.y);
+            match c {
+                '#' => (0..scale).for_each(|dx| walls[pos + Coord::new(dx, 0)] = '#'),
+                'O' => boxes.push(pos),
+                '@' => robot = pos,
+                _ => (),
             }
         }
+
+        let mut warehouse = Self {
+            cells: vec![None; walls.iter().count()],
+            walls,
+            box_width: scale,
+            boxes,
+            robot,
+            log: Vec::new(),
+            undone: Vec::new(),
+        };
+        for id in 0..warehouse.boxes.len() {
+            warehouse.place(id, Some(id));
+        }
+        Ok(warehouse)
     }
 
-    let directions = [('^', (-1, 0)), ('v', (1, 0)), ('<', (0, -1)), ('>', (0, 1))];
-    let mut r = robot_pos.0;
-    let mut c = robot_pos.1;
-
-    for &inst in &instructions {
-        let (dr, dc) = directions.iter().find(|&&(ch, _)| ch == inst).unwrap().1;
-        let rr = (r as isize + dr) as usize;
-        let cc = (c as isize + dc) as usize;
-
-        if grid[rr][cc] == '#' {
-            continue; // Wall, no movement
-        } else if grid[rr][cc] == '.' {
-            r = rr;
-            c = cc;
-        } else if grid[rr][cc] == '[' || grid[rr][cc] == ']' || grid[rr][cc] == 'O' {
-            let mut queue = VecDeque::new();
-            let mut seen = HashSet::new();
-            queue.push_back((r, c));
-            let mut ok = true;
-
-            while let Some((cr, cc)) = queue.pop_front() {
-                if seen.contains(&(cr, cc)) {
-                    continue;
-                }
-                seen.insert((cr, cc));
-                let nrr = (cr as isize + dr) as usize;
-                let ncc = (cc as isize + dc) as usize;
+    fn cell(&self, pos: Coord) -> usize {
+        usize::try_from(pos.y * self.walls.width() + pos.x).unwrap()
+    }
 
-                if grid[nrr][ncc] == '#' {
-                    ok = false;
-                    break;
-                }
-                match grid[nrr][ncc] {
-                    'O' => queue.push_back((nrr, ncc)),
-                    '[' => {
-                        queue.push_back((nrr, ncc));
-                        if grid[nrr][ncc + 1] == ']' {
-                            queue.push_back((nrr, ncc + 1));
-                        }
-                    }
-                    ']' => {
-                        queue.push_back((nrr, ncc));
-                        if grid[nrr][ncc - 1] == '[' {
-                            queue.push_back((nrr, ncc - 1));
-                        }
-                    }
-                    '.' => {}
-                    _ => (),
-                }
-            }
+    /// Marks the cells of a box as covered by `value`.
+    fn place(&mut self, id: usize, value: Option<usize>) {
+        for dx in 0..self.box_width {
+            let cell = self.cell(self.boxes[id] + Coord::new(dx, 0));
+            self.cells[cell] = value;
+        }
+    }
 
-            if !ok {
-                continue;
-            }
+    fn shift(&mut self, boxes: &[usize], by: Coord) {
+        for &id in boxes {
+            self.place(id, None);
+        }
+        for &id in boxes {
+            self.boxes[id] += by;
+            self.place(id, Some(id));
+        }
+    }
 
-            while !seen.is_empty() {
-                let mut to_remove = vec![];
-                for &(cr, cc) in &seen {
-                    let nrr = (cr as isize + dr) as usize;
-                    let ncc = (cc as isize + dc) as usize;
-
-                    if !seen.contains(&(nrr, ncc)) {
-                        assert_eq!(grid[nrr][ncc], '.');
-                        grid[nrr][ncc] = grid[cr][cc];
-                        grid[cr][cc] = '.';
-                        to_remove.push((cr, cc));
-                    }
-                }
+    #[must_use]
+    pub const fn robot(&self) -> Coord {
+        self.robot
+    }
+
+    #[must_use]
+    pub fn boxes(&self) -> &[Coord] {
+        &self.boxes
+    }
+
+    /// Moves applied so far.
+    #[must_use]
+    pub fn log(&self) -> &[Move] {
+        &self.log
+    }
+
+    /// Tries to move the robot, pushing the boxes in its way, and returns the
+    /// boxes that moved. Nothing moves if a wall blocks any of them.
+    pub fn push(&mut self, direction: char) -> Vec<usize> {
+        let by = offset(direction);
+        let mut pushed = Vec::new();
+        let mut front = vec![self.robot];
+        let mut moved = true;
 
-                for pos in to_remove {
-                    seen.remove(&pos);
+        while let Some(pos) = front.pop() {
+            let next = pos + by;
+            if self.walls[next] == '#' {
+                moved = false;
+                pushed.clear();
+                break;
+            }
+            if let Some(id) = self.cells[self.cell(next)] {
+                if !pushed.contains(&id) {
+                    pushed.push(id);
+                    // Only the cells of the box that face the move can hit something.
+                    let cells = (0..self.box_width).map(|dx| self.boxes[id] + Coord::new(dx, 0));
+                    front.extend(cells.filter(|&p| self.cells[self.cell(p + by)] != Some(id)));
                 }
             }
+        }
 
-            r = (r as isize + dr) as usize;
-            c = (c as isize + dc) as usize;
+        if moved {
+            self.shift(&pushed, by);
+            self.robot += by;
         }
+        self.undone.clear();
+        self.log.push(Move {
+            direction,
+            moved,
+            boxes: pushed.clone(),
+        });
+        pushed
     }
 
-    // Calculate GPS coordinate sum
-    let mut gps_sum = 0;
-    for (r, row) in grid.iter().enumerate() {
-        for (c, &cell) in row.iter().enumerate() {
-            if cell == '[' || cell == 'O' {
-                gps_sum += 100 * r + c;
+    /// Reverts the last move, returning it.
+    pub fn undo(&mut self) -> Option<&Move> {
+        let last = self.log.pop()?;
+        if last.moved {
+            let back = Coord::new(0, 0) - offset(last.direction);
+            self.shift(&last.boxes, back);
+            self.robot += back;
+        }
+        self.undone.push(last);
+        self.undone.last()
+    }
+
+    /// Applies again the last undone move, returning it.
+    pub fn redo(&mut self) -> Option<&Move> {
+        let next = self.undone.pop()?;
+        if next.moved {
+            let by = offset(next.direction);
+            self.shift(&next.boxes, by);
+            self.robot += by;
+        }
+        self.log.push(next);
+        self.log.last()
+    }
+
+    /// Undoes or redoes moves until `step` moves are applied, or as close as possible.
+    pub fn seek(&mut self, step: usize) {
+        while self.log.len() > step && self.undo().is_some() {}
+        while self.log.len() < step && self.redo().is_some() {}
+    }
+
+    /// Returns the sum of the GPS coordinates of the boxes.
+    #[must_use]
+    pub fn gps(&self) -> usize {
+        self.boxes
+            .iter()
+            .map(|pos| usize::try_from(100 * pos.y + pos.x).unwrap())
+            .sum()
+    }
+
+    /// Draws the warehouse, boxes as `O` or `[==]` when wider.
+    #[must_use]
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = self.walls.clone();
+        for &pos in &self.boxes {
+            if self.box_width == 1 {
+                grid[pos] = 'O';
+            } else {
+                for dx in 0..self.box_width {
+                    grid[pos + Coord::new(dx, 0)] = '=';
+                }
+                grid[pos] = '[';
+                grid[pos + Coord::new(self.box_width - 1, 0)] = ']';
             }
         }
+        grid[self.robot] = '@';
+        grid
+    }
+}
+
+/// Parses the input and returns the warehouse and instructions.
+fn parse_input(input: &str, box_width: usize) -> Result<(Warehouse, Vec<char>), WarehouseError> {
+    let (map, moves) = input.split_once("\n\n").unwrap();
+    let instructions = moves.chars().filter(|&c| "^v<>".contains(c)).collect();
+
+    Ok((Warehouse::parse(map, box_width)?, instructions))
+}
+
+/// Runs the moves, recording a frame before each one, and returns the GPS sum.
+fn solve(
+    input: &str,
+    box_width: usize,
+    steps: Result<Option<usize>, WarehouseError>,
+    recorder: &mut dyn Recorder,
+) -> String {
+    let parsed = steps.and_then(|steps| Ok((parse_input(input, box_width)?, steps)));
+    let ((mut warehouse, instructions), steps) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_string(),
+    };
+    let steps = steps.unwrap_or(instructions.len()).min(instructions.len());
+
+    for (i, &inst) in instructions[..steps].iter().enumerate() {
+        if recorder.is_active() {
+            let label = format!("move {}/{}: {inst}", i + 1, instructions.len());
+            recorder.record(&label, &warehouse.to_grid());
+        }
+        warehouse.push(inst);
+    }
+
+    if recorder.is_active() {
+        recorder.record("final state", &warehouse.to_grid());
+    }
+    warehouse.gps().to_string()
+}
+
+fn steps(params: &Params) -> Result<Option<usize>, WarehouseError> {
+    let steps = params.value::<String>(&STEPS);
+    if steps == "all" {
+        return Ok(None);
     }
-    gps_sum.to_string()
+    steps
+        .parse()
+        .map(Some)
+        .map_err(|_| WarehouseError::Steps(steps))
 }
 
-pub fn solve_part1(input: &str) -> String {
-    solve(input, false)
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    solve(
+        input,
+        params.value(&BOX_WIDTH_PART1),
+        steps(params),
+        &mut NoFrames,
+    )
 }
 
-pub fn solve_part2(input: &str) -> String {
-    solve(input, true)
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    solve(
+        input,
+        params.value(&BOX_WIDTH_PART2),
+        steps(params),
+        &mut NoFrames,
+    )
+}
+
+/// Runs the simulation of the given part, recording a frame before each move.
+pub fn visualize(input: &str, part: u32, params: &Params, recorder: &mut dyn Recorder) -> String {
+    let box_width = if part == 2 {
+        &BOX_WIDTH_PART2
+    } else {
+        &BOX_WIDTH_PART1
+    };
+    solve(input, params.value(box_width), steps(params), recorder)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::viz::FrameLog;
 
-    #[test]
-    fn test_small_example() {
-        let input = r"########
+    const SMALL: &str = r"########
 #..O.O.#
 ##@.O..#
 #...O..#
@@ -182,7 +337,91 @@ mod tests {
 
 <^^>>>vv<v>>v<<";
 
-        assert_eq!(solve_part1(input), "2028");
-        assert_eq!(solve_part2(input), "1751");
+    #[test]
+    fn test_small_example() {
+        assert_eq!(solve_part1(SMALL, &Params::default()), "2028");
+        assert_eq!(solve_part2(SMALL, &Params::default()), "1751");
+    }
+
+    #[test]
+    fn test_visualize() {
+        let input = "#####\n#@O.#\n#####\n\n>>";
+        let mut log = FrameLog::default();
+
+        assert_eq!(visualize(input, 1, &Params::default(), &mut log), "103");
+        let frames: Vec<_> = log.frames.iter().map(|f| f.grid.to_string()).collect();
+        assert_eq!(
+            frames,
+            vec![
+                "#####\n#@O.#\n#####\n",
+                "#####\n#.@O#\n#####\n",
+                "#####\n#.@O#\n#####\n",
+            ]
+        );
+        assert_eq!(log.frames[0].label, "move 1/2: >");
+    }
+
+    #[test]
+    fn test_wide_boxes() {
+        let map = "#######\n#.....#\n#..O..#\n#..OO@#\n#.....#\n#######";
+        let mut warehouse = Warehouse::parse(map, 3).unwrap();
+
+        assert_eq!(warehouse.push('<'), vec![2, 1]);
+        assert_eq!(
+            warehouse.to_grid().to_string().lines().nth(3),
+            Some("###.....[=][=]@...###")
+        );
+
+        // The pushed box catches the box above that straddles its edge.
+        for m in "v<<<".chars() {
+            assert_eq!(warehouse.push(m), Vec::<usize>::new());
+        }
+        assert_eq!(warehouse.push('^'), vec![2, 0]);
+        assert_eq!(warehouse.push('^'), Vec::<usize>::new());
+        assert!(!warehouse.log().last().unwrap().moved);
+        assert_eq!(warehouse.robot(), Coord::new(11, 3));
+
+        warehouse.seek(0);
+        assert_eq!(warehouse.robot(), Coord::new(15, 3));
+        assert_eq!(
+            warehouse.to_grid().to_string(),
+            Warehouse::parse(map, 3).unwrap().to_grid().to_string()
+        );
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (mut warehouse, moves) = parse_input(SMALL, 2).unwrap();
+        let mut gps = vec![warehouse.gps()];
+        for &m in &moves {
+            warehouse.push(m);
+            gps.push(warehouse.gps());
+        }
+        assert_eq!(gps.last(), Some(&1751));
+
+        let end = warehouse.to_grid().to_string();
+        warehouse.seek(5);
+        assert_eq!(warehouse.gps(), gps[5]);
+        assert_eq!(warehouse.log().len(), 5);
+        warehouse.seek(0);
+        assert_eq!(warehouse.gps(), gps[0]);
+        warehouse.seek(moves.len());
+        assert_eq!(warehouse.to_grid().to_string(), end);
+
+        let params = Params::new().with("steps", 3);
+        assert_eq!(solve_part2(SMALL, &params), gps[3].to_string());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let params = Params::new().with("box_width", 0);
+        assert_eq!(solve_part1(SMALL, &params), "invalid box width 0");
+        let params = Params::new().with("box_width", 1_usize << 31);
+        assert_eq!(solve_part1(SMALL, &params), "invalid box width 2147483648");
+        let params = Params::new().with("steps", "some");
+        assert_eq!(
+            solve_part1(SMALL, &params),
+            "invalid steps 'some', expected a number or all"
+        );
     }
 }
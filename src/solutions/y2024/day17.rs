@@ -1,161 +1,505 @@
-/// Represents the Chronospatial Computer.
-pub struct Puzzle {
-    reg_a: u32,
-    reg_b: u32,
-    reg_c: u32,
-    program: Vec<u32>,
+use std::fmt;
+
+use crate::params::{ParamSpec, Params};
+
+const SHOW: ParamSpec = ParamSpec::new("show", "output", "What to print: output, listing or trace")
+    .part(1)
+    .choices(&["output", "listing", "trace"]);
+
+pub const PARAMS: &[ParamSpec] = &[SHOW];
+
+/// Maximum number of instructions executed before a run is abandoned.
+const STEP_LIMIT: usize = 1_000_000;
+
+/// The eight instructions of the 3-bit computer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+    Adv,
+    Bxl,
+    Bst,
+    Jnz,
+    Bxc,
+    Out,
+    Bdv,
+    Cdv,
+}
+
+impl Opcode {
+    const fn from_bits(bits: u8) -> Self {
+        match bits & 7 {
+            0 => Self::Adv,
+            1 => Self::Bxl,
+            2 => Self::Bst,
+            3 => Self::Jnz,
+            4 => Self::Bxc,
+            5 => Self::Out,
+            6 => Self::Bdv,
+            _ => Self::Cdv,
+        }
+    }
+
+    #[must_use]
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Adv => "adv",
+            Self::Bxl => "bxl",
+            Self::Bst => "bst",
+            Self::Jnz => "jnz",
+            Self::Bxc => "bxc",
+            Self::Out => "out",
+            Self::Bdv => "bdv",
+            Self::Cdv => "cdv",
+        }
+    }
+
+    /// Returns true if the operand is a combo operand rather than a literal.
+    #[must_use]
+    pub const fn takes_combo(self) -> bool {
+        matches!(
+            self,
+            Self::Adv | Self::Bst | Self::Out | Self::Bdv | Self::Cdv
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operand: u8,
 }
 
-impl Puzzle {
-    /// Create a new Puzzle instance.
-    pub const fn new() -> Self {
-        Self {
-            reg_a: 0,
-            reg_b: 0,
-            reg_c: 0,
-            program: Vec::new(),
+/// Prints the mnemonic, with combo operands 4 to 6 shown as register names.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.opcode.mnemonic();
+        match (self.opcode, self.operand) {
+            (Opcode::Bxc, _) => write!(f, "{mnemonic}"),
+            (op, 4) if op.takes_combo() => write!(f, "{mnemonic} a"),
+            (op, 5) if op.takes_combo() => write!(f, "{mnemonic} b"),
+            (op, 6) if op.takes_combo() => write!(f, "{mnemonic} c"),
+            (op, 7) if op.takes_combo() => write!(f, "{mnemonic} ?"),
+            (_, operand) => write!(f, "{mnemonic} {operand}"),
         }
     }
+}
+
+/// Decodes a program into instructions, ignoring a trailing opcode without operand.
+#[must_use]
+pub fn disassemble(program: &[u8]) -> Vec<Instruction> {
+    program
+        .chunks_exact(2)
+        .map(|pair| Instruction {
+            opcode: Opcode::from_bits(pair[0]),
+            operand: pair[1],
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl Registers {
+    fn combo(&self, operand: u8, ip: usize) -> Result<u64, VmError> {
+        match operand {
+            0..=3 => Ok(u64::from(operand)),
+            4 => Ok(self.a),
+            5 => Ok(self.b),
+            6 => Ok(self.c),
+            _ => Err(VmError::InvalidOperand { ip }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VmError {
+    /// Combo operand 7 was used by the instruction at `ip`.
+    InvalidOperand { ip: usize },
+    /// The program did not halt within the step limit.
+    StepLimit,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOperand { ip } => write!(f, "invalid combo operand at {ip}"),
+            Self::StepLimit => write!(f, "no halt after {STEP_LIMIT} steps"),
+        }
+    }
+}
+
+/// One executed instruction, with the registers before it ran.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceStep {
+    pub ip: usize,
+    pub instruction: Instruction,
+    pub registers: Registers,
+    pub output: Option<u8>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Registers { a, b, c } = self.registers;
+        write!(
+            f,
+            "{:02}: {:<6} a={a} b={b} c={c}",
+            self.ip,
+            self.instruction.to_string()
+        )?;
+        if let Some(value) = self.output {
+            write!(f, " -> {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuineError {
+    /// The program is not a single loop the reverse search understands.
+    UnsupportedShape(String),
+    /// No initial value of register A reproduces the program.
+    NotFound,
+}
+
+impl fmt::Display for QuineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedShape(reason) => write!(f, "Unsupported program: {reason}"),
+            Self::NotFound => write!(f, "No solution"),
+        }
+    }
+}
+
+/// How one iteration of a looping program consumes register A.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopShape {
+    /// Bits shifted out of A per iteration.
+    pub shift: u32,
+    /// Values output per iteration.
+    pub outputs: usize,
+}
+
+/// What a register holds during the symbolic run of one loop iteration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Sym {
+    Known(u64),
+    /// The value of A at the start of the iteration, shifted right.
+    Shifted(u32),
+    /// Some other function of A at the start of the iteration.
+    FromA,
+    /// Depends on B or C left over from the previous iteration.
+    Stale,
+}
+
+impl Sym {
+    fn xor(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Known(x), Self::Known(y)) => Self::Known(x ^ y),
+            (Self::Stale, _) | (_, Self::Stale) => Self::Stale,
+            _ => Self::FromA,
+        }
+    }
+
+    fn mod8(self) -> Self {
+        match self {
+            Self::Known(x) => Self::Known(x % 8),
+            Self::Stale => Self::Stale,
+            _ => Self::FromA,
+        }
+    }
+
+    fn shr(self, amount: Self) -> Self {
+        match (self, amount) {
+            (Self::Known(x), Self::Known(k)) => Self::Known(shr(x, k)),
+            (Self::Shifted(s), Self::Known(k)) => {
+                u32::try_from(k).map_or(Self::FromA, |k| Self::Shifted(s.saturating_add(k)))
+            }
+            (Self::Stale, _) | (_, Self::Stale) => Self::Stale,
+            _ => Self::FromA,
+        }
+    }
+}
+
+/// Division by a power of two, which is zero once every bit is shifted out.
+fn shr(value: u64, amount: u64) -> u64 {
+    u32::try_from(amount)
+        .ok()
+        .and_then(|amount| value.checked_shr(amount))
+        .unwrap_or(0)
+}
+
+/// The Chronospatial Computer.
+#[derive(Clone, Debug)]
+pub struct Computer {
+    registers: Registers,
+    program: Vec<u8>,
+}
+
+impl Computer {
+    /// Parses the registers and the program.
+    ///
+    /// # Panics
+    /// if a register or program value is not a number
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let mut registers = Registers::default();
+        let mut program = Vec::new();
 
-    /// Parses the input into registers and program.
-    pub fn configure(&mut self, input: &str) {
         for line in input.lines() {
             if let Some(v) = line.strip_prefix("Register A: ") {
-                self.reg_a = v.parse().unwrap();
+                registers.a = v.trim().parse().unwrap();
             } else if let Some(v) = line.strip_prefix("Register B: ") {
-                self.reg_b = v.parse().unwrap();
+                registers.b = v.trim().parse().unwrap();
             } else if let Some(v) = line.strip_prefix("Register C: ") {
-                self.reg_c = v.parse().unwrap();
+                registers.c = v.trim().parse().unwrap();
             } else if let Some(v) = line.strip_prefix("Program: ") {
-                self.program = v.split(',').filter_map(|i| i.trim().parse().ok()).collect();
+                program = v.split(',').map(|i| i.trim().parse().unwrap()).collect();
             }
         }
+
+        Self { registers, program }
+    }
+
+    #[must_use]
+    pub fn program(&self) -> &[u8] {
+        &self.program
+    }
+
+    #[must_use]
+    pub const fn registers(&self) -> Registers {
+        self.registers
     }
 
-    /// Runs the program with given initial register values.
-    fn run(&self, mut a: u32, mut b: u32, mut c: u32) -> Vec<u32> {
+    /// Runs the program from the given registers, calling `trace` for each instruction.
+    fn execute(
+        &self,
+        mut regs: Registers,
+        mut trace: impl FnMut(TraceStep),
+    ) -> Result<Vec<u8>, VmError> {
         let mut ip = 0;
         let mut output = Vec::new();
 
-        while ip < self.program.len() - 1 {
-            let opcode = self.program[ip];
-            let literal = self.program[ip + 1];
+        for _ in 0..STEP_LIMIT {
+            if ip + 1 >= self.program.len() {
+                return Ok(output);
+            }
 
-            let combo = || match literal {
-                0..=3 => literal,
-                4 => a,
-                5 => b,
-                6 => c,
-                _ => panic!("Invalid combo operand"),
+            let instruction = Instruction {
+                opcode: Opcode::from_bits(self.program[ip]),
+                operand: self.program[ip + 1],
             };
+            let literal = u64::from(instruction.operand);
+            let before = regs;
+            let mut out = None;
+            let mut next = ip + 2;
 
-            match opcode {
-                0 => a >>= literal,   // adv
-                1 => b ^= literal,    // bxl
-                2 => b = combo() % 8, // bst
-                3 => {
-                    if a != 0 {
-                        ip = literal as usize;
-                        continue;
-                    }
-                } // jnz
-                4 => b ^= c,          // bxc
-                5 => output.push(combo() % 8), // out
-                6 => b = a >> combo(), // bdv
-                7 => c = a >> combo(), // cdv
-                _ => panic!("Unknown opcode: {}", opcode),
+            match instruction.opcode {
+                Opcode::Adv => regs.a = shr(regs.a, regs.combo(instruction.operand, ip)?),
+                Opcode::Bxl => regs.b ^= literal,
+                Opcode::Bst => regs.b = regs.combo(instruction.operand, ip)? % 8,
+                Opcode::Jnz if regs.a != 0 => next = usize::from(instruction.operand),
+                Opcode::Jnz => (),
+                Opcode::Bxc => regs.b ^= regs.c,
+                Opcode::Out => out = Some((regs.combo(instruction.operand, ip)? % 8) as u8),
+                Opcode::Bdv => regs.b = shr(regs.a, regs.combo(instruction.operand, ip)?),
+                Opcode::Cdv => regs.c = shr(regs.a, regs.combo(instruction.operand, ip)?),
             }
 
-            ip += 2;
+            trace(TraceStep {
+                ip,
+                instruction,
+                registers: before,
+                output: out,
+            });
+            output.extend(out);
+            ip = next;
         }
 
-        output
+        Err(VmError::StepLimit)
     }
 
-    /// Finds the lowest initial value of A that makes the program output itself.
-    fn quine(&self, a: u64, i: usize, xor1: u64, xor2: u64) -> u64 {
-        let target = self.program[i] as u64;
-        let start_octal = u64::from(i == self.program.len() - 1);
-
-        for octal in start_octal..8 {
-            let new_a = (a * 8) | octal;
-            let mut b = octal ^ xor1;
-            let c = new_a >> b;
-            b ^= xor2;
-            b ^= c;
-
-            if b % 8 == target {
-                if i == 0 {
-                    return new_a;
-                }
-                let new_a = self.quine(new_a, i - 1, xor1, xor2);
-                if new_a != u64::MAX {
-                    return new_a;
-                }
-            }
-        }
-        u64::MAX
+    /// Runs the program with the given initial value of register A.
+    ///
+    /// # Errors
+    /// if the program uses an invalid operand or does not halt
+    pub fn run(&self, a: u64) -> Result<Vec<u8>, VmError> {
+        self.execute(
+            Registers {
+                a,
+                ..self.registers
+            },
+            |_| (),
+        )
+    }
+
+    /// Runs the program from the parsed registers, recording every instruction.
+    ///
+    /// # Errors
+    /// if the program uses an invalid operand or does not halt
+    pub fn trace(&self) -> Result<Vec<TraceStep>, VmError> {
+        let mut steps = Vec::new();
+        self.execute(self.registers, |step| steps.push(step))?;
+        Ok(steps)
     }
 
-    /// Solve part one.
-    pub fn part1(&self) -> String {
-        let output = self.run(self.reg_a, self.reg_b, self.reg_c);
-        output
+    /// Returns the mnemonic listing of the program, one instruction per line.
+    #[must_use]
+    pub fn listing(&self) -> String {
+        disassemble(&self.program)
             .iter()
-            .map(u32::to_string)
-            .collect::<Vec<String>>()
-            .join(",")
+            .enumerate()
+            .map(|(i, instruction)| format!("{:02}: {instruction}\n", i * 2))
+            .collect()
     }
 
-    /// Solve part two.
-    pub fn part2(&self) -> String {
-        let xors = self
-            .program
-            .chunks(2)
-            .filter(|instr| instr[0] == 1)
-            .map(|instr| instr[1])
-            .collect::<Vec<_>>();
+    /// Runs one loop iteration symbolically to find how it consumes register A.
+    ///
+    /// The program must be a straight loop body closed by `jnz 0`, whose
+    /// outputs only depend on A at the start of the iteration.
+    ///
+    /// # Errors
+    /// if the program has any other shape
+    pub fn loop_shape(&self) -> Result<LoopShape, QuineError> {
+        let unsupported = |reason: &str| Err(QuineError::UnsupportedShape(reason.to_string()));
+
+        let instructions = disassemble(&self.program);
+        let Some((last, body)) = instructions.split_last() else {
+            return unsupported("empty program");
+        };
+        if !self.program.len().is_multiple_of(2) {
+            return unsupported("odd program length");
+        }
+        if *last
+            != (Instruction {
+                opcode: Opcode::Jnz,
+                operand: 0,
+            })
+        {
+            return unsupported("program does not end with jnz 0");
+        }
+
+        let (mut a, mut b, mut c) = (Sym::Shifted(0), Sym::Stale, Sym::Stale);
+        let mut outputs = 0;
+        for instruction in body {
+            let combo = match instruction.operand {
+                operand @ 0..=3 => Sym::Known(u64::from(operand)),
+                4 => a,
+                5 => b,
+                6 => c,
+                _ if instruction.opcode.takes_combo() => {
+                    return unsupported("invalid combo operand");
+                }
+                _ => Sym::Known(0),
+            };
+            let literal = Sym::Known(u64::from(instruction.operand));
+
+            match instruction.opcode {
+                Opcode::Adv => a = a.shr(combo),
+                Opcode::Bxl => b = b.xor(literal),
+                Opcode::Bst => b = combo.mod8(),
+                Opcode::Jnz => return unsupported("jump inside the loop body"),
+                Opcode::Bxc => b = b.xor(c),
+                Opcode::Out if combo == Sym::Stale => {
+                    return unsupported("output depends on the previous iteration");
+                }
+                Opcode::Out => outputs += 1,
+                Opcode::Bdv => b = a.shr(combo),
+                Opcode::Cdv => c = a.shr(combo),
+            }
+        }
 
-        if xors.len() != 2 {
-            return "No solution".to_string();
+        match a {
+            Sym::Shifted(shift) if (1..64).contains(&shift) && outputs > 0 => {
+                if self.program.len().is_multiple_of(outputs) {
+                    Ok(LoopShape { shift, outputs })
+                } else {
+                    unsupported("program length is not a multiple of the outputs per loop")
+                }
+            }
+            Sym::Shifted(_) if outputs == 0 => unsupported("loop body has no output"),
+            _ => unsupported("register A is not shifted by a constant"),
         }
+    }
 
-        let xor1 = xors[0] as u64;
-        let xor2 = xors[1] as u64;
+    /// Finds the lowest initial value of A that makes the program output itself.
+    ///
+    /// A is rebuilt from its most significant chunk, each chunk covering one
+    /// more loop iteration at the end of the output.
+    ///
+    /// # Errors
+    /// if the program shape is unsupported or no value works
+    pub fn find_quine(&self) -> Result<u64, QuineError> {
+        let shape = self.loop_shape()?;
+        let iterations = self.program.len() / shape.outputs;
+        self.search_quine(shape, 0, 0, iterations)
+            .ok_or(QuineError::NotFound)
+    }
 
-        let result = self.quine(0, self.program.len() - 1, xor1, xor2);
-        if result == u64::MAX {
-            "No solution".to_string()
-        } else {
-            result.to_string()
+    fn search_quine(&self, shape: LoopShape, a: u64, done: usize, total: usize) -> Option<u64> {
+        if done == total {
+            return Some(a);
         }
+        if a.leading_zeros() < shape.shift {
+            return None;
+        }
+
+        let suffix = &self.program[self.program.len() - (done + 1) * shape.outputs..];
+        (0..1_u64 << shape.shift).find_map(|chunk| {
+            let candidate = (a << shape.shift) | chunk;
+            if candidate == 0 || self.run(candidate).ok()? != suffix {
+                return None;
+            }
+            self.search_quine(shape, candidate, done + 1, total)
+        })
     }
 }
 
-impl Default for Puzzle {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Joins output values with commas.
+fn join(values: &[u8]) -> String {
+    values
+        .iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 /// Solves part one of the puzzle.
-pub fn solve_part1(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
-    puzzle.configure(input);
-    puzzle.part1()
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    let computer = Computer::parse(input);
+
+    match params.value::<String>(&SHOW).as_str() {
+        "listing" => computer.listing(),
+        "trace" => match computer.trace() {
+            Ok(steps) => steps.iter().map(|step| format!("{step}\n")).collect(),
+            Err(e) => e.to_string(),
+        },
+        _ => computer
+            .run(computer.registers().a)
+            .map_or_else(|e| e.to_string(), |out| join(&out)),
+    }
 }
 
 /// Solves part two of the puzzle.
 pub fn solve_part2(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
-    puzzle.configure(input);
-    puzzle.part2()
+    Computer::parse(input)
+        .find_quine()
+        .map_or_else(|e| e.to_string(), |a| a.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn computer(a: u64, b: u64, c: u64, program: &[u8]) -> Computer {
+        Computer {
+            registers: Registers { a, b, c },
+            program: program.to_vec(),
+        }
+    }
+
     #[test]
     fn test_part1() {
         let input = r"Register A: 729
@@ -164,7 +508,10 @@ Register C: 0
 
 Program: 0,1,5,4,3,0";
 
-        assert_eq!(solve_part1(input), "4,6,3,5,6,3,5,2,1,0");
+        assert_eq!(
+            solve_part1(input, &Params::default()),
+            "4,6,3,5,6,3,5,2,1,0"
+        );
     }
 
     #[test]
@@ -175,6 +522,83 @@ Register C: 0
 
 Program: 0,3,5,4,3,0";
 
-        assert_eq!(solve_part2(input), "No solution");
+        assert_eq!(solve_part2(input), "117440");
+    }
+
+    #[test]
+    fn test_small_programs() {
+        assert_eq!(
+            computer(10, 0, 0, &[5, 0, 5, 1, 5, 4]).run(10),
+            Ok(vec![0, 1, 2])
+        );
+        assert_eq!(
+            computer(2024, 0, 0, &[0, 1, 5, 4, 3, 0]).run(2024),
+            Ok(vec![4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0])
+        );
+        assert_eq!(
+            computer(0, 0, 0, &[5, 7]).run(0),
+            Err(VmError::InvalidOperand { ip: 0 })
+        );
+        assert_eq!(computer(1, 0, 0, &[3, 0]).run(1), Err(VmError::StepLimit));
+    }
+
+    #[test]
+    fn test_listing_and_trace() {
+        let vm = computer(0, 29, 9, &[1, 7, 2, 6, 4, 0, 5, 5]);
+        assert_eq!(vm.listing(), "00: bxl 7\n02: bst c\n04: bxc\n06: out b\n");
+
+        let steps = vm.trace().unwrap();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[1].registers.b, 26);
+        assert_eq!(steps[2].registers.b, 1);
+        assert_eq!(steps[3].to_string(), "06: out b  a=0 b=8 c=9 -> 0");
+
+        let params = Params::new().with("show", "listing");
+        let input = "Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0";
+        assert_eq!(
+            solve_part1(input, &params),
+            "00: adv 3\n02: out a\n04: jnz 0\n"
+        );
+    }
+
+    #[test]
+    fn test_quine_search() {
+        // Shaped like the real inputs, with B and C derived from A each loop.
+        let vm = computer(0, 0, 0, &[2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 5, 5, 0, 3, 3, 0]);
+        assert_eq!(
+            vm.loop_shape(),
+            Ok(LoopShape {
+                shift: 3,
+                outputs: 1
+            })
+        );
+        let a = vm.find_quine().unwrap();
+        assert_eq!(vm.run(a).unwrap(), vm.program());
+        assert!((0..a.min(1 << 16)).all(|x| vm.run(x).unwrap() != vm.program()));
+    }
+
+    #[test]
+    fn test_unsupported_shapes() {
+        let shape = |program: &[u8]| computer(0, 0, 0, program).loop_shape();
+
+        assert!(matches!(
+            shape(&[0, 3, 5, 4]),
+            Err(QuineError::UnsupportedShape(_))
+        ));
+        assert!(matches!(
+            shape(&[0, 5, 5, 4, 3, 0]),
+            Err(QuineError::UnsupportedShape(_))
+        ));
+        assert!(matches!(
+            shape(&[0, 3, 5, 5, 3, 0]),
+            Err(QuineError::UnsupportedShape(_))
+        ));
+        assert_eq!(
+            shape(&[0, 2, 5, 4, 5, 4, 3, 0]),
+            Ok(LoopShape {
+                shift: 2,
+                outputs: 2
+            })
+        );
     }
 }
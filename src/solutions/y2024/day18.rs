@@ -1,5 +1,14 @@
+use crate::params::{ParamSpec, Params};
 use std::collections::{HashSet, VecDeque};
 
+const SIZE: ParamSpec =
+    ParamSpec::new("size", "71", "Width and height of the memory grid").number::<usize>();
+const BYTES: ParamSpec = ParamSpec::new("bytes", "1024", "Number of fallen bytes")
+    .part(1)
+    .number::<usize>();
+
+pub const PARAMS: &[ParamSpec] = &[SIZE, BYTES];
+
 /// Parses the input into a vector of byte positions.
 fn parse_input(input: &str) -> Vec<(usize, usize)> {
     input
@@ -47,12 +56,12 @@ fn bfs_path_exists(grid: &[Vec<bool>], start: (usize, usize), goal: (usize, usiz
     false // No path exists
 }
 
-/// Simulates the corrupted grid and calculates the shortest path.
-fn find_shortest_path(grid_size: usize, bytes: &[(usize, usize)]) -> Option<usize> {
+/// Simulates the corrupted grid after `fallen` bytes and calculates the shortest path.
+fn find_shortest_path(grid_size: usize, fallen: usize, bytes: &[(usize, usize)]) -> Option<usize> {
     let mut grid = vec![vec![false; grid_size]; grid_size];
 
     // Corrupt memory according to the bytes
-    for &(x, y) in bytes.iter().take(1024) {
+    for &(x, y) in bytes.iter().take(fallen) {
         grid[y][x] = true;
     }
 
@@ -104,19 +113,20 @@ fn find_blocking_byte(grid_size: usize, bytes: &[(usize, usize)]) -> Option<(usi
 }
 
 /// Solves Part 1.
-pub fn solve_part1(input: &str) -> String {
-    let grid_size = 71; // Grid size for 0 to 70 inclusive
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    let grid_size = params.value(&SIZE);
+    let fallen = params.value(&BYTES);
     let bytes = parse_input(input);
 
-    match find_shortest_path(grid_size, &bytes) {
+    match find_shortest_path(grid_size, fallen, &bytes) {
         Some(steps) => steps.to_string(),
         None => "No path to the exit".to_string(),
     }
 }
 
 /// Solves Part 2.
-pub fn solve_part2(input: &str) -> String {
-    let grid_size = 71; // Grid size for 0 to 70 inclusive
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    let grid_size = params.value(&SIZE);
     let bytes = parse_input(input);
 
     match find_blocking_byte(grid_size, &bytes) {
@@ -156,7 +166,7 @@ mod tests {
 0,5
 1,6
 2,0";
-        assert_eq!(solve_part1(input), "146");
+        assert_eq!(solve_part1(input, &Params::default()), "146");
     }
 
     #[test]
@@ -186,6 +196,41 @@ mod tests {
 0,5
 1,6
 2,0";
-        assert_eq!(solve_part2(input), "No blocking byte found");
+        assert_eq!(
+            solve_part2(input, &Params::default()),
+            "No blocking byte found"
+        );
+    }
+
+    #[test]
+    fn test_example_dimensions() {
+        let input = r"5,4
+4,2
+4,5
+3,0
+2,1
+6,3
+2,4
+1,5
+0,6
+3,3
+2,6
+5,1
+1,2
+5,5
+2,5
+6,5
+1,4
+0,4
+6,4
+1,1
+6,1
+1,0
+0,5
+1,6
+2,0";
+        let params = Params::new().with("size", 7).with("bytes", 12);
+        assert_eq!(solve_part1(input, &params), "22");
+        assert_eq!(solve_part2(input, &params), "6,1");
     }
 }
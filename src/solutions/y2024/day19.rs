@@ -1,46 +1,42 @@
-use std::collections::{HashMap, HashSet};
+use crate::solutions::y2024::helpers::memo::ArrayMemo;
+use crate::solutions::y2024::helpers::trie::Trie;
 
 /// Parses the input and returns the towel patterns and desired designs.
-fn parse_input(input: &str) -> (HashSet<String>, Vec<String>) {
+fn parse_input(input: &str) -> (Trie, Vec<&str>) {
     let mut sections = input.split("\n\n");
     let towel_patterns = sections
         .next()
         .unwrap()
         .split(", ")
-        .map(|s| s.to_string())
-        .collect();
-    let designs = sections
-        .next()
-        .unwrap()
-        .lines()
-        .map(|line| line.to_string())
+        .map(str::trim)
         .collect();
+    let designs = sections.next().unwrap().lines().collect();
     (towel_patterns, designs)
 }
 
-/// Counts the number of ways to form a design using the available towel patterns.
+/// Counts the number of ways to form `design[start..]` using the available towel patterns.
 fn count_design_ways(
     design: &str,
-    towel_patterns: &HashSet<String>,
-    memo: &mut HashMap<String, usize>,
+    start: usize,
+    towel_patterns: &Trie,
+    memo: &mut ArrayMemo<usize>,
 ) -> usize {
-    if design.is_empty() {
+    if start == design.len() {
         return 1; // Base case: An empty design has 1 way to be formed
     }
 
-    if let Some(&cached) = memo.get(design) {
-        return cached; // Return cached result if available
-    }
-
-    let mut count = 0;
-    for i in 1..=design.len() {
-        if towel_patterns.contains(&design[..i]) {
-            count += count_design_ways(&design[i..], towel_patterns, memo);
-        }
-    }
+    memo.get_or_compute(start, |memo| {
+        towel_patterns
+            .prefix_lengths(&design[start..])
+            .map(|len| count_design_ways(design, start + len, towel_patterns, memo))
+            .sum()
+    })
+}
 
-    memo.insert(design.to_string(), count); // Cache result
-    count
+/// Counts the number of ways to form a whole design.
+fn design_ways(design: &str, towel_patterns: &Trie) -> usize {
+    let mut memo = ArrayMemo::new(design.len());
+    count_design_ways(design, 0, towel_patterns, &mut memo)
 }
 
 /// Solves Part 1.
@@ -49,10 +45,7 @@ pub fn solve_part1(input: &str) -> String {
 
     let count = designs
         .iter()
-        .filter(|design| {
-            let mut memo = HashMap::new();
-            count_design_ways(design, &towel_patterns, &mut memo) > 0
-        })
+        .filter(|design| design_ways(design, &towel_patterns) > 0)
         .count();
 
     count.to_string()
@@ -64,10 +57,7 @@ pub fn solve_part2(input: &str) -> String {
 
     let total_ways: usize = designs
         .iter()
-        .map(|design| {
-            let mut memo = HashMap::new();
-            count_design_ways(design, &towel_patterns, &mut memo)
-        })
+        .map(|design| design_ways(design, &towel_patterns))
         .sum();
 
     total_ways.to_string()
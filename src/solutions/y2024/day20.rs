@@ -1,142 +1,262 @@
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BTreeMap, VecDeque};
 
+use crate::params::{ParamSpec, Params};
 use crate::solutions::y2024::helpers::coord::Coord;
 use crate::solutions::y2024::helpers::grid::Grid;
 
-/// Represents the puzzle state and operations.
-struct Puzzle {
-    racetrack: Grid,                 // The racetrack
-    start: Coord,                    // Start position
-    end: Coord,                      // End position
-    from_start: HashMap<Coord, i32>, // Distance from start
-    to_end: HashMap<Coord, i32>,     // Distance from end
-    boring: i32,                     // Total track length without cheats
-    track: Vec<Coord>,               // Valid track positions
+const CHEAT_PART1: ParamSpec =
+    ParamSpec::new("cheat", "2", "Maximum cheat duration in picoseconds")
+        .part(1)
+        .number::<i32>();
+const CHEAT_PART2: ParamSpec =
+    ParamSpec::new("cheat", "20", "Maximum cheat duration in picoseconds")
+        .part(2)
+        .number::<i32>();
+const MIN_GAIN: ParamSpec =
+    ParamSpec::new("min_gain", "100", "Minimum picoseconds a cheat must save").number::<u32>();
+const SHOW: ParamSpec =
+    ParamSpec::new("show", "count", "What to print: count, histogram or cheats").choices(&[
+        "count",
+        "histogram",
+        "cheats",
+    ]);
+
+pub const PARAMS: &[ParamSpec] = &[CHEAT_PART1, CHEAT_PART2, MIN_GAIN, SHOW];
+
+const UNREACHED: u32 = u32::MAX;
+
+/// A shortcut through the walls, between two track cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cheat {
+    pub start: Coord,
+    pub end: Coord,
+    /// Picoseconds saved over the race without cheating.
+    pub saving: u32,
 }
 
-impl Puzzle {
-    /// Initializes a new Puzzle instance.
-    fn new(grid: Grid, start: Coord, end: Coord) -> Self {
-        let mut puzzle = Self {
-            racetrack: grid,
-            start,
-            end,
-            from_start: HashMap::new(),
-            to_end: HashMap::new(),
-            boring: 0,
-            track: Vec::new(),
+/// The racetrack with the distances from the start and to the end of every
+/// cell, kept in flat arrays indexed row by row.
+pub struct Racetrack {
+    width: i32,
+    height: i32,
+    walls: Vec<bool>,
+    from_start: Vec<u32>,
+    to_end: Vec<u32>,
+    /// Race time without cheating.
+    best: u32,
+}
+
+impl Racetrack {
+    /// # Panics
+    /// if the track has no start or end, or the end cannot be reached
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let grid = Grid::parse(input.trim());
+        let find = |target| {
+            grid.iter()
+                .position(|(_, &c)| c == target)
+                .unwrap_or_else(|| panic!("No '{target}' on the track"))
+        };
+        let (start, end) = (find('S'), find('E'));
+
+        let mut track = Self {
+            width: grid.width(),
+            height: grid.height(),
+            walls: grid.iter().map(|(_, &c)| c == '#').collect(),
+            from_start: Vec::new(),
+            to_end: Vec::new(),
+            best: 0,
         };
-        puzzle.initialize();
-        puzzle
+        track.from_start = track.distances(start);
+        track.to_end = track.distances(end);
+        track.best = track.from_start[end];
+        assert!(track.best != UNREACHED, "The end cannot be reached");
+        track
     }
 
-    /// Configures the puzzle with distances and track positions.
-    fn initialize(&mut self) {
-        self.from_start = self.compute_distances(self.start);
-        self.to_end = self.compute_distances(self.end);
-        self.boring = self.from_start[&self.end];
+    /// Race time without cheating.
+    #[must_use]
+    pub const fn best(&self) -> u32 {
+        self.best
+    }
 
-        self.track = self
-            .racetrack
-            .iter()
-            .filter(|&(pos, _)| self.racetrack[pos] != '#')
-            .map(|(pos, _)| pos)
-            .collect();
+    fn coord(&self, cell: usize) -> Coord {
+        let cell = i32::try_from(cell).unwrap();
+        Coord::new(cell % self.width, cell / self.width)
     }
 
-    /// Computes distances from a given start position to all reachable positions.
-    fn compute_distances(&self, start: Coord) -> HashMap<Coord, i32> {
-        let mut costs = HashMap::new();
-        let mut heap = BinaryHeap::new();
+    fn cell(&self, pos: Coord) -> Option<usize> {
+        ((0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y))
+            .then(|| usize::try_from(pos.y * self.width + pos.x).unwrap())
+    }
 
-        costs.insert(start, 0);
-        heap.push((0, start));
+    /// Breadth-first distances from `source` along the track.
+    fn distances(&self, source: usize) -> Vec<u32> {
+        let mut dist = vec![UNREACHED; self.walls.len()];
+        let mut queue = VecDeque::from([source]);
+        dist[source] = 0;
 
-        while let Some((cost, p)) = heap.pop() {
-            for np in self.racetrack.iter_directions(p) {
-                if self.racetrack[np] != '#' {
-                    let new_cost = cost + 1;
-                    if costs.get(&np).unwrap_or(&i32::MAX) > &new_cost {
-                        costs.insert(np, new_cost);
-                        heap.push((new_cost, np));
+        while let Some(cell) = queue.pop_front() {
+            let pos = self.coord(cell);
+            for d in [Coord::UP, Coord::RIGHT, Coord::DOWN, Coord::LEFT] {
+                if let Some(next) = self.cell(pos + d) {
+                    if !self.walls[next] && dist[next] == UNREACHED {
+                        dist[next] = dist[cell] + 1;
+                        queue.push_back(next);
                     }
                 }
             }
         }
-
-        costs
+        dist
     }
 
-    /// Solves the problem with the given cheat constraints.
-    fn solve(&self, max_cheats: i32, min_gain: i32) -> u32 {
-        let mut nb = 0;
-
-        for cheat_start in &self.track {
-            for cheat_end in &self.track {
-                let cheat_dist = cheat_start.manhattan_distance(cheat_end);
-
-                if cheat_dist <= max_cheats {
-                    let time = match (self.from_start.get(cheat_start), self.to_end.get(cheat_end))
-                    {
-                        (Some(&start_dist), Some(&end_dist)) => start_dist + cheat_dist + end_dist,
-                        _ => continue,
+    /// Calls `f` for every cheat of at most `max_len` picoseconds that saves
+    /// at least `min_gain`, by start cell then end cell in row order.
+    fn for_each_cheat(&self, max_len: i32, min_gain: u32, mut f: impl FnMut(usize, usize, u32)) {
+        let min_gain = min_gain.max(1);
+        for (start, &before) in self.from_start.iter().enumerate() {
+            if before == UNREACHED {
+                continue;
+            }
+            let pos = self.coord(start);
+            for dy in -max_len..=max_len {
+                let reach = max_len - dy.abs();
+                for dx in -reach..=reach {
+                    let Some(end) = self.cell(pos + Coord::new(dx, dy)) else {
+                        continue;
                     };
-
-                    if time + min_gain <= self.boring {
-                        nb += 1;
+                    let after = self.to_end[end];
+                    if after == UNREACHED {
+                        continue;
+                    }
+                    let time = before + dx.unsigned_abs() + dy.unsigned_abs() + after;
+                    if time + min_gain <= self.best {
+                        f(start, end, self.best - time);
                     }
                 }
             }
         }
-        nb
     }
 
-    /// Solves Part 1.
-    pub fn solve_part1(grid: Grid, start: Coord, end: Coord) -> u32 {
-        let puzzle = Puzzle::new(grid, start, end);
-        puzzle.solve(2, 100)
+    /// Counts the cheats of at most `max_len` picoseconds saving at least
+    /// `min_gain`.
+    #[must_use]
+    pub fn count(&self, max_len: i32, min_gain: u32) -> usize {
+        let mut count = 0;
+        self.for_each_cheat(max_len, min_gain, |_, _, _| count += 1);
+        count
     }
 
-    /// Solves Part 2.
-    pub fn solve_part2(grid: Grid, start: Coord, end: Coord) -> u32 {
-        let puzzle = Puzzle::new(grid, start, end);
-        puzzle.solve(20, 100)
+    /// Lists the cheats, by start then end cell in row order.
+    #[must_use]
+    pub fn cheats(&self, max_len: i32, min_gain: u32) -> Vec<Cheat> {
+        let mut cheats = Vec::new();
+        self.for_each_cheat(max_len, min_gain, |start, end, saving| {
+            cheats.push(Cheat {
+                start: self.coord(start),
+                end: self.coord(end),
+                saving,
+            });
+        });
+        cheats
     }
-}
 
-/// Parses input into a Grid and start/end positions.
-fn parse_input(input: &str) -> (Grid, Coord, Coord) {
-    let grid = Grid::parse(input);
-    let mut start = Coord::new(0, 0);
-    let mut end = Coord::new(0, 0);
-
-    for (pos, &c) in grid.iter() {
-        if c == 'S' {
-            start = pos;
-        } else if c == 'E' {
-            end = pos;
-        }
+    /// Number of cheats for each saving.
+    #[must_use]
+    pub fn histogram(&self, max_len: i32, min_gain: u32) -> BTreeMap<u32, usize> {
+        let mut histogram = BTreeMap::new();
+        self.for_each_cheat(max_len, min_gain, |_, _, saving| {
+            *histogram.entry(saving).or_default() += 1;
+        });
+        histogram
     }
+}
 
-    (grid, start, end)
+/// Formats the histogram the way the puzzle describes it.
+fn histogram_lines(histogram: &BTreeMap<u32, usize>) -> String {
+    histogram
+        .iter()
+        .map(|(saving, &count)| match count {
+            1 => format!("There is one cheat that saves {saving} picoseconds."),
+            _ => format!("There are {count} cheats that save {saving} picoseconds."),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn solve(input: &str, params: &Params, cheat: &ParamSpec) -> String {
+    let track = Racetrack::parse(input);
+    let (max_len, min_gain) = (params.value(cheat), params.value(&MIN_GAIN));
+
+    match params.value::<String>(&SHOW).as_str() {
+        "histogram" => histogram_lines(&track.histogram(max_len, min_gain)),
+        "cheats" => track
+            .cheats(max_len, min_gain)
+            .iter()
+            .map(|c| {
+                format!(
+                    "{},{} -> {},{} saves {}",
+                    c.start.x, c.start.y, c.end.x, c.end.y, c.saving
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => track.count(max_len, min_gain).to_string(),
+    }
 }
 
 /// Solves Part 1.
-pub fn solve_part1(input: &str) -> String {
-    let (grid, start, end) = parse_input(input);
-    Puzzle::solve_part1(grid, start, end).to_string()
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    solve(input, params, &CHEAT_PART1)
 }
 
 /// Solves Part 2.
-pub fn solve_part2(input: &str) -> String {
-    let (grid, start, end) = parse_input(input);
-    Puzzle::solve_part2(grid, start, end).to_string()
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    solve(input, params, &CHEAT_PART2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = r"###############
+#...#...#.....#
+#.#.#.#.#.###.#
+#S#...#.#.#...#
+#######.#.#.###
+#######.#.#...#
+#######.#.###.#
+###..E#...#...#
+###.#######.###
+#...###...#...#
+#.#####.#.###.#
+#.#...#.#.#...#
+#.#.#.#.#.#.###
+#...#...#...###
+###############";
+
+    /// A winding track of `n` rows, as a generated larger input.
+    fn serpentine(n: usize) -> String {
+        let width = 2 * n + 1;
+        let mut rows = vec!["#".repeat(width)];
+        for row in 0..n {
+            let mut line: Vec<char> = format!("#{}#", ".".repeat(width - 2)).chars().collect();
+            if row == 0 {
+                line[1] = 'S';
+            }
+            if row == n - 1 {
+                line[if n % 2 == 1 { width - 2 } else { 1 }] = 'E';
+            }
+            rows.push(line.into_iter().collect());
+            let mut wall = vec!['#'; width];
+            wall[if row % 2 == 0 { width - 2 } else { 1 }] = '.';
+            rows.push(wall.into_iter().collect());
+        }
+        rows.pop();
+        rows.push("#".repeat(width));
+        rows.join("\n")
+    }
+
     #[test]
     fn test_part1() {
         let input = r"
@@ -147,7 +267,7 @@ mod tests {
 ###############
 ";
 
-        assert_eq!(solve_part1(input), "0");
+        assert_eq!(solve_part1(input, &Params::default()), "0");
     }
 
     #[test]
@@ -160,6 +280,94 @@ mod tests {
 ###############
 ";
 
-        assert_eq!(solve_part2(input), "0");
+        assert_eq!(solve_part2(input, &Params::default()), "0");
+    }
+
+    #[test]
+    fn test_example_params() {
+        let params = Params::new().with("min_gain", 20);
+        assert_eq!(solve_part1(EXAMPLE, &params), "5");
+        let params = Params::new().with("min_gain", 76);
+        assert_eq!(solve_part2(EXAMPLE, &params), "3");
+        let params = Params::new().with("cheat", 20).with("min_gain", 76);
+        assert_eq!(solve_part1(EXAMPLE, &params), "3");
+    }
+
+    #[test]
+    fn test_histogram() {
+        let track = Racetrack::parse(EXAMPLE);
+        assert_eq!(track.best(), 84);
+
+        let histogram: Vec<_> = track.histogram(2, 1).into_iter().collect();
+        assert_eq!(
+            histogram,
+            vec![
+                (2, 14),
+                (4, 14),
+                (6, 2),
+                (8, 4),
+                (10, 2),
+                (12, 3),
+                (20, 1),
+                (36, 1),
+                (38, 1),
+                (40, 1),
+                (64, 1)
+            ]
+        );
+
+        let histogram = track.histogram(20, 50);
+        assert_eq!(histogram[&50], 32);
+        assert_eq!(histogram[&76], 3);
+        assert_eq!(histogram.values().sum::<usize>(), 285);
+
+        let params = Params::new().with("min_gain", 40).with("show", "histogram");
+        assert_eq!(
+            solve_part1(EXAMPLE, &params),
+            "There is one cheat that saves 40 picoseconds.\n\
+             There is one cheat that saves 64 picoseconds."
+        );
+    }
+
+    #[test]
+    fn test_cheats() {
+        let track = Racetrack::parse(EXAMPLE);
+        assert_eq!(
+            track.cheats(2, 64),
+            vec![Cheat {
+                start: Coord::new(7, 7),
+                end: Coord::new(5, 7),
+                saving: 64,
+            }]
+        );
+
+        let params = Params::new().with("min_gain", 64).with("show", "cheats");
+        assert_eq!(solve_part1(EXAMPLE, &params), "7,7 -> 5,7 saves 64");
+    }
+
+    #[test]
+    fn test_generated_track() {
+        let track = Racetrack::parse(&serpentine(8));
+        assert_eq!(track.best(), 8 * 16 - 2);
+
+        // Pairwise check of every two track cells.
+        let cells: Vec<_> = (0..track.walls.len())
+            .filter(|&c| track.from_start[c] != UNREACHED)
+            .collect();
+        let naive = cells
+            .iter()
+            .flat_map(|&s| cells.iter().map(move |&e| (s, e)))
+            .filter(|&(s, e)| {
+                let d = track
+                    .coord(s)
+                    .manhattan_distance(&track.coord(e))
+                    .unsigned_abs();
+                d <= 6 && track.from_start[s] + d + track.to_end[e] + 10 <= track.best
+            })
+            .count();
+        assert_eq!(track.count(6, 10), naive);
+
+        let large = Racetrack::parse(&serpentine(100));
+        assert!(large.count(20, 100) > 0);
     }
 }
@@ -1,204 +1,474 @@
-use itertools::Itertools;
+use crate::params::{ParamSpec, Params};
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
-type ButtonSequences = HashMap<(char, char), Vec<String>>;
+const ROBOTS_PART1: ParamSpec =
+    ParamSpec::new("robots", "2", "Number of robots on directional keypads")
+        .part(1)
+        .number::<usize>();
+const ROBOTS_PART2: ParamSpec =
+    ParamSpec::new("robots", "25", "Number of robots on directional keypads")
+        .part(2)
+        .number::<usize>();
+const KEYPAD: ParamSpec = ParamSpec::new(
+    "keypad",
+    "789/456/123/ 0A",
+    "Door keypad rows separated by '/', gaps as spaces",
+);
+const CONTROLS: ParamSpec = ParamSpec::new(
+    "controls",
+    " ^A/<v>",
+    "Directional keypad rows separated by '/', gaps as spaces",
+);
+const SHOW: ParamSpec = ParamSpec::new(
+    "show",
+    "complexity",
+    "What to print: complexity or sequences (one line per layer)",
+)
+.choices(&["complexity", "sequences"]);
 
-/// Compute sequences for moving between buttons on the keypad.
-fn compute_sequences(keypad: &[&str]) -> ButtonSequences {
-    let mut positions = HashMap::new();
-    let size_x = i32::try_from(keypad[0].len()).unwrap();
-    let size_y = i32::try_from(keypad.len()).unwrap();
+pub const PARAMS: &[ParamSpec] = &[ROBOTS_PART1, ROBOTS_PART2, KEYPAD, CONTROLS, SHOW];
 
-    // Store positions of buttons
-    for (y, row) in keypad.iter().enumerate() {
-        for (x, button) in row.chars().enumerate() {
-            if button != ' ' {
-                positions.insert(button, (x as i32, y as i32));
-            }
-        }
-    }
+/// Longest sequence `Chain::sequences` builds, costs grow exponentially with robots.
+const SEQUENCE_LIMIT: u64 = 1_000_000;
 
-    let mut sequences: ButtonSequences = HashMap::new();
-    for &from_button in positions.keys() {
-        for &to_button in positions.keys() {
-            if from_button == to_button {
-                sequences.insert((from_button, to_button), vec!["A".to_string()]);
-                continue;
-            }
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeypadError {
+    Layout(String),
+    UnknownKey(char),
+    /// No path between the two keys on the door keypad.
+    Unreachable(char, char),
+    /// The sequence would be longer than `SEQUENCE_LIMIT`.
+    TooLong(u64),
+    /// The press at this index points a robot arm at a gap or off the keypad.
+    Gap(usize),
+}
 
-            let mut possibilities = Vec::new();
-            let mut queue = VecDeque::new();
-            let mut shortest = usize::MAX;
-            let mut visited = HashMap::new();
+impl fmt::Display for KeypadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Layout(reason) => write!(f, "invalid keypad: {reason}"),
+            Self::UnknownKey(key) => write!(f, "unknown key '{key}'"),
+            Self::Unreachable(from, to) => write!(f, "no path from '{from}' to '{to}'"),
+            Self::TooLong(len) => write!(f, "sequence of {len} presses is too long to build"),
+            Self::Gap(step) => write!(f, "press {step} points a robot at a gap"),
+        }
+    }
+}
 
-            queue.push_front((positions[&from_button], String::new()));
-            visited.insert(positions[&from_button], 0);
+/// A rectangular keypad whose gaps robot arms must never point at.
+#[derive(Clone, Debug)]
+pub struct Keypad {
+    keys: Vec<char>,
+    positions: Vec<(i32, i32)>,
+    index: HashMap<char, usize>,
+    /// All shortest move sequences between two keys, each ending with `A`.
+    paths: Vec<Vec<Vec<String>>>,
+}
 
-            while let Some(((x, y), moves)) = queue.pop_back() {
-                if (x, y) == positions[&to_button] {
-                    if moves.len() < shortest {
-                        shortest = moves.len();
-                        possibilities.clear();
-                    }
+impl Keypad {
+    /// Parses rows separated by `/`, with spaces as gaps. Short rows are padded
+    /// with gaps and the keypad needs an `A` key, where every arm starts.
+    ///
+    /// # Errors
+    /// if a key repeats or there is no `A` key
+    pub fn parse(layout: &str) -> Result<Self, KeypadError> {
+        let rows: Vec<Vec<char>> = layout.split('/').map(|row| row.chars().collect()).collect();
+        let mut keys = Vec::new();
+        let mut positions = Vec::new();
+        let mut index = HashMap::new();
 
-                    if moves.len() == shortest {
-                        possibilities.push(format!("{moves}A"));
-                    }
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &key) in row.iter().enumerate() {
+                if key == ' ' {
                     continue;
                 }
+                if index.insert(key, keys.len()).is_some() {
+                    return Err(KeypadError::Layout(format!("key '{key}' repeats")));
+                }
+                keys.push(key);
+                positions.push((x as i32, y as i32));
+            }
+        }
+        if !index.contains_key(&'A') {
+            return Err(KeypadError::Layout("no 'A' key".to_string()));
+        }
 
-                for (nx, ny, nm) in [
-                    (x - 1, y, '<'),
-                    (x + 1, y, '>'),
-                    (x, y - 1, '^'),
-                    (x, y + 1, 'v'),
-                ] {
-                    if nx < 0 || nx >= size_x || ny < 0 || ny >= size_y {
-                        continue;
-                    }
+        let mut keypad = Self {
+            keys,
+            positions,
+            index,
+            paths: Vec::new(),
+        };
+        keypad.paths = (0..keypad.keys.len())
+            .map(|from| {
+                (0..keypad.keys.len())
+                    .map(|to| keypad.shortest_paths(from, to))
+                    .collect()
+            })
+            .collect();
+        Ok(keypad)
+    }
 
-                    let button = keypad[ny as usize].chars().nth(nx as usize).unwrap();
-                    if button == ' ' {
-                        continue;
-                    }
+    fn key_at(&self, pos: (i32, i32)) -> Option<usize> {
+        self.positions.iter().position(|&p| p == pos)
+    }
 
-                    if *visited.get(&(nx, ny)).unwrap_or(&usize::MAX) >= moves.len() {
-                        queue.push_front(((nx, ny), format!("{moves}{nm}")));
-                        visited.insert((nx, ny), moves.len());
-                    }
+    fn key(&self, key: char) -> Result<usize, KeypadError> {
+        self.index
+            .get(&key)
+            .copied()
+            .ok_or(KeypadError::UnknownKey(key))
+    }
+
+    fn distances(&self, from: usize) -> Vec<Option<usize>> {
+        let mut dist = vec![None; self.keys.len()];
+        let mut queue = VecDeque::from([from]);
+        dist[from] = Some(0);
+        while let Some(key) = queue.pop_front() {
+            let (x, y) = self.positions[key];
+            for (next, _) in self.neighbours(x, y) {
+                if dist[next].is_none() {
+                    dist[next] = dist[key].map(|d| d + 1);
+                    queue.push_back(next);
                 }
             }
-
-            sequences.insert((from_button, to_button), possibilities);
         }
+        dist
+    }
+
+    fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (usize, char)> + '_ {
+        [
+            (x - 1, y, '<'),
+            (x + 1, y, '>'),
+            (x, y - 1, '^'),
+            (x, y + 1, 'v'),
+        ]
+        .into_iter()
+        .filter_map(|(nx, ny, m)| self.key_at((nx, ny)).map(|key| (key, m)))
     }
 
-    sequences
+    /// Enumerates the moves through keys that lie on some shortest path.
+    fn shortest_paths(&self, from: usize, to: usize) -> Vec<String> {
+        fn extend(
+            keypad: &Keypad,
+            key: usize,
+            to: usize,
+            to_dist: &[Option<usize>],
+            moves: &mut String,
+            paths: &mut Vec<String>,
+        ) {
+            if key == to {
+                paths.push(format!("{moves}A"));
+                return;
+            }
+            let (x, y) = keypad.positions[key];
+            for (next, m) in keypad.neighbours(x, y) {
+                if to_dist[next].is_some_and(|d| Some(d + 1) == to_dist[key]) {
+                    moves.push(m);
+                    extend(keypad, next, to, to_dist, moves, paths);
+                    moves.pop();
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        let to_dist = self.distances(to);
+        extend(self, from, to, &to_dist, &mut String::new(), &mut paths);
+        paths
+    }
 }
 
-struct Solver {
-    numerical_sequences: ButtonSequences,
-    directional_sequences: ButtonSequences,
+/// A door keypad operated through a chain of directional keypads, the last
+/// of which is pressed by a human.
+pub struct Chain {
+    pads: Vec<Keypad>,
+    /// Human presses to move from one key to another and press it, per pad.
+    costs: Vec<Vec<Vec<u64>>>,
+    /// Index of the cheapest path between two keys, per pad.
+    choices: Vec<Vec<Vec<usize>>>,
 }
 
-impl Solver {
-    fn new() -> Self {
-        let numerical_keypad = ["789", "456", "123", " 0A"];
-        let directional_keypad = [" ^A", "<v>"];
-        Self {
-            numerical_sequences: compute_sequences(&numerical_keypad),
-            directional_sequences: compute_sequences(&directional_keypad),
+impl Chain {
+    /// Builds the chain for `robots` robots on directional keypads between
+    /// the robot at the door and the human.
+    ///
+    /// # Errors
+    /// if the control keypad lacks one of the `<>^vA` keys
+    pub fn new(door: Keypad, controls: Keypad, robots: usize) -> Result<Self, KeypadError> {
+        for key in ['<', '>', '^', 'v', 'A'] {
+            if !controls.index.contains_key(&key) {
+                return Err(KeypadError::Layout(format!(
+                    "control keypad has no '{key}'"
+                )));
+            }
         }
-    }
 
-    fn find_code_seqs(&self, code: &str) -> Vec<String> {
-        let mut sequences = Vec::new();
-        for i in 0..code.len() {
-            let button_from = if i == 0 {
-                'A'
-            } else {
-                code.chars().nth(i - 1).unwrap()
-            };
-            let button_to = code.chars().nth(i).unwrap();
-            sequences.push(self.numerical_sequences[&(button_from, button_to)].clone());
+        let mut pads = vec![door];
+        pads.extend(std::iter::repeat_n(controls, robots + 1));
+
+        let human = pads.last().unwrap().keys.len();
+        let mut costs = vec![vec![vec![1; human]; human]];
+        let mut choices = vec![vec![vec![0; human]; human]];
+        for pad in pads.iter().rev().skip(1) {
+            let (cost, choice) = Self::cheapest(pad, &pads[1], costs.last().unwrap());
+            costs.push(cost);
+            choices.push(choice);
         }
-        sequences
+        costs.reverse();
+        choices.reverse();
+
+        Ok(Self {
+            pads,
+            costs,
+            choices,
+        })
+    }
+
+    /// Picks the cheapest path between every pair of keys on `pad`, given the
+    /// costs of the control keypad that drives it.
+    fn cheapest(
+        pad: &Keypad,
+        controls: &Keypad,
+        next: &[Vec<u64>],
+    ) -> (Vec<Vec<u64>>, Vec<Vec<usize>>) {
+        let path_cost = |path: &str| {
+            let mut from = controls.index[&'A'];
+            path.chars()
+                .map(|key| {
+                    let to = controls.index[&key];
+                    let cost = next[from][to];
+                    from = to;
+                    cost
+                })
+                .fold(0_u64, u64::saturating_add)
+        };
+
+        pad.paths
             .iter()
-            .multi_cartesian_product()
-            .map(|steps| steps.iter().join(""))
-            .collect()
-    }
-
-    fn compute_seq_length(
-        &self,
-        target_seq: &str,
-        robots: u32,
-        cache: &mut HashMap<(String, u32), u64>,
-    ) -> u64 {
-        if let Some(&cached) = cache.get(&(target_seq.to_string(), robots)) {
-            return cached;
+            .map(|row| {
+                row.iter()
+                    .map(|paths| {
+                        paths
+                            .iter()
+                            .enumerate()
+                            .map(|(i, path)| (path_cost(path), i))
+                            .min()
+                            .unwrap_or((u64::MAX, 0))
+                    })
+                    .unzip()
+            })
+            .unzip()
+    }
+
+    fn steps<'a>(
+        &'a self,
+        pad: usize,
+        keys: &'a str,
+    ) -> impl Iterator<Item = Result<(usize, usize), KeypadError>> + 'a {
+        let keypad = &self.pads[pad];
+        let mut from = keypad.index[&'A'];
+        keys.chars().map(move |key| {
+            let to = keypad.key(key)?;
+            if keypad.paths[from][to].is_empty() {
+                return Err(KeypadError::Unreachable(keypad.keys[from], key));
+            }
+            Ok((std::mem::replace(&mut from, to), to))
+        })
+    }
+
+    /// Returns the fewest human presses that make the door robot type `code`.
+    ///
+    /// # Errors
+    /// if the code has keys missing from the door keypad or unreachable
+    pub fn cost(&self, code: &str) -> Result<u64, KeypadError> {
+        self.steps(0, code).try_fold(0_u64, |total, step| {
+            let (from, to) = step?;
+            Ok(total.saturating_add(self.costs[0][from][to]))
+        })
+    }
+
+    /// Returns an optimal sequence for every keypad, from the code typed on
+    /// the door keypad to the presses of the human.
+    ///
+    /// # Errors
+    /// if the code cannot be typed or the human sequence would be too long
+    pub fn sequences(&self, code: &str) -> Result<Vec<String>, KeypadError> {
+        let cost = self.cost(code)?;
+        if cost > SEQUENCE_LIMIT {
+            return Err(KeypadError::TooLong(cost));
         }
 
-        if robots <= 1 {
-            let result: u64 = (0..target_seq.len())
-                .map(|i| {
-                    let from = if i == 0 {
-                        'A'
-                    } else {
-                        target_seq.chars().nth(i - 1).unwrap()
-                    };
-                    let to = target_seq.chars().nth(i).unwrap();
-                    self.directional_sequences[&(from, to)][0].len() as u64
+        let mut layers = vec![code.to_string()];
+        for pad in 0..self.pads.len() - 1 {
+            let layer = self
+                .steps(pad, layers.last().unwrap())
+                .map(|step| {
+                    let (from, to) = step?;
+                    Ok(self.pads[pad].paths[from][to][self.choices[pad][from][to]].as_str())
                 })
-                .sum();
-            cache.insert((target_seq.to_string(), robots), result);
-            return result;
+                .collect::<Result<String, KeypadError>>()?;
+            layers.push(layer);
         }
+        Ok(layers)
+    }
 
-        let result: u64 = (0..target_seq.len())
-            .map(|i| {
-                let from = if i == 0 {
-                    'A'
-                } else {
-                    target_seq.chars().nth(i - 1).unwrap()
-                };
-                let to = target_seq.chars().nth(i).unwrap();
-                self.directional_sequences[&(from, to)]
-                    .iter()
-                    .map(|seq| self.compute_seq_length(seq, robots - 1, cache))
-                    .min()
-                    .unwrap()
-            })
-            .sum();
+    /// Replays human presses through the chain and returns what the door
+    /// robot typed.
+    ///
+    /// # Errors
+    /// if a key is unknown or a robot arm is pointed at a gap
+    pub fn replay(&self, presses: &str) -> Result<String, KeypadError> {
+        let human = self.pads.len() - 1;
+        let mut arms: Vec<usize> = self.pads.iter().map(|pad| pad.index[&'A']).collect();
+        let mut typed = String::new();
 
-        cache.insert((target_seq.to_string(), robots), result);
-        result
+        for (step, key) in presses.chars().enumerate() {
+            // Follow the press down the chain while it is an `A`.
+            let mut pad = human;
+            let mut key = self.pads[pad].key(key)?;
+            loop {
+                if pad == 0 {
+                    typed.push(self.pads[0].keys[key]);
+                    break;
+                }
+                let command = self.pads[pad].keys[key];
+                pad -= 1;
+                let (x, y) = self.pads[pad].positions[arms[pad]];
+                let target = match command {
+                    'A' => {
+                        key = arms[pad];
+                        continue;
+                    }
+                    '<' => (x - 1, y),
+                    '>' => (x + 1, y),
+                    '^' => (x, y - 1),
+                    _ => (x, y + 1),
+                };
+                arms[pad] = self.pads[pad]
+                    .key_at(target)
+                    .ok_or(KeypadError::Gap(step))?;
+                break;
+            }
+        }
+        Ok(typed)
     }
+}
 
-    fn complexity(&self, code: &str, robots: u32) -> u64 {
-        let seqs = self.find_code_seqs(code);
-        let mut cache = HashMap::new();
-        let min_length = seqs
-            .iter()
-            .map(|seq| self.compute_seq_length(seq, robots, &mut cache))
-            .min()
-            .unwrap();
-        let num_code = code
-            .chars()
-            .map_while(|c| c.to_digit(10))
-            .fold(0, |acc, d| acc * 10 + d);
-        min_length * u64::from(num_code)
-    }
+fn complexity(chain: &Chain, code: &str) -> Result<u64, KeypadError> {
+    let num_code = code
+        .chars()
+        .map_while(|c| c.to_digit(10))
+        .fold(0, |acc, d| acc * 10 + d);
+    Ok(chain.cost(code)? * u64::from(num_code))
 }
 
-pub fn solve_part1(input: &str) -> String {
-    let codes: Vec<String> = input.lines().map(|line| line.to_string()).collect();
-    let solver = Solver::new();
+fn solve(input: &str, params: &Params, robots: &ParamSpec) -> Result<String, KeypadError> {
+    let door = Keypad::parse(&params.value::<String>(&KEYPAD))?;
+    let controls = Keypad::parse(&params.value::<String>(&CONTROLS))?;
+    let chain = Chain::new(door, controls, params.value(robots))?;
+    let codes = input.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    if params.value::<String>(&SHOW) == "sequences" {
+        let mut out = Vec::new();
+        for code in codes {
+            out.extend(chain.sequences(code)?);
+        }
+        return Ok(out.join("\n"));
+    }
+
     codes
-        .iter()
-        .map(|code| solver.complexity(code, 2))
-        .sum::<u64>()
-        .to_string()
+        .map(|code| complexity(&chain, code))
+        .sum::<Result<u64, _>>()
+        .map(|total| total.to_string())
 }
 
-pub fn solve_part2(input: &str) -> String {
-    let codes: Vec<String> = input.lines().map(|line| line.to_string()).collect();
-    let solver = Solver::new();
-    codes
-        .iter()
-        .map(|code| solver.complexity(code, 25))
-        .sum::<u64>()
-        .to_string()
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    solve(input, params, &ROBOTS_PART1).unwrap_or_else(|e| e.to_string())
+}
+
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    solve(input, params, &ROBOTS_PART2).unwrap_or_else(|e| e.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn chain(robots: usize) -> Chain {
+        let door = Keypad::parse("789/456/123/ 0A").unwrap();
+        let controls = Keypad::parse(" ^A/<v>").unwrap();
+        Chain::new(door, controls, robots).unwrap()
+    }
+
     #[test]
     fn test_example() {
         let input = "029A\n980A\n";
-        assert_eq!(solve_part1(input), "60772");
-        assert_eq!(solve_part2(input), "73176637651790");
+        assert_eq!(solve_part1(input, &Params::default()), "60772");
+        assert_eq!(solve_part2(input, &Params::default()), "73176637651790");
+    }
+
+    #[test]
+    fn test_robots_param() {
+        let input = "029A\n";
+        let params = Params::new().with("robots", 0);
+        assert_eq!(solve_part1(input, &params), (12 * 29).to_string());
+        let params = Params::new().with("robots", 1);
+        assert_eq!(solve_part1(input, &params), (28 * 29).to_string());
+    }
+
+    #[test]
+    fn test_sequences() {
+        let chain = chain(2);
+        let layers = chain.sequences("029A").unwrap();
+
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[0], "029A");
+        assert_eq!(layers[1].len(), 12);
+        assert_eq!(layers[3].len(), 68);
+        for (pad, pair) in layers.windows(2).enumerate() {
+            let sub = Chain {
+                pads: chain.pads[pad..pad + 2].to_vec(),
+                costs: Vec::new(),
+                choices: Vec::new(),
+            };
+            assert_eq!(sub.replay(&pair[1]), Ok(pair[0].clone()));
+        }
+        assert_eq!(chain.replay(&layers[3]), Ok("029A".to_string()));
+    }
+
+    #[test]
+    fn test_replay() {
+        let chain = chain(2);
+        let example = "<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A";
+        assert_eq!(chain.replay(example), Ok("029A".to_string()));
+        // The second `<` points the arm next to the human at the top-left gap.
+        assert_eq!(chain.replay("<<"), Err(KeypadError::Gap(1)));
+        assert_eq!(chain.replay("x"), Err(KeypadError::UnknownKey('x')));
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        let door = Keypad::parse("12/ 3/A4").unwrap();
+        let controls = Keypad::parse(" ^A/<v>").unwrap();
+        let chain = Chain::new(door, controls, 0).unwrap();
+
+        assert_eq!(
+            chain.sequences("1"),
+            Ok(vec!["1".to_string(), ">^^<A".to_string()])
+        );
+        assert!(chain.sequences("21").is_ok());
+        assert_eq!(
+            chain.replay(&chain.sequences("3A").unwrap()[1]),
+            Ok("3A".to_string())
+        );
+        assert_eq!(
+            Keypad::parse("12/1A").unwrap_err(),
+            KeypadError::Layout("key '1' repeats".to_string())
+        );
+        assert!(matches!(
+            chain.sequences("0"),
+            Err(KeypadError::UnknownKey('0'))
+        ));
     }
 }
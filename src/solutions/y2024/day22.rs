@@ -1,83 +1,220 @@
-use std::collections::{HashMap, HashSet};
+use crate::params::{ParamSpec, Params};
+use std::thread;
+
+const ITERATIONS: ParamSpec = ParamSpec::new(
+    "iterations",
+    "2000",
+    "Number of new secrets generated per buyer",
+)
+.number::<usize>();
+const THREADS: ParamSpec = ParamSpec::new(
+    "threads",
+    "0",
+    "Worker threads for part 2, 0 for one per CPU",
+)
+.number::<usize>();
+
+pub const PARAMS: &[ParamSpec] = &[ITERATIONS, THREADS];
+
+/// Number of possible windows of four price changes, each in `-9..=9`.
+const WINDOWS: usize = 19 * 19 * 19 * 19;
 
 /// Computes the next secret value in the sequence.
-const fn next_secret(secret: i64) -> i64 {
-    let secret = (secret ^ (secret * 64)) % 16_777_216;
-    let secret = (secret ^ (secret / 32)) % 16_777_216;
-    (secret ^ (secret * 2048)) % 16_777_216
+const fn next_secret(secret: u64) -> u64 {
+    let secret = (secret ^ (secret << 6)) & 0xFF_FFFF;
+    let secret = (secret ^ (secret >> 5)) & 0xFF_FFFF;
+    (secret ^ (secret << 11)) & 0xFF_FFFF
 }
 
-/// Represents the puzzle with its initial secrets.
-pub struct Puzzle {
-    initial_secrets: Vec<i64>,
+/// The secrets generated after an initial secret, endlessly.
+#[derive(Clone, Debug)]
+pub struct Secrets {
+    secret: u64,
 }
 
-impl Puzzle {
-    /// Creates a new Puzzle instance.
-    pub fn new() -> Self {
-        Self {
-            initial_secrets: Vec::new(),
+impl Iterator for Secrets {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.secret = next_secret(self.secret);
+        Some(self.secret)
+    }
+}
+
+/// Returns the prices of a buyer: the last digit of the initial secret and of
+/// the `iterations` secrets that follow.
+pub fn prices(initial: u64, iterations: usize) -> impl Iterator<Item = u8> {
+    std::iter::once(initial)
+        .chain(secrets(initial).take(iterations))
+        .map(|secret| (secret % 10) as u8)
+}
+
+#[must_use]
+pub const fn secrets(initial: u64) -> Secrets {
+    Secrets { secret: initial }
+}
+
+/// Packs four price changes into an index below `WINDOWS`.
+#[must_use]
+pub fn window_index(changes: [i8; 4]) -> usize {
+    changes
+        .iter()
+        .fold(0, |index, &change| index * 19 + (change + 9) as usize)
+}
+
+/// Unpacks the four price changes of a window index.
+#[must_use]
+pub fn window_changes(mut index: usize) -> [i8; 4] {
+    let mut changes = [0; 4];
+    for change in changes.iter_mut().rev() {
+        *change = (index % 19) as i8 - 9;
+        index /= 19;
+    }
+    changes
+}
+
+/// Calls `f` with each window index of a buyer and the price it sells at.
+fn for_each_window(initial: u64, iterations: usize, mut f: impl FnMut(usize, u8)) {
+    let mut index = 0;
+    let mut previous = None;
+    for (i, price) in prices(initial, iterations).enumerate() {
+        if let Some(previous) = previous {
+            // Drop the oldest change by keeping the index modulo 19^3.
+            index = (index % (19 * 19 * 19)) * 19 + usize::from(9 + price - previous);
+            if i >= 4 {
+                f(index, price);
+            }
         }
+        previous = Some(price);
     }
+}
 
-    /// Configures the puzzle with input data.
-    pub fn configure(&mut self, input: &str) {
-        self.initial_secrets
-            .extend(input.lines().map_while(|s| s.parse::<i64>().ok()));
+/// The change sequence that earns the most bananas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Best {
+    pub changes: [i8; 4],
+    pub bananas: u64,
+    /// Price each buyer sells at, or `None` if the sequence never shows up.
+    pub prices: Vec<Option<u8>>,
+}
+
+/// The buyers of the monkey market with their initial secrets.
+pub struct Market {
+    buyers: Vec<u64>,
+    iterations: usize,
+}
+
+impl Market {
+    #[must_use]
+    pub fn parse(input: &str, iterations: usize) -> Self {
+        Self {
+            buyers: input.lines().map_while(|s| s.trim().parse().ok()).collect(),
+            iterations,
+        }
     }
 
-    /// Solves part one of the puzzle.
-    pub fn solve_part1(&self) -> i64 {
-        self.initial_secrets
+    /// Sums the last secret of every buyer.
+    #[must_use]
+    pub fn secret_sum(&self) -> u64 {
+        self.buyers
             .iter()
-            .map(|&initial_secret| (0..2000).fold(initial_secret, |secret, _| next_secret(secret)))
+            .map(|&initial| {
+                secrets(initial)
+                    .take(self.iterations)
+                    .last()
+                    .unwrap_or(initial)
+            })
             .sum()
     }
 
-    /// Solves part two of the puzzle.
-    pub fn solve_part2(&self) -> i64 {
-        let mut bananas = HashMap::new();
+    /// Bananas earned for every window, summed over `buyers`.
+    fn totals(&self, buyers: &[u64]) -> Vec<u32> {
+        let mut totals = vec![0; WINDOWS];
+        // Marks the windows already sold at, with the 1-based buyer number.
+        let mut seen = vec![0_u32; WINDOWS];
+        for (buyer, &initial) in (1..).zip(buyers) {
+            for_each_window(initial, self.iterations, |index, price| {
+                if seen[index] != buyer {
+                    seen[index] = buyer;
+                    totals[index] += u32::from(price);
+                }
+            });
+        }
+        totals
+    }
 
-        for &initial_secret in &self.initial_secrets {
-            let mut prices = Vec::new();
+    /// Finds the best change sequence, splitting the buyers over `threads`
+    /// workers that each keep their own totals.
+    #[must_use]
+    pub fn best(&self, threads: usize) -> Option<Best> {
+        let threads = threads.clamp(1, self.buyers.len().max(1));
+        let chunk = self.buyers.len().div_ceil(threads).max(1);
 
-            let mut secret = initial_secret;
-            prices.push(secret % 10);
-            for _ in 0..2000 {
-                secret = next_secret(secret);
-                prices.push(secret % 10);
-            }
+        let totals = thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .buyers
+                .chunks(chunk)
+                .map(|buyers| scope.spawn(move || self.totals(buyers)))
+                .collect();
 
-            let mut seen = HashSet::new();
-            for p in prices.windows(5) {
-                let sequence = [p[1] - p[0], p[2] - p[1], p[3] - p[2], p[4] - p[3]];
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .reduce(|mut sum, totals| {
+                    sum.iter_mut().zip(totals).for_each(|(s, t)| *s += t);
+                    sum
+                })
+        })?;
 
-                if seen.insert(sequence) {
-                    *bananas.entry(sequence).or_default() += p[4];
-                }
-            }
-        }
+        let (index, &bananas) = totals
+            .iter()
+            .enumerate()
+            .max_by_key(|&(index, &total)| (total, std::cmp::Reverse(index)))?;
+        let changes = window_changes(index);
+        Some(Best {
+            changes,
+            bananas: u64::from(bananas),
+            prices: self.sale_prices(changes),
+        })
+    }
 
-        *bananas.values().max().unwrap()
+    /// Returns the price each buyer sells at when the monkey waits for `changes`.
+    #[must_use]
+    pub fn sale_prices(&self, changes: [i8; 4]) -> Vec<Option<u8>> {
+        let target = window_index(changes);
+        self.buyers
+            .iter()
+            .map(|&initial| {
+                let mut sold = None;
+                for_each_window(initial, self.iterations, |index, price| {
+                    if index == target && sold.is_none() {
+                        sold = Some(price);
+                    }
+                });
+                sold
+            })
+            .collect()
     }
 }
 
-impl Default for Puzzle {
-    fn default() -> Self {
-        Self::new()
+fn threads(params: &Params) -> usize {
+    match params.value(&THREADS) {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        n => n,
     }
 }
 
-pub fn solve_part1(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
-    puzzle.configure(input);
-    puzzle.solve_part1().to_string()
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    Market::parse(input, params.value(&ITERATIONS))
+        .secret_sum()
+        .to_string()
 }
 
-pub fn solve_part2(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
-    puzzle.configure(input);
-    puzzle.solve_part2().to_string()
+pub fn solve_part2(input: &str, params: &Params) -> String {
+    Market::parse(input, params.value(&ITERATIONS))
+        .best(threads(params))
+        .map_or(0, |best| best.bananas)
+        .to_string()
 }
 
 #[cfg(test)]
@@ -87,12 +224,47 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "123456\n654321\n";
-        assert_eq!(solve_part1(input), "12178264");
+        assert_eq!(solve_part1(input, &Params::default()), "12178264");
     }
 
     #[test]
     fn test_part2() {
         let input = "123456\n654321\n";
-        assert_eq!(solve_part2(input), "18");
+        assert_eq!(solve_part2(input, &Params::default()), "18");
+    }
+
+    #[test]
+    fn test_iterations_param() {
+        let params = Params::new().with("iterations", 10);
+        assert_eq!(solve_part1("123\n", &params), "5908254");
+    }
+
+    #[test]
+    fn test_secrets() {
+        let first: Vec<_> = secrets(123).take(3).collect();
+        assert_eq!(first, vec![15_887_950, 16_495_136, 527_345]);
+
+        let prices: Vec<_> = prices(123, 9).collect();
+        assert_eq!(prices, vec![3, 0, 6, 5, 4, 4, 6, 4, 4, 2]);
+    }
+
+    #[test]
+    fn test_window_index() {
+        for changes in [[-9, -9, -9, -9], [9, 9, 9, 9], [-2, 1, -1, 3], [0, 0, 0, 0]] {
+            assert_eq!(window_changes(window_index(changes)), changes);
+        }
+        assert_eq!(window_index([9, 9, 9, 9]), WINDOWS - 1);
+    }
+
+    #[test]
+    fn test_best_sequence() {
+        let market = Market::parse("1\n2\n3\n2024\n", 2000);
+
+        for threads in [1, 3] {
+            let best = market.best(threads).unwrap();
+            assert_eq!(best.changes, [-2, 1, -1, 3]);
+            assert_eq!(best.bananas, 23);
+            assert_eq!(best.prices, vec![Some(7), Some(7), None, Some(9)]);
+        }
     }
 }
@@ -1,4 +1,21 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::rng::SplitMix64;
+
+const SHOW: ParamSpec = ParamSpec::new("show", "output", "What to print: output or dot")
+    .part(1)
+    .choices(&["output", "dot"]);
+const CHECK: ParamSpec = ParamSpec::new(
+    "check",
+    "false",
+    "Pair the swapped wires and prove the result is an adder",
+)
+.part(2)
+.boolean();
+
+pub const PARAMS: &[ParamSpec] = &[SHOW, CHECK];
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 enum Role {
@@ -9,7 +26,7 @@ enum Role {
     SumOut,       // the S wire
 }
 
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 enum Operation {
     And,
     Or,
@@ -87,38 +104,6 @@ impl Puzzle {
         }
     }
 
-    /// Solve part one.
-    fn solve_part1(&self) -> String {
-        let mut waiting_gates = self.gates.iter().collect::<Vec<_>>();
-        let mut wires = self.wires.clone();
-
-        while !waiting_gates.is_empty() {
-            let mut next_waiting = Vec::new();
-
-            for gate in &waiting_gates {
-                if let Some(&a) = wires.get(&gate.a) {
-                    if let Some(&b) = wires.get(&gate.b) {
-                        let r = gate.op.eval(a, b);
-                        wires.insert(gate.r.clone(), r);
-                        continue;
-                    }
-                }
-                next_waiting.push(*gate);
-            }
-
-            waiting_gates = next_waiting;
-        }
-
-        let result = wires
-            .iter()
-            .filter(|(r, &v)| r.starts_with('z') && v == 1)
-            .fold(0_u64, |acc, (r, _)| {
-                acc | (1 << r[1..].parse::<u64>().unwrap())
-            });
-
-        result.to_string()
-    }
-
     /// Solve part two.
     fn solve_part2(&self) -> String {
         let mut input_types: HashMap<&str, HashSet<Role>> = HashMap::new();
@@ -189,18 +174,488 @@ impl Puzzle {
     }
 }
 
+/// Largest adder width checked over every input pair.
+const EXHAUSTIVE_BITS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    /// These gate outputs depend on themselves.
+    Cycle(Vec<String>),
+    /// The wire has no value: it is neither an input nor driven by a gate.
+    Undriven(String),
+    UnknownWire(String),
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(wires) => write!(f, "cycle through {}", wires.join(",")),
+            Self::Undriven(wire) => write!(f, "wire {wire} has no value"),
+            Self::UnknownWire(wire) => write!(f, "unknown wire {wire}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdderError {
+    Circuit(CircuitError),
+    /// The x, y and z buses do not have the widths of an adder.
+    Shape(String),
+    /// `x + y` gave a wrong sum, the lowest wrong bit is `bit`.
+    WrongSum {
+        x: u64,
+        y: u64,
+        expected: u64,
+        actual: u64,
+        bit: u32,
+    },
+    /// Bit `bit` is not a full adder stage fed by the carry of the bit below.
+    Unproven(usize),
+    /// Too many inputs to try every pair.
+    TooWide(usize),
+}
+
+impl From<CircuitError> for AdderError {
+    fn from(e: CircuitError) -> Self {
+        Self::Circuit(e)
+    }
+}
+
+impl fmt::Display for AdderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Circuit(e) => write!(f, "{e}"),
+            Self::Shape(reason) => write!(f, "not an adder: {reason}"),
+            Self::WrongSum {
+                x,
+                y,
+                expected,
+                actual,
+                bit,
+            } => write!(
+                f,
+                "bit {bit} fails: {x} + {y} gave {actual} instead of {expected}"
+            ),
+            Self::Unproven(bit) => write!(f, "bit {bit} is not a full adder stage"),
+            Self::TooWide(bits) => write!(f, "{bits} bit inputs are too wide to try every pair"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Link {
+    a: usize,
+    b: usize,
+    op: Operation,
+    out: usize,
+}
+
+/// A gate netlist with interned wire names.
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    initial: Vec<Option<u8>>,
+    gates: Vec<Link>,
+}
+
+impl Circuit {
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let mut puzzle = Puzzle::new();
+        puzzle.configure(input);
+
+        let mut circuit = Self {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            initial: Vec::new(),
+            gates: Vec::new(),
+        };
+        for gate in &puzzle.gates {
+            let link = Link {
+                a: circuit.intern(&gate.a),
+                b: circuit.intern(&gate.b),
+                op: gate.op,
+                out: circuit.intern(&gate.r),
+            };
+            circuit.gates.push(link);
+        }
+        for (wire, &value) in &puzzle.wires {
+            let id = circuit.intern(wire);
+            circuit.initial[id] = Some(value);
+        }
+        circuit
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.initial.push(None);
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn id(&self, name: &str) -> Result<usize, CircuitError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| CircuitError::UnknownWire(name.to_string()))
+    }
+
+    /// Returns the wires `{prefix}00`, `{prefix}01`, ... up to the first missing one.
+    fn bus(&self, prefix: char) -> Vec<usize> {
+        (0..)
+            .map_while(|bit| self.ids.get(&format!("{prefix}{bit:02}")).copied())
+            .collect()
+    }
+
+    /// Orders the gates so that every gate comes after the gates driving its inputs.
+    ///
+    /// # Errors
+    /// if the gates form a cycle
+    pub fn order(&self) -> Result<Vec<usize>, CircuitError> {
+        let mut driver = vec![None; self.names.len()];
+        for (g, gate) in self.gates.iter().enumerate() {
+            driver[gate.out] = Some(g);
+        }
+
+        let mut pending = vec![0; self.gates.len()];
+        let mut users = vec![Vec::new(); self.gates.len()];
+        for (g, gate) in self.gates.iter().enumerate() {
+            for input in [gate.a, gate.b] {
+                if let Some(d) = driver[input] {
+                    pending[g] += 1;
+                    users[d].push(g);
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..self.gates.len()).filter(|&g| pending[g] == 0).collect();
+        let mut i = 0;
+        while i < order.len() {
+            for &user in &users[order[i]] {
+                pending[user] -= 1;
+                if pending[user] == 0 {
+                    order.push(user);
+                }
+            }
+            i += 1;
+        }
+
+        if order.len() < self.gates.len() {
+            // Drop the gates that merely hang off a cycle, keeping those on one.
+            let mut on_cycle: Vec<bool> = pending.iter().map(|&p| p > 0).collect();
+            while let Some(g) = (0..self.gates.len())
+                .find(|&g| on_cycle[g] && !users[g].iter().any(|&u| on_cycle[u]))
+            {
+                on_cycle[g] = false;
+            }
+
+            let mut wires: Vec<_> = (0..self.gates.len())
+                .filter(|&g| on_cycle[g])
+                .map(|g| self.names[self.gates[g].out].clone())
+                .collect();
+            wires.sort_unstable();
+            return Err(CircuitError::Cycle(wires));
+        }
+        Ok(order)
+    }
+
+    /// Propagates the given wire values through the gates.
+    ///
+    /// Gates with an input left unknown leave their output unknown.
+    fn simulate(&self, mut values: Vec<Option<u8>>) -> Result<Vec<Option<u8>>, CircuitError> {
+        for g in self.order()? {
+            let gate = self.gates[g];
+            if let (Some(a), Some(b)) = (values[gate.a], values[gate.b]) {
+                values[gate.out] = Some(gate.op.eval(a, b));
+            }
+        }
+        Ok(values)
+    }
+
+    fn read(&self, values: &[Option<u8>], bus: &[usize]) -> Result<u64, CircuitError> {
+        bus.iter().enumerate().try_fold(0, |acc, (bit, &id)| {
+            let value = values[id].ok_or_else(|| CircuitError::Undriven(self.names[id].clone()))?;
+            Ok(acc | (u64::from(value) << bit))
+        })
+    }
+
+    /// Evaluates the circuit from its initial wire values and returns the z bus.
+    ///
+    /// # Errors
+    /// if the gates form a cycle or a z wire gets no value
+    pub fn output(&self) -> Result<u64, CircuitError> {
+        let values = self.simulate(self.initial.clone())?;
+        self.read(&values, &self.bus('z'))
+    }
+
+    /// Evaluates the circuit with `x` and `y` on the input buses.
+    ///
+    /// # Errors
+    /// if the gates form a cycle or a z wire gets no value
+    pub fn add(&self, x: u64, y: u64) -> Result<u64, CircuitError> {
+        let mut values = vec![None; self.names.len()];
+        for (bus, n) in [(self.bus('x'), x), (self.bus('y'), y)] {
+            for (bit, id) in bus.into_iter().enumerate() {
+                values[id] = Some(((n >> bit) & 1) as u8);
+            }
+        }
+        let values = self.simulate(values)?;
+        self.read(&values, &self.bus('z'))
+    }
+
+    /// Swaps the outputs of the gates driving the two wires.
+    ///
+    /// # Errors
+    /// if a wire is unknown or not driven by a gate
+    pub fn swap(&mut self, a: &str, b: &str) -> Result<(), CircuitError> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        let driver = |wire: usize| {
+            self.gates
+                .iter()
+                .position(|gate| gate.out == wire)
+                .ok_or_else(|| CircuitError::Undriven(self.names[wire].clone()))
+        };
+        let (ga, gb) = (driver(a)?, driver(b)?);
+        self.gates[ga].out = b;
+        self.gates[gb].out = a;
+        Ok(())
+    }
+
+    /// Exports the netlist as a Graphviz digraph, with gates as ellipses and
+    /// input and output bits as boxes.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph circuit {\n  rankdir=LR;\n");
+
+        let mut bits: Vec<_> = ['x', 'y', 'z']
+            .into_iter()
+            .flat_map(|p| self.bus(p))
+            .collect();
+        bits.sort_unstable_by_key(|&id| &self.names[id]);
+        for id in bits {
+            dot.push_str(&format!("  {} [shape=box];\n", self.names[id]));
+        }
+
+        for (g, gate) in self.gates.iter().enumerate() {
+            let op = match gate.op {
+                Operation::And => "AND",
+                Operation::Or => "OR",
+                Operation::Xor => "XOR",
+            };
+            dot.push_str(&format!("  g{g} [label=\"{op}\"];\n"));
+            dot.push_str(&format!("  {} -> g{g};\n", self.names[gate.a]));
+            dot.push_str(&format!("  {} -> g{g};\n", self.names[gate.b]));
+            dot.push_str(&format!("  g{g} -> {};\n", self.names[gate.out]));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the input width, checking the buses are shaped like an adder.
+    fn adder_bits(&self) -> Result<usize, AdderError> {
+        let (x, y, z) = (
+            self.bus('x').len(),
+            self.bus('y').len(),
+            self.bus('z').len(),
+        );
+        if x != y || z != x + 1 || x > 63 {
+            return Err(AdderError::Shape(format!(
+                "{x} x bits, {y} y bits and {z} z bits"
+            )));
+        }
+        Ok(x)
+    }
+
+    fn check_sum(&self, x: u64, y: u64) -> Result<(), AdderError> {
+        let expected = x + y;
+        let actual = self.add(x, y)?;
+        if actual != expected {
+            return Err(AdderError::WrongSum {
+                x,
+                y,
+                expected,
+                actual,
+                bit: (actual ^ expected).trailing_zeros(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks single bit sums and carries, then `trials` random sums.
+    ///
+    /// # Errors
+    /// the first wrong sum, or why the circuit cannot be run as an adder
+    pub fn verify_random(&self, trials: usize, seed: u64) -> Result<(), AdderError> {
+        let bits = self.adder_bits()?;
+        let mask = (1_u64 << bits) - 1;
+
+        for bit in 0..bits {
+            let one = 1 << bit;
+            for (x, y) in [(one, 0), (0, one), (one, one), (mask, one)] {
+                self.check_sum(x, y)?;
+            }
+        }
+
+        let mut rng = SplitMix64::new(seed);
+        for _ in 0..trials {
+            self.check_sum(rng.next_u64() & mask, rng.next_u64() & mask)?;
+        }
+        Ok(())
+    }
+
+    /// Checks every sum of two inputs, for adders up to `EXHAUSTIVE_BITS` wide.
+    ///
+    /// # Errors
+    /// the first wrong sum, or why the circuit cannot be checked
+    pub fn verify_exhaustive(&self) -> Result<(), AdderError> {
+        let bits = self.adder_bits()?;
+        if bits > EXHAUSTIVE_BITS {
+            return Err(AdderError::TooWide(bits));
+        }
+        for x in 0..1 << bits {
+            for y in 0..1 << bits {
+                self.check_sum(x, y)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Proves the circuit is a ripple-carry adder and returns its carry wires.
+    ///
+    /// Each bit is evaluated on the 8 combinations of `x`, `y` and the carry
+    /// wire found for the bit below, with every other input unknown. The sum
+    /// bit must come out as their xor and some wire as their majority, which
+    /// becomes the carry into the next bit. By induction every sum bit is
+    /// correct for all inputs.
+    ///
+    /// # Errors
+    /// the lowest bit that is not a full adder stage
+    pub fn prove_adder(&self) -> Result<Vec<String>, AdderError> {
+        const X: u8 = 0b1111_0000;
+        const Y: u8 = 0b1100_1100;
+        const C: u8 = 0b1010_1010;
+
+        let bits = self.adder_bits()?;
+        let (xs, ys, zs) = (self.bus('x'), self.bus('y'), self.bus('z'));
+        let order = self.order()?;
+
+        let mut carries = Vec::new();
+        let mut carry = None;
+        for bit in 0..=bits {
+            // Truth tables over the cut inputs, one table bit per combination.
+            let mut tables = vec![None; self.names.len()];
+            let c = carry.map_or(0, |wire| {
+                tables[wire] = Some(C);
+                C
+            });
+            let (x, y) = if bit < bits {
+                tables[xs[bit]] = Some(X);
+                tables[ys[bit]] = Some(Y);
+                (X, Y)
+            } else {
+                (0, 0)
+            };
+
+            for &g in &order {
+                let gate = self.gates[g];
+                if tables[gate.out].is_none() {
+                    if let (Some(a), Some(b)) = (tables[gate.a], tables[gate.b]) {
+                        tables[gate.out] = Some(gate.op.eval(a, b));
+                    }
+                }
+            }
+
+            if tables[zs[bit]] != Some(x ^ y ^ c) {
+                return Err(AdderError::Unproven(bit));
+            }
+            if bit < bits {
+                let majority = (x & y) | (x & c) | (y & c);
+                let wire = (0..self.names.len())
+                    .find(|&id| tables[id] == Some(majority))
+                    .ok_or(AdderError::Unproven(bit))?;
+                carries.push(self.names[wire].clone());
+                carry = Some(wire);
+            }
+        }
+        Ok(carries)
+    }
+
+    /// Finds swaps among the given wires that make the circuit a provable
+    /// adder. Wires may be left in place, pairings using more of them are
+    /// tried first.
+    #[must_use]
+    pub fn find_swaps(&self, wires: &[&str]) -> Option<Vec<(String, String)>> {
+        fn search(
+            circuit: &Circuit,
+            rest: &[&str],
+            pairs: &mut Vec<(String, String)>,
+        ) -> Option<Vec<(String, String)>> {
+            let Some((&first, rest)) = rest.split_first() else {
+                let mut swapped = circuit.clone();
+                for (a, b) in pairs.iter() {
+                    swapped.swap(a, b).ok()?;
+                }
+                return swapped.prove_adder().ok().map(|_| pairs.clone());
+            };
+
+            (0..rest.len())
+                .find_map(|i| {
+                    let mut others = rest.to_vec();
+                    let second = others.remove(i);
+                    pairs.push((first.to_string(), second.to_string()));
+                    let found = search(circuit, &others, pairs);
+                    pairs.pop();
+                    found
+                })
+                .or_else(|| search(circuit, rest, pairs))
+        }
+
+        search(self, wires, &mut Vec::new())
+    }
+}
+
 /// Exposed function for part one.
-pub fn solve_part1(input: &str) -> String {
-    let mut puzzle = Puzzle::new();
-    puzzle.configure(input);
-    puzzle.solve_part1()
+pub fn solve_part1(input: &str, params: &Params) -> String {
+    let circuit = Circuit::parse(input);
+    if params.value::<String>(&SHOW) == "dot" {
+        return circuit.to_dot();
+    }
+    circuit
+        .output()
+        .map_or_else(|e| e.to_string(), |z| z.to_string())
 }
 
 /// Exposed function for part two.
-pub fn solve_part2(input: &str) -> String {
+pub fn solve_part2(input: &str, params: &Params) -> String {
     let mut puzzle = Puzzle::new();
     puzzle.configure(input);
-    puzzle.solve_part2()
+    let wires = puzzle.solve_part2();
+    if !params.value::<bool>(&CHECK) {
+        return wires;
+    }
+
+    let names: Vec<_> = wires.split(',').filter(|w| !w.is_empty()).collect();
+    match Circuit::parse(input).find_swaps(&names) {
+        Some(pairs) => {
+            let kept: Vec<_> = names
+                .iter()
+                .filter(|&&w| !pairs.iter().any(|(a, b)| a == w || b == w))
+                .copied()
+                .collect();
+            let pairs: Vec<_> = pairs.iter().map(|(a, b)| format!("{a}<->{b}")).collect();
+            let mut report = format!("{wires}\nadder proven after swapping {}", pairs.join(" "));
+            if !kept.is_empty() {
+                report.push_str(&format!(", left {} in place", kept.join(",")));
+            }
+            report
+        }
+        None => format!("{wires}\nno pairing of these wires makes an adder"),
+    }
 }
 
 #[cfg(test)]
@@ -224,7 +679,7 @@ x00 AND y00 -> z00
 x01 XOR y01 -> z01
 x02 OR y02 -> z02";
 
-        assert_eq!(solve_part1(input), "7");
+        assert_eq!(solve_part1(input, &Params::default()), "7");
     }
 
     #[test]
@@ -244,6 +699,93 @@ x00 AND y00 -> z00
 x01 XOR y01 -> z01
 x02 OR y02 -> z02";
 
-        assert_eq!(solve_part2(input), "z00,z01");
+        assert_eq!(solve_part2(input, &Params::default()), "z00,z01");
+    }
+
+    /// Builds a ripple-carry adder netlist shaped like the puzzle inputs.
+    fn adder(bits: usize, x: u64, y: u64) -> String {
+        let mut lines = Vec::new();
+        for (prefix, n) in [('x', x), ('y', y)] {
+            for bit in 0..bits {
+                lines.push(format!("{prefix}{bit:02}: {}", (n >> bit) & 1));
+            }
+        }
+        lines.push(String::new());
+        lines.push("x00 XOR y00 -> z00".to_string());
+        lines.push("x00 AND y00 -> c00".to_string());
+        for i in 1..bits {
+            let (p, carry) = (
+                i - 1,
+                if i + 1 == bits {
+                    format!("z{bits:02}")
+                } else {
+                    format!("c{i:02}")
+                },
+            );
+            lines.push(format!("x{i:02} XOR y{i:02} -> s{i:02}"));
+            lines.push(format!("s{i:02} XOR c{p:02} -> z{i:02}"));
+            lines.push(format!("x{i:02} AND y{i:02} -> a{i:02}"));
+            lines.push(format!("s{i:02} AND c{p:02} -> b{i:02}"));
+            lines.push(format!("a{i:02} OR b{i:02} -> {carry}"));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_adder() {
+        let circuit = Circuit::parse(&adder(8, 200, 100));
+        assert_eq!(circuit.output(), Ok(300));
+        assert_eq!(circuit.add(255, 1), Ok(256));
+        assert_eq!(circuit.verify_random(100, 1), Ok(()));
+        assert_eq!(circuit.prove_adder().unwrap().len(), 8);
+
+        let small = Circuit::parse(&adder(4, 0, 0));
+        assert_eq!(small.verify_exhaustive(), Ok(()));
+    }
+
+    #[test]
+    fn test_swapped_wires() {
+        let mut circuit = Circuit::parse(&adder(8, 0, 0));
+        circuit.swap("z05", "c05").unwrap();
+
+        assert_eq!(circuit.prove_adder(), Err(AdderError::Unproven(5)));
+        assert!(matches!(
+            circuit.verify_random(100, 1),
+            Err(AdderError::WrongSum { bit: 5, .. })
+        ));
+        assert_eq!(
+            circuit.find_swaps(&["c05", "z05"]),
+            Some(vec![("c05".to_string(), "z05".to_string())])
+        );
+
+        circuit.swap("z03", "s03").unwrap();
+        assert_eq!(
+            circuit.output(),
+            Err(CircuitError::Cycle(vec!["s03".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_check_swaps() {
+        let input = adder(8, 0, 0)
+            .replace("-> z05", "-> tmp")
+            .replace("-> c05", "-> z05")
+            .replace("-> tmp", "-> c05");
+        let params = Params::new().with("check", "true");
+
+        assert_eq!(
+            solve_part2(&input, &params),
+            "c00,c05,z00,z05\nadder proven after swapping c05<->z05, left c00,z00 in place"
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let circuit = Circuit::parse("x00: 1\ny00: 0\n\nx00 XOR y00 -> z00");
+        assert_eq!(
+            circuit.to_dot(),
+            "digraph circuit {\n  rankdir=LR;\n  x00 [shape=box];\n  y00 [shape=box];\n  \
+             z00 [shape=box];\n  g0 [label=\"XOR\"];\n  x00 -> g0;\n  y00 -> g0;\n  g0 -> z00;\n}\n"
+        );
     }
 }
@@ -0,0 +1,258 @@
+/// Knuth's Algorithm X with dancing links. Primary columns must be covered
+/// exactly once, secondary columns at most once.
+///
+/// Node 0 is the root, nodes `1..=columns` are the column headers and the
+/// rows follow, each a circular list of its nodes.
+#[derive(Clone, Debug)]
+pub struct Dlx {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Header of each node.
+    header: Vec<usize>,
+    /// Row of each node, unused for the root and headers.
+    row: Vec<usize>,
+    /// Nodes left in each column, indexed by header.
+    size: Vec<usize>,
+    rows: usize,
+}
+
+impl Dlx {
+    #[must_use]
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let headers = primary + secondary + 1;
+        let mut dlx = Self {
+            left: (0..headers).collect(),
+            right: (0..headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            header: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+            rows: 0,
+        };
+        // Only primary headers join the root list, so the search never has
+        // to pick a secondary column.
+        for c in 1..=primary {
+            dlx.left[c] = c - 1;
+            dlx.right[c - 1] = c;
+        }
+        dlx.left[0] = primary;
+        dlx.right[primary] = 0;
+        dlx
+    }
+
+    /// Adds a row covering `columns`, primary ones first, and returns its
+    /// index.
+    ///
+    /// # Panics
+    /// if a column is out of range
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let row = self.rows;
+        self.rows += 1;
+        let first = self.left.len();
+        for (i, &column) in columns.iter().enumerate() {
+            let c = column + 1;
+            assert!(c < self.size.len(), "column {column} out of range");
+            let node = first + i;
+            self.header.push(c);
+            self.row.push(row);
+            self.up.push(self.up[c]);
+            self.down.push(c);
+            self.down[self.up[c]] = node;
+            self.up[c] = node;
+            self.size[c] += 1;
+            self.left.push(if i == 0 { node } else { node - 1 });
+            self.right.push(first);
+            self.right[self.left[node]] = node;
+            self.left[first] = node;
+        }
+        row
+    }
+
+    fn cover(&mut self, c: usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.header[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.header[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+
+    /// Visits the solutions, as row indices in the order chosen, until
+    /// `visit` returns `true`. Rows are only tried when `accept` allows
+    /// them next to the rows chosen so far, which prunes the search.
+    pub fn search(
+        &mut self,
+        accept: &mut dyn FnMut(usize, &[usize]) -> bool,
+        visit: &mut dyn FnMut(&[usize]) -> bool,
+    ) {
+        self.search_from(&mut Vec::new(), accept, visit);
+    }
+
+    fn search_from(
+        &mut self,
+        chosen: &mut Vec<usize>,
+        accept: &mut dyn FnMut(usize, &[usize]) -> bool,
+        visit: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if self.right[0] == 0 {
+            return visit(chosen);
+        }
+
+        // The column with the fewest options left.
+        let mut c = self.right[0];
+        let mut j = self.right[c];
+        while j != 0 {
+            if self.size[j] < self.size[c] {
+                c = j;
+            }
+            j = self.right[j];
+        }
+        if self.size[c] == 0 {
+            return false;
+        }
+
+        self.cover(c);
+        let mut r = self.down[c];
+        let mut stop = false;
+        while r != c && !stop {
+            if accept(self.row[r], chosen) {
+                chosen.push(self.row[r]);
+                let mut j = self.right[r];
+                while j != r {
+                    self.cover(self.header[j]);
+                    j = self.right[j];
+                }
+                stop = self.search_from(chosen, accept, visit);
+                let mut j = self.left[r];
+                while j != r {
+                    self.uncover(self.header[j]);
+                    j = self.left[j];
+                }
+                chosen.pop();
+            }
+            r = self.down[r];
+        }
+        self.uncover(c);
+        stop
+    }
+
+    /// Returns the first solution found.
+    pub fn solve(&mut self) -> Option<Vec<usize>> {
+        let mut solution = None;
+        self.search(&mut |_, _| true, &mut |rows| {
+            solution = Some(rows.to_vec());
+            true
+        });
+        solution
+    }
+
+    /// Counts every solution.
+    pub fn count(&mut self) -> usize {
+        let mut count = 0;
+        self.search(&mut |_, _| true, &mut |_| {
+            count += 1;
+            false
+        });
+        count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exact_cover() {
+        // Knuth's example from "Dancing Links".
+        let mut dlx = Dlx::new(7, 0);
+        for row in [
+            vec![2, 4, 5],
+            vec![0, 3, 6],
+            vec![1, 2, 5],
+            vec![0, 3],
+            vec![1, 6],
+            vec![3, 4, 6],
+        ] {
+            dlx.add_row(&row);
+        }
+        let mut solution = dlx.solve().unwrap();
+        solution.sort_unstable();
+        assert_eq!(solution, vec![0, 3, 4]);
+        assert_eq!(dlx.count(), 1);
+
+        let mut empty = Dlx::new(2, 0);
+        empty.add_row(&[0]);
+        assert_eq!(empty.solve(), None);
+    }
+
+    #[test]
+    fn test_secondary_columns() {
+        // Queens: ranks and files primary, diagonals secondary.
+        let queens = |n: usize| {
+            let mut dlx = Dlx::new(2 * n, 2 * (2 * n - 1));
+            for r in 0..n {
+                for c in 0..n {
+                    let diagonals = 2 * n + r + c;
+                    let anti = 2 * n + (2 * n - 1) + r + n - 1 - c;
+                    dlx.add_row(&[r, n + c, diagonals, anti]);
+                }
+            }
+            dlx.count()
+        };
+        assert_eq!(queens(4), 2);
+        assert_eq!(queens(6), 4);
+        assert_eq!(queens(8), 92);
+    }
+
+    #[test]
+    fn test_accept() {
+        // Two identical dominoes on a 1x4 strip: the second copy must come
+        // after the first, so the one tiling is found once.
+        let mut dlx = Dlx::new(6, 0);
+        for piece in 0..2 {
+            for start in 0..3 {
+                dlx.add_row(&[piece, 2 + start, 3 + start]);
+            }
+        }
+        let mut count = 0;
+        dlx.search(
+            &mut |row, chosen| {
+                chosen
+                    .iter()
+                    .all(|&c| (c < 3) == (row < 3) || (c % 3 < row % 3) == (c < row))
+            },
+            &mut |_| {
+                count += 1;
+                false
+            },
+        );
+        assert_eq!(count, 1);
+        assert_eq!(dlx.count(), 2);
+    }
+}
@@ -0,0 +1,387 @@
+use std::ops::{BitOrAssign, BitXorAssign};
+
+/// A fixed-length vector over GF(2), packed 64 bits to a word.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitVec {
+    #[must_use]
+    pub fn zeros(len: usize) -> Self {
+        Self {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let mut v = Self::zeros(bits.len());
+        for (i, &bit) in bits.iter().enumerate() {
+            v.set(i, bit);
+        }
+        v
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit {i} out of {}", self.len);
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        assert!(i < self.len, "bit {i} out of {}", self.len);
+        let mask = 1 << (i % 64);
+        if bit {
+            self.words[i / 64] |= mask;
+        } else {
+            self.words[i / 64] &= !mask;
+        }
+    }
+
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.len, "bit {i} out of {}", self.len);
+        self.words[i / 64] ^= 1 << (i % 64);
+    }
+
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Indices of the set bits, in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    i * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+impl BitXorAssign<&Self> for BitVec {
+    fn bitxor_assign(&mut self, other: &Self) {
+        assert_eq!(self.len, other.len, "length mismatch");
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a ^= b;
+        }
+    }
+}
+
+impl BitOrAssign<&Self> for BitVec {
+    fn bitor_assign(&mut self, other: &Self) {
+        assert_eq!(self.len, other.len, "length mismatch");
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+}
+
+/// A matrix over GF(2), stored as bit rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    columns: usize,
+    rows: Vec<BitVec>,
+}
+
+impl Matrix {
+    #[must_use]
+    pub fn zeros(rows: usize, columns: usize) -> Self {
+        Self {
+            columns,
+            rows: vec![BitVec::zeros(columns); rows],
+        }
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    #[must_use]
+    pub const fn columns(&self) -> usize {
+        self.columns
+    }
+
+    #[must_use]
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.rows[row].get(column)
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, bit: bool) {
+        self.rows[row].set(column, bit);
+    }
+
+    /// Solves `self x = b` by Gauss-Jordan elimination, returning every
+    /// solution as one particular solution plus the null space, or `None`
+    /// if the system is inconsistent.
+    ///
+    /// # Panics
+    /// if `b` does not have one bit per row
+    #[must_use]
+    pub fn solve(&self, b: &BitVec) -> Option<SolutionSpace> {
+        assert_eq!(b.len(), self.rows(), "right hand side length mismatch");
+        let n = self.columns;
+        let mut rows: Vec<BitVec> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut augmented = BitVec::zeros(n + 1);
+                row.ones().for_each(|j| augmented.set(j, true));
+                augmented.set(n, b.get(i));
+                augmented
+            })
+            .collect();
+
+        let mut pivots = Vec::new();
+        let mut free = Vec::new();
+        for k in 0..n {
+            let h = pivots.len();
+            let Some(found) = (h..rows.len()).find(|&i| rows[i].get(k)) else {
+                free.push(k);
+                continue;
+            };
+            rows.swap(h, found);
+            let pivot_row = rows[h].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                if i != h && row.get(k) {
+                    *row ^= &pivot_row;
+                }
+            }
+            pivots.push(k);
+        }
+
+        if rows[pivots.len()..].iter().any(|row| row.get(n)) {
+            return None;
+        }
+
+        let mut particular = BitVec::zeros(n);
+        for (row, &k) in rows.iter().zip(&pivots) {
+            particular.set(k, row.get(n));
+        }
+        let basis = free
+            .iter()
+            .map(|&f| {
+                let mut v = BitVec::zeros(n);
+                v.set(f, true);
+                for (row, &k) in rows.iter().zip(&pivots) {
+                    v.set(k, row.get(f));
+                }
+                v
+            })
+            .collect();
+        Some(SolutionSpace { particular, basis })
+    }
+}
+
+/// Largest null space whose `2^nullity` solutions are worth enumerating.
+pub const MAX_NULLITY: usize = 24;
+
+/// The solutions of a consistent system: `particular` plus any sum of
+/// `basis` vectors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolutionSpace {
+    pub particular: BitVec,
+    pub basis: Vec<BitVec>,
+}
+
+impl SolutionSpace {
+    /// Number of solutions is `2^nullity`.
+    #[must_use]
+    pub fn nullity(&self) -> usize {
+        self.basis.len()
+    }
+
+    /// Every solution once, in Gray code order so that each step adds a
+    /// single basis vector, or `None` if the nullity exceeds [`MAX_NULLITY`].
+    #[must_use]
+    pub fn iter(&self) -> Option<Solutions<'_>> {
+        (self.nullity() <= MAX_NULLITY).then(|| Solutions {
+            space: self,
+            current: self.particular.clone(),
+            step: 0,
+        })
+    }
+
+    /// A solution with the fewest set bits, the first one found on ties, or
+    /// `None` if the nullity exceeds [`MAX_NULLITY`].
+    #[must_use]
+    pub fn min_weight(&self) -> Option<BitVec> {
+        if self.nullity() > MAX_NULLITY {
+            return None;
+        }
+        let mut current = self.particular.clone();
+        let (mut best, mut best_weight) = (current.clone(), current.count_ones());
+        for step in 1..1u64 << self.nullity() {
+            current ^= &self.basis[step.trailing_zeros() as usize];
+            let weight = current.count_ones();
+            if weight < best_weight {
+                (best, best_weight) = (current.clone(), weight);
+            }
+        }
+        Some(best)
+    }
+}
+
+/// Iterator over the solutions of a [`SolutionSpace`].
+pub struct Solutions<'a> {
+    space: &'a SolutionSpace,
+    current: BitVec,
+    step: u64,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = BitVec;
+
+    fn next(&mut self) -> Option<BitVec> {
+        if self.step >= 1 << self.space.nullity() {
+            return None;
+        }
+        if self.step > 0 {
+            self.current ^= &self.space.basis[self.step.trailing_zeros() as usize];
+        }
+        self.step += 1;
+        Some(self.current.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solutions::y2024::helpers::rng::SplitMix64;
+
+    fn multiply(a: &Matrix, x: &BitVec) -> BitVec {
+        let bits: Vec<bool> = a
+            .rows
+            .iter()
+            .map(|row| row.ones().filter(|&j| x.get(j)).count() % 2 == 1)
+            .collect();
+        BitVec::from_bools(&bits)
+    }
+
+    #[test]
+    fn test_bitvec() {
+        let mut v = BitVec::zeros(130);
+        v.set(0, true);
+        v.set(64, true);
+        v.flip(129);
+        assert_eq!(v.ones().collect::<Vec<_>>(), vec![0, 64, 129]);
+        assert_eq!(v.count_ones(), 3);
+        v.flip(64);
+        assert!(!v.get(64));
+
+        let mut w = BitVec::from_bools(&[true; 130]);
+        w ^= &v;
+        assert_eq!(w.count_ones(), 128);
+    }
+
+    #[test]
+    fn test_solve() {
+        // The first machine of 2025 day 10: lights .##. and six buttons.
+        let buttons = [
+            vec![3],
+            vec![1, 3],
+            vec![2],
+            vec![2, 3],
+            vec![0, 2],
+            vec![0, 1],
+        ];
+        let mut a = Matrix::zeros(4, buttons.len());
+        for (j, button) in buttons.iter().enumerate() {
+            button.iter().for_each(|&i| a.set(i, j, true));
+        }
+        let b = BitVec::from_bools(&[false, true, true, false]);
+
+        let space = a.solve(&b).unwrap();
+        assert_eq!(space.nullity(), 2);
+        let solutions: Vec<_> = space.iter().unwrap().collect();
+        assert_eq!(solutions.len(), 4);
+        assert!(solutions.iter().all(|x| multiply(&a, x) == b));
+        assert_eq!(space.min_weight().unwrap().count_ones(), 2);
+
+        let mut a = Matrix::zeros(2, 1);
+        a.set(0, 0, true);
+        a.set(1, 0, true);
+        assert_eq!(a.solve(&BitVec::from_bools(&[true, false])), None);
+    }
+
+    #[test]
+    fn test_wide_system() {
+        // 100 lights each toggled by its own button and the next one's.
+        let n = 100;
+        let mut a = Matrix::zeros(n, n);
+        for i in 0..n {
+            a.set(i, i, true);
+            a.set((i + 1) % n, i, true);
+        }
+        let mut b = BitVec::zeros(n);
+        b.set(10, true);
+        b.set(90, true);
+
+        let space = a.solve(&b).unwrap();
+        assert_eq!(space.nullity(), 1);
+        let x = space.min_weight().unwrap();
+        assert_eq!(multiply(&a, &x), b);
+        assert_eq!(x.count_ones(), 20);
+
+        // One light and 41 or 70 buttons all toggling it.
+        for buttons in [41, 70] {
+            let mut a = Matrix::zeros(1, buttons);
+            (0..buttons).for_each(|j| a.set(0, j, true));
+            let space = a.solve(&BitVec::from_bools(&[true])).unwrap();
+            assert_eq!(space.nullity(), buttons - 1);
+            assert!(space.iter().is_none());
+            assert_eq!(space.min_weight(), None);
+        }
+    }
+
+    #[test]
+    fn test_random_systems() {
+        let mut rng = SplitMix64::new(2);
+        for _ in 0..200 {
+            let (m, n) = (1 + rng.below(6) as usize, 1 + rng.below(8) as usize);
+            let mut a = Matrix::zeros(m, n);
+            for i in 0..m {
+                for j in 0..n {
+                    a.set(i, j, rng.below(2) == 1);
+                }
+            }
+            let bits: Vec<bool> = (0..m).map(|_| rng.below(2) == 1).collect();
+            let b = BitVec::from_bools(&bits);
+
+            let brute = (0..1u32 << n)
+                .map(|mask| {
+                    BitVec::from_bools(&(0..n).map(|j| mask >> j & 1 == 1).collect::<Vec<_>>())
+                })
+                .filter(|x| multiply(&a, x) == b)
+                .collect::<Vec<_>>();
+            match a.solve(&b) {
+                Some(space) => {
+                    assert_eq!(space.iter().unwrap().count(), brute.len());
+                    let best = brute.iter().map(BitVec::count_ones).min().unwrap();
+                    let x = space.min_weight().unwrap();
+                    assert_eq!((x.count_ones(), multiply(&a, &x)), (best, b.clone()));
+                }
+                None => assert!(brute.is_empty()),
+            }
+        }
+    }
+}
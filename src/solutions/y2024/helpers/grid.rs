@@ -3,7 +3,7 @@ use std::ops::{Index, IndexMut};
 
 pub use crate::y2024::helpers::coord::Coord;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Grid {
     size: Coord,
     data: Vec<char>,
@@ -62,6 +62,31 @@ impl Grid {
         }
     }
 
+    /// Builds a grid from rows of cells, padding short rows with spaces.
+    ///
+    /// # Panics
+    #[must_use]
+    pub fn from_rows(rows: &[Vec<char>]) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+
+        let mut data = Vec::with_capacity(width * height);
+        for row in rows {
+            data.extend(row);
+            data.extend((row.len()..width).map(|_| ' '));
+        }
+
+        Self {
+            size: Coord::new(width.try_into().unwrap(), height.try_into().unwrap()),
+            data,
+            dummy: '.',
+        }
+    }
+
+    pub fn fill(&mut self, c: char) {
+        self.data.fill(c);
+    }
+
     /// # Panics
     pub fn iter(&self) -> impl Iterator<Item = (Coord, &char)> {
         self.data.iter().enumerate().map(move |(i, c)| {
@@ -0,0 +1,390 @@
+use std::fmt;
+
+/// Why no optimum of `A x = b` over non-negative integers was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IlpError {
+    /// The equations contradict each other, even over the rationals.
+    Inconsistent,
+    /// Solutions exist, but none with non-negative integers.
+    NoSolution,
+    /// A free variable has no bound, and no solution turned up with it below
+    /// [`SEARCH_LIMIT`], so whether one exists is unknown.
+    Unbounded { variable: usize },
+    /// Eliminating the equations overflowed `i64`.
+    Overflow,
+}
+
+impl fmt::Display for IlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inconsistent => write!(f, "equations are inconsistent"),
+            Self::NoSolution => write!(f, "no non-negative integer solution"),
+            Self::Unbounded { variable } => write!(
+                f,
+                "variable {variable} is unbounded and no solution was found below {SEARCH_LIMIT}"
+            ),
+            Self::Overflow => write!(f, "coefficients overflow during elimination"),
+        }
+    }
+}
+
+/// How far unbounded free variables are searched before giving up.
+pub const SEARCH_LIMIT: i64 = 1 << 10;
+
+/// Values of the variables at the optimum, and their sum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub values: Vec<i64>,
+    pub total: i64,
+}
+
+const fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+/// An equation left after elimination:
+/// `pivot * x[column] + Σ coefficients[i] * x[free[i]] = rhs`.
+#[derive(Clone, Debug)]
+struct PivotRow {
+    column: usize,
+    pivot: i64,
+    coefficients: Vec<i64>,
+    rhs: i64,
+}
+
+/// Reduces `[A | b]` with integer row operations only, dividing each row by
+/// the gcd of its entries to keep them small. Returns the pivot rows and the
+/// free columns.
+fn reduce(a: &[Vec<i64>], b: &[i64]) -> Result<(Vec<PivotRow>, Vec<usize>), IlpError> {
+    let n = a.first().map_or(0, Vec::len);
+    let mut rows: Vec<Vec<i64>> = a
+        .iter()
+        .zip(b)
+        .map(|(row, &rhs)| row.iter().copied().chain([rhs]).collect())
+        .collect();
+    let mut pivots = Vec::new();
+    let mut free = Vec::new();
+    let mut h = 0;
+
+    for k in 0..n {
+        let Some(best) = (h..rows.len())
+            .filter(|&i| rows[i][k] != 0)
+            .min_by_key(|&i| rows[i][k].abs())
+        else {
+            free.push(k);
+            continue;
+        };
+        rows.swap(h, best);
+        let pivot_row = rows[h].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == h || row[k] == 0 {
+                continue;
+            }
+            let g = gcd(pivot_row[k], row[k]);
+            let (p, q) = (pivot_row[k] / g, row[k] / g);
+            for (v, &w) in row.iter_mut().zip(&pivot_row) {
+                *v = v
+                    .checked_mul(p)
+                    .zip(w.checked_mul(q))
+                    .and_then(|(x, y)| x.checked_sub(y))
+                    .ok_or(IlpError::Overflow)?;
+            }
+            let g = row.iter().fold(0, |g, &v| gcd(g, v));
+            if g > 1 {
+                row.iter_mut().for_each(|v| *v /= g);
+            }
+        }
+        pivots.push(k);
+        h += 1;
+    }
+
+    if rows[h..].iter().any(|row| row[n] != 0) {
+        return Err(IlpError::Inconsistent);
+    }
+
+    let pivot_rows = pivots
+        .into_iter()
+        .zip(&rows)
+        .map(|(column, row)| {
+            let sign = row[column].signum();
+            PivotRow {
+                column,
+                pivot: row[column] * sign,
+                coefficients: free.iter().map(|&f| row[f] * sign).collect(),
+                rhs: row[n] * sign,
+            }
+        })
+        .collect();
+    Ok((pivot_rows, free))
+}
+
+/// Largest value of the `d`-th free variable `j`, from the equations and
+/// pivot rows with no negative entries. A variable appearing in no equation
+/// never helps and stays at zero.
+fn upper_bound(a: &[Vec<i64>], b: &[i64], rows: &[PivotRow], j: usize, d: usize) -> Option<i64> {
+    if a.iter().all(|row| row[j] == 0) {
+        return Some(0);
+    }
+    let equations = a
+        .iter()
+        .zip(b)
+        .filter(|(row, _)| row[j] > 0 && row.iter().all(|&v| v >= 0))
+        .map(|(row, &rhs)| rhs.div_euclid(row[j]));
+    let pivots = rows
+        .iter()
+        .filter(|row| row.coefficients[d] > 0 && row.coefficients.iter().all(|&c| c >= 0))
+        .map(|row| row.rhs.div_euclid(row.coefficients[d]));
+    equations.chain(pivots).min()
+}
+
+/// Depth first search over the free variables, pruning on the bounds the
+/// pivot variables can still reach.
+struct Search<'a> {
+    rows: &'a [PivotRow],
+    upper: Vec<i64>,
+    /// Per row and depth, the most the free variables from that depth on
+    /// can add to and subtract from the right hand side.
+    add: Vec<Vec<i64>>,
+    sub: Vec<Vec<i64>>,
+    values: Vec<i64>,
+    best: Option<(i64, Vec<i64>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self, depth: usize, residuals: &[i64], assigned: i64) {
+        let mut bound = assigned;
+        for (h, row) in self.rows.iter().enumerate() {
+            if residuals[h] + self.add[h][depth] < 0 {
+                return;
+            }
+            let least = (residuals[h] - self.sub[h][depth]).max(0);
+            bound += least.div_euclid(row.pivot) + i64::from(least % row.pivot != 0);
+        }
+        if self.best.as_ref().is_some_and(|&(best, _)| bound >= best) {
+            return;
+        }
+
+        if depth == self.upper.len() {
+            if self
+                .rows
+                .iter()
+                .zip(residuals)
+                .all(|(row, &r)| r % row.pivot == 0)
+            {
+                let pivots = self
+                    .rows
+                    .iter()
+                    .zip(residuals)
+                    .map(|(row, &r)| r / row.pivot);
+                let total = assigned + pivots.sum::<i64>();
+                self.best = Some((total, self.values.clone()));
+            }
+            return;
+        }
+
+        let mut next = residuals.to_vec();
+        for value in 0..=self.upper[depth] {
+            self.values[depth] = value;
+            self.run(depth + 1, &next, assigned + value);
+            for (r, row) in next.iter_mut().zip(self.rows) {
+                *r -= row.coefficients[depth];
+            }
+        }
+    }
+}
+
+/// Finds the smallest total of the free variables up to `upper`, and their
+/// values, or `None` if there is no solution within those bounds.
+fn search(rows: &[PivotRow], upper: Vec<i64>) -> Option<(i64, Vec<i64>)> {
+    let reach = |positive: bool| -> Vec<Vec<i64>> {
+        rows.iter()
+            .map(|row| {
+                let mut sums = vec![0; upper.len() + 1];
+                for d in (0..upper.len()).rev() {
+                    let c = row.coefficients[d];
+                    let part = if (c > 0) == positive {
+                        c.abs() * upper[d]
+                    } else {
+                        0
+                    };
+                    sums[d] = sums[d + 1] + part;
+                }
+                sums
+            })
+            .collect()
+    };
+    let mut search = Search {
+        rows,
+        add: reach(false),
+        sub: reach(true),
+        values: vec![0; upper.len()],
+        upper,
+        best: None,
+    };
+    let residuals: Vec<i64> = rows.iter().map(|row| row.rhs).collect();
+    search.run(0, &residuals, 0);
+    search.best
+}
+
+/// Minimizes the sum of `x` over the non-negative integer solutions of
+/// `a x = b`, searching the free variables up to bounds taken from the rows
+/// without negative entries.
+///
+/// A free variable without such a bound is searched up to a cap doubling
+/// from 1. Once a solution with total `t` turns up, no variable of the
+/// optimum exceeds `t`, so one more search with cap `t` finds it.
+///
+/// # Errors
+/// if the system has no non-negative integer solution, or a free variable is
+/// unbounded and no solution was found below [`SEARCH_LIMIT`]
+pub fn minimize_sum(a: &[Vec<i64>], b: &[i64]) -> Result<Solution, IlpError> {
+    let n = a.first().map_or(0, Vec::len);
+    let (rows, free) = reduce(a, b)?;
+    let bounds: Vec<Option<i64>> = free
+        .iter()
+        .enumerate()
+        .map(|(d, &j)| upper_bound(a, b, &rows, j, d))
+        .collect();
+    if bounds.iter().flatten().any(|&u| u < 0) {
+        return Err(IlpError::NoSolution);
+    }
+
+    let mut cap = 1;
+    let (total, free_values) = loop {
+        let upper = bounds.iter().map(|u| u.unwrap_or(cap)).collect();
+        match search(&rows, upper) {
+            Some((total, _)) if bounds.contains(&None) && cap < total => cap = total,
+            Some(best) => break best,
+            None => {
+                let Some(d) = bounds.iter().position(Option::is_none) else {
+                    return Err(IlpError::NoSolution);
+                };
+                if cap >= SEARCH_LIMIT {
+                    return Err(IlpError::Unbounded { variable: free[d] });
+                }
+                cap *= 2;
+            }
+        }
+    };
+
+    let mut values = vec![0; n];
+    for (&j, &v) in free.iter().zip(&free_values) {
+        values[j] = v;
+    }
+    for row in &rows {
+        let r = row.rhs
+            - row
+                .coefficients
+                .iter()
+                .zip(&free_values)
+                .map(|(c, v)| c * v)
+                .sum::<i64>();
+        values[row.column] = r / row.pivot;
+    }
+    Ok(Solution { values, total })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solutions::y2024::helpers::rng::SplitMix64;
+
+    /// Tries every vector up to `max` in each variable, as a reference.
+    fn brute_force(a: &[Vec<i64>], b: &[i64], max: i64) -> Option<i64> {
+        let n = a[0].len();
+        let mut x = vec![0; n];
+        let mut best = None;
+        loop {
+            if a.iter()
+                .zip(b)
+                .all(|(row, &rhs)| row.iter().zip(&x).map(|(c, v)| c * v).sum::<i64>() == rhs)
+            {
+                let total = x.iter().sum::<i64>();
+                best = Some(best.map_or(total, |b: i64| b.min(total)));
+            }
+            let Some(i) = x.iter().position(|&v| v < max) else {
+                return best;
+            };
+            x[..i].fill(0);
+            x[i] += 1;
+        }
+    }
+
+    #[test]
+    fn test_minimize_sum() {
+        // Counters 3,5,4,7 from the 2025 day 10 example.
+        let a = vec![
+            vec![0, 0, 0, 0, 1, 1],
+            vec![0, 1, 0, 0, 0, 1],
+            vec![0, 0, 1, 1, 1, 0],
+            vec![1, 1, 0, 1, 0, 0],
+        ];
+        let solution = minimize_sum(&a, &[3, 5, 4, 7]).unwrap();
+        assert_eq!(solution.total, 10);
+        for (row, rhs) in a.iter().zip([3, 5, 4, 7]) {
+            let sum: i64 = row.iter().zip(&solution.values).map(|(c, v)| c * v).sum();
+            assert_eq!(sum, rhs);
+        }
+    }
+
+    #[test]
+    fn test_infeasible() {
+        let a = vec![vec![1, 1], vec![1, 1]];
+        assert_eq!(minimize_sum(&a, &[1, 2]), Err(IlpError::Inconsistent));
+        // x = 1/2 over the rationals.
+        assert_eq!(minimize_sum(&[vec![2]], &[1]), Err(IlpError::NoSolution));
+        assert_eq!(
+            minimize_sum(&[vec![1, 1], vec![1, 0]], &[1, 2]),
+            Err(IlpError::NoSolution)
+        );
+        // 2x - 2y = 1 has no integer solution however large y gets.
+        assert_eq!(
+            minimize_sum(&[vec![2, -2]], &[1]),
+            Err(IlpError::Unbounded { variable: 1 })
+        );
+    }
+
+    #[test]
+    fn test_unbounded_variables() {
+        // x - y = 1 puts no bound on y, but the optimum is still y = 0.
+        let solution = minimize_sum(&[vec![1, -1]], &[1]).unwrap();
+        assert_eq!((solution.values, solution.total), (vec![1, 0], 1));
+
+        // x - y = -5 needs y of at least 5, beyond the first caps.
+        let solution = minimize_sum(&[vec![1, -1]], &[-5]).unwrap();
+        assert_eq!((solution.values, solution.total), (vec![0, 5], 5));
+
+        // x - y - z = 0 and y - 3z = 2: the cheapest is z = 0, y = 2, x = 2.
+        let a = vec![vec![1, -1, -1], vec![0, 1, -3]];
+        let solution = minimize_sum(&a, &[0, 2]).unwrap();
+        assert_eq!((solution.values, solution.total), (vec![2, 2, 0], 4));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let big = i64::MAX / 2;
+        let a = vec![vec![big, big - 1], vec![big - 1, big]];
+        assert_eq!(minimize_sum(&a, &[1, 1]), Err(IlpError::Overflow));
+    }
+
+    #[test]
+    fn test_random_systems() {
+        let mut rng = SplitMix64::new(10);
+        for _ in 0..300 {
+            let (m, n) = (1 + rng.below(3) as usize, 1 + rng.below(4) as usize);
+            let a: Vec<Vec<i64>> = (0..m)
+                .map(|_| (0..n).map(|_| rng.range(0, 2)).collect())
+                .collect();
+            let b: Vec<i64> = (0..m).map(|_| rng.range(0, 6)).collect();
+
+            let expected = brute_force(&a, &b, 6);
+            match minimize_sum(&a, &b) {
+                Ok(solution) => assert_eq!(Some(solution.total), expected, "{a:?} {b:?}"),
+                Err(_) => assert_eq!(expected, None, "{a:?} {b:?}"),
+            }
+        }
+    }
+}
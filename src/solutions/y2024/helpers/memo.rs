@@ -0,0 +1,207 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Hash map backed cache for memoized recursion.
+///
+/// Lookups take a borrowed key, so the owned key is only built when a new
+/// value is inserted.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.get(key)
+    }
+
+    /// Returns the cached value for `key`, or computes it with `f` and caches it.
+    ///
+    /// `f` receives the memo itself so it can recurse into smaller sub-problems.
+    pub fn get_or_compute<Q>(&mut self, key: &Q, f: impl FnOnce(&mut Self) -> V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        if let Some(cached) = self.cache.get(key) {
+            return cached.clone();
+        }
+
+        let value = f(self);
+        self.cache.insert(key.to_owned(), value.clone());
+        value
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fixed-size cache for memoized recursion over dense `usize` keys.
+#[derive(Debug)]
+pub struct ArrayMemo<V> {
+    cache: Vec<Option<V>>,
+}
+
+impl<V: Clone> ArrayMemo<V> {
+    #[must_use]
+    pub fn new(len: usize) -> Self {
+        Self {
+            cache: vec![None; len],
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&V> {
+        self.cache.get(index).and_then(Option::as_ref)
+    }
+
+    /// Returns the cached value at `index`, or computes it with `f` and caches it.
+    ///
+    /// # Panics
+    /// if `index` is out of the cache bounds
+    pub fn get_or_compute(&mut self, index: usize, f: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(cached) = &self.cache[index] {
+            return cached.clone();
+        }
+
+        let value = f(self);
+        self.cache[index] = Some(value.clone());
+        value
+    }
+}
+
+/// Maps strings to dense ids, so they can be used as cheap memo keys.
+#[derive(Debug, Default)]
+pub struct Interner {
+    ids: HashMap<String, usize>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of `name`, allocating a new one on first sight.
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = self.names.len();
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    #[must_use]
+    pub fn resolve(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fibonacci(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        if n < 2 {
+            return n;
+        }
+        memo.get_or_compute(&n, |memo| fibonacci(n - 1, memo) + fibonacci(n - 2, memo))
+    }
+
+    #[test]
+    fn test_memo_recursion() {
+        let mut memo = Memo::new();
+        assert_eq!(fibonacci(90, &mut memo), 2_880_067_194_370_816_120);
+        assert_eq!(memo.len(), 89);
+    }
+
+    #[test]
+    fn test_memo_borrowed_key() {
+        let mut memo: Memo<String, usize> = Memo::new();
+        assert_eq!(memo.get_or_compute("abc", |_| 3), 3);
+        assert_eq!(memo.get_or_compute("abc", |_| unreachable!()), 3);
+        assert_eq!(memo.get("abc"), Some(&3));
+    }
+
+    #[test]
+    fn test_array_memo() {
+        fn staircase(n: usize, memo: &mut ArrayMemo<u64>) -> u64 {
+            if n <= 1 {
+                return 1;
+            }
+            memo.get_or_compute(n, |memo| staircase(n - 1, memo) + staircase(n - 2, memo))
+        }
+
+        let mut memo = ArrayMemo::new(51);
+        assert_eq!(staircase(50, &mut memo), 20_365_011_074);
+        assert_eq!(memo.get(50), Some(&20_365_011_074));
+        assert_eq!(memo.get(0), None);
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut names = Interner::new();
+        let a = names.intern("svr");
+        let b = names.intern("out");
+
+        assert_eq!(names.intern("svr"), a);
+        assert_ne!(a, b);
+        assert_eq!(names.resolve(b), "out");
+        assert_eq!(names.get("dac"), None);
+        assert_eq!(names.len(), 2);
+    }
+}
@@ -1,2 +1,11 @@
 pub mod coord;
+pub mod dlx;
+pub mod gf2;
 pub mod grid;
+pub mod ilp;
+pub mod memo;
+pub mod packing;
+pub mod polygon;
+pub mod region;
+pub mod rng;
+pub mod trie;
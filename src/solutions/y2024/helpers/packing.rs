@@ -0,0 +1,657 @@
+use std::fmt;
+
+use crate::solutions::y2024::helpers::coord::Coord;
+use crate::solutions::y2024::helpers::dlx::Dlx;
+use crate::solutions::y2024::helpers::gf2::BitVec;
+
+/// Cells sorted in row order, moved so the smallest x and y are zero.
+fn normalize(mut cells: Vec<Coord>) -> Vec<Coord> {
+    let min_x = cells.iter().map(|c| c.x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.y).min().unwrap_or(0);
+    for c in &mut cells {
+        *c -= Coord::new(min_x, min_y);
+    }
+    cells.sort_unstable_by_key(|c| (c.y, c.x));
+    cells
+}
+
+/// Width and height of the box around normalized cells.
+fn bounds(cells: &[Coord]) -> (usize, usize) {
+    let size = |f: fn(&Coord) -> i32| usize::try_from(cells.iter().map(f).max().unwrap_or(-1) + 1);
+    (size(|c| c.x).unwrap(), size(|c| c.y).unwrap())
+}
+
+/// A polyomino with its distinct rotations and reflections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    orientations: Vec<Vec<Coord>>,
+}
+
+impl Shape {
+    /// Reads a shape drawn with `#` for its cells.
+    ///
+    /// # Panics
+    /// if the drawing has no cells
+    #[must_use]
+    pub fn parse(drawing: &str) -> Self {
+        let cells: Vec<Coord> = drawing
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.trim()
+                    .chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| {
+                        Coord::new(i32::try_from(x).unwrap(), i32::try_from(y).unwrap())
+                    })
+            })
+            .collect();
+        assert!(!cells.is_empty(), "Shape has no cells");
+
+        let mut orientations: Vec<Vec<Coord>> = Vec::new();
+        let mut current = cells;
+        for _ in 0..4 {
+            let mirrored = current.iter().map(|c| Coord::new(-c.x, c.y)).collect();
+            for cells in [current.clone(), mirrored] {
+                let cells = normalize(cells);
+                if !orientations.contains(&cells) {
+                    orientations.push(cells);
+                }
+            }
+            current = current.iter().map(|c| Coord::new(-c.y, c.x)).collect();
+        }
+        Self { orientations }
+    }
+
+    #[must_use]
+    pub fn area(&self) -> usize {
+        self.orientations[0].len()
+    }
+
+    /// Distinct orientations, each normalized to the top-left corner.
+    #[must_use]
+    pub fn orientations(&self) -> &[Vec<Coord>] {
+        &self.orientations
+    }
+
+    /// Side of the smallest square holding the shape.
+    fn block(&self) -> usize {
+        let (width, height) = bounds(&self.orientations[0]);
+        width.max(height)
+    }
+
+    /// Difference between the cells of each colour of a checkerboard, the
+    /// same wherever the shape is put.
+    fn imbalance(&self) -> usize {
+        let even = self.orientations[0]
+            .iter()
+            .filter(|c| (c.x + c.y) % 2 == 0)
+            .count();
+        even.abs_diff(self.area() - even)
+    }
+}
+
+/// A region to fill, with how many presents of each shape go in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub width: usize,
+    pub height: usize,
+    pub counts: Vec<usize>,
+}
+
+impl Region {
+    #[must_use]
+    pub fn presents(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    fn cells(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// A present put in a region.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub shape: usize,
+    pub cells: Vec<Coord>,
+}
+
+/// Presents placed in a region, drawn with a letter per present.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+    pub placements: Vec<Placement>,
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut grid = vec![vec!['.'; self.width]; self.height];
+        for (letter, placement) in (b'A'..=b'Z').cycle().zip(&self.placements) {
+            for c in &placement.cells {
+                grid[usize::try_from(c.y).unwrap()][usize::try_from(c.x).unwrap()] =
+                    char::from(letter);
+            }
+        }
+        let rows: Vec<String> = grid.into_iter().map(String::from_iter).collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+/// Why a region could not be packed or ruled out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackError {
+    /// The region counts presents of more shapes than the packer knows.
+    UnknownShapes { counted: usize, known: usize },
+    /// The exact cover would need more than [`MAX_ROWS`] rows.
+    TooLarge { rows: usize },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownShapes { counted, known } => {
+                write!(f, "counts {counted} shapes but only {known} are known")
+            }
+            Self::TooLarge { rows } => {
+                write!(f, "exact search needs {rows} rows, more than {MAX_ROWS}")
+            }
+        }
+    }
+}
+
+/// Largest exact cover, in rows, that `pack_exact` builds.
+pub const MAX_ROWS: usize = 200_000;
+
+/// What a row of the exact cover stands for.
+#[derive(Clone, Copy, Debug)]
+enum Choice {
+    /// A cell left empty.
+    Hole,
+    /// Copy `instance` of `shape`, put at its `placement`-th position.
+    Present {
+        shape: usize,
+        instance: usize,
+        placement: usize,
+    },
+}
+
+/// Packs presents of a fixed set of shapes into regions.
+#[derive(Clone, Debug)]
+pub struct Packer<'a> {
+    shapes: &'a [Shape],
+}
+
+impl<'a> Packer<'a> {
+    #[must_use]
+    pub const fn new(shapes: &'a [Shape]) -> Self {
+        Self { shapes }
+    }
+
+    fn area(&self, region: &Region) -> usize {
+        self.used(region)
+            .map(|(shape, count)| shape.area() * count)
+            .sum()
+    }
+
+    /// Checks the region only counts shapes the packer knows.
+    ///
+    /// # Errors
+    /// if the region has more counts than there are shapes
+    pub fn check(&self, region: &Region) -> Result<(), PackError> {
+        if region.counts.len() > self.shapes.len() {
+            return Err(PackError::UnknownShapes {
+                counted: region.counts.len(),
+                known: self.shapes.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Shapes with at least one present in the region, with their counts.
+    ///
+    /// # Panics
+    /// if the region fails [`Packer::check`]
+    fn used<'r>(
+        &self,
+        region: &'r Region,
+    ) -> impl Iterator<Item = (&'a Shape, usize)> + use<'a, 'r> {
+        assert!(
+            region.counts.len() <= self.shapes.len(),
+            "Region counts {} shapes but only {} are known",
+            region.counts.len(),
+            self.shapes.len()
+        );
+        self.shapes
+            .iter()
+            .zip(region.counts.iter().copied())
+            .filter(|&(_, count)| count > 0)
+    }
+
+    /// Whether the presents cover no more cells than the region has.
+    ///
+    /// # Panics
+    /// if the region fails [`Packer::check`]
+    #[must_use]
+    pub fn area_fits(&self, region: &Region) -> bool {
+        self.area(region) <= region.cells()
+    }
+
+    /// Whether the presents can take few enough cells of each colour of a
+    /// checkerboard, each being placed with its surplus on either colour.
+    ///
+    /// # Panics
+    /// if the region fails [`Packer::check`]
+    #[must_use]
+    pub fn parity_fits(&self, region: &Region) -> bool {
+        let cells = region.cells();
+        let (black, white) = (cells.div_ceil(2), cells / 2);
+        let area = self.area(region);
+        if area > cells {
+            return false;
+        }
+
+        // Reachable sums of the signed surpluses, offset by their total.
+        let surpluses: Vec<usize> = self
+            .used(region)
+            .flat_map(|(shape, count)| std::iter::repeat_n(shape.imbalance(), count))
+            .filter(|&d| d > 0)
+            .collect();
+        let total: usize = surpluses.iter().sum();
+        let mut reachable = vec![false; 2 * total + 1];
+        reachable[total] = true;
+        for d in surpluses {
+            let mut next = vec![false; reachable.len()];
+            for (s, _) in reachable.iter().enumerate().filter(|&(_, &r)| r) {
+                if s >= d {
+                    next[s - d] = true;
+                }
+                if s + d < next.len() {
+                    next[s + d] = true;
+                }
+            }
+            reachable = next;
+        }
+
+        // Black cells used are `(area + sum) / 2`, white ones `(area - sum) / 2`.
+        reachable
+            .iter()
+            .enumerate()
+            .any(|(s, &r)| r && area + s <= 2 * black + total && area + total <= 2 * white + s)
+    }
+
+    /// Whether each present gets a square block of its own, which always
+    /// fits but wastes space.
+    ///
+    /// # Panics
+    /// if the region fails [`Packer::check`]
+    #[must_use]
+    pub fn block_fits(&self, region: &Region) -> bool {
+        self.block_layout(region).is_some()
+    }
+
+    /// Puts the presents in square blocks, row by row.
+    ///
+    /// # Panics
+    /// if the region fails [`Packer::check`]
+    #[must_use]
+    pub fn block_layout(&self, region: &Region) -> Option<Layout> {
+        let side = self.used(region).map(|(shape, _)| shape.block()).max();
+        let Some(side) = side else {
+            return Some(Layout {
+                width: region.width,
+                height: region.height,
+                placements: Vec::new(),
+            });
+        };
+        let per_row = region.width / side;
+        if per_row * (region.height / side) < region.presents() {
+            return None;
+        }
+
+        let placements = region
+            .counts
+            .iter()
+            .enumerate()
+            .flat_map(|(shape, &count)| std::iter::repeat_n(shape, count))
+            .enumerate()
+            .map(|(i, shape)| {
+                let corner = Coord::new(
+                    i32::try_from(i % per_row * side).unwrap(),
+                    i32::try_from(i / per_row * side).unwrap(),
+                );
+                Placement {
+                    shape,
+                    cells: self.shapes[shape].orientations[0]
+                        .iter()
+                        .map(|&c| c + corner)
+                        .collect(),
+                }
+            })
+            .collect();
+        Some(Layout {
+            width: region.width,
+            height: region.height,
+            placements,
+        })
+    }
+
+    /// Every position of every orientation of `shape` in the region, as cell
+    /// masks and cells.
+    fn placements(&self, shape: &Shape, region: &Region) -> Vec<(BitVec, Vec<Coord>)> {
+        let mut placements = Vec::new();
+        for cells in &shape.orientations {
+            let (width, height) = bounds(cells);
+            if width > region.width || height > region.height {
+                continue;
+            }
+            for y in 0..=region.height - height {
+                for x in 0..=region.width - width {
+                    let corner = Coord::new(i32::try_from(x).unwrap(), i32::try_from(y).unwrap());
+                    let moved: Vec<Coord> = cells.iter().map(|&c| c + corner).collect();
+                    let mut mask = BitVec::zeros(region.cells());
+                    for c in &moved {
+                        let (x, y) = (usize::try_from(c.x).unwrap(), usize::try_from(c.y).unwrap());
+                        mask.set(y * region.width + x, true);
+                    }
+                    placements.push((mask, moved));
+                }
+            }
+        }
+        placements
+    }
+
+    /// Searches for a packing with dancing links, the cells being columns
+    /// that a present or a hole covers, holes being limited to the spare
+    /// area. Copies of a shape are placed in order to skip permutations.
+    ///
+    /// # Errors
+    /// if the region fails [`Packer::check`] or the search would need more
+    /// than [`MAX_ROWS`] rows
+    pub fn pack_exact(&self, region: &Region) -> Result<Option<Layout>, PackError> {
+        self.check(region)?;
+        if !self.parity_fits(region) {
+            return Ok(None);
+        }
+        let slack = region.cells() - self.area(region);
+        let placements: Vec<_> = self
+            .shapes
+            .iter()
+            .zip(&region.counts)
+            .map(|(shape, &count)| {
+                if count > 0 {
+                    self.placements(shape, region)
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        // Cells no present can reach must be holes.
+        let mut reachable = BitVec::zeros(region.cells());
+        for (mask, _) in placements.iter().flatten() {
+            reachable |= mask;
+        }
+        if region.cells() - reachable.count_ones() > slack {
+            return Ok(None);
+        }
+
+        let rows = region
+            .counts
+            .iter()
+            .zip(&placements)
+            .map(|(&count, placements)| count * placements.len())
+            .sum::<usize>()
+            + region.cells();
+        if rows > MAX_ROWS {
+            return Err(PackError::TooLarge { rows });
+        }
+
+        let presents = region.presents();
+        let mut dlx = Dlx::new(presents + region.cells(), 0);
+        let mut choices = Vec::new();
+        let mut column = 0;
+        for (shape, &count) in region.counts.iter().enumerate() {
+            for instance in 0..count {
+                for (placement, (mask, _)) in placements[shape].iter().enumerate() {
+                    let row: Vec<usize> = std::iter::once(column)
+                        .chain(mask.ones().map(|cell| presents + cell))
+                        .collect();
+                    dlx.add_row(&row);
+                    choices.push(Choice::Present {
+                        shape,
+                        instance,
+                        placement,
+                    });
+                }
+                column += 1;
+            }
+        }
+        for cell in 0..region.cells() {
+            dlx.add_row(&[presents + cell]);
+            choices.push(Choice::Hole);
+        }
+
+        let mut accept = |row: usize, chosen: &[usize]| match choices[row] {
+            Choice::Hole => {
+                chosen
+                    .iter()
+                    .filter(|&&r| matches!(choices[r], Choice::Hole))
+                    .count()
+                    < slack
+            }
+            Choice::Present {
+                shape,
+                instance,
+                placement,
+            } => chosen.iter().all(|&r| match choices[r] {
+                Choice::Present {
+                    shape: s,
+                    instance: i,
+                    placement: p,
+                } if s == shape => (i < instance) == (p < placement),
+                _ => true,
+            }),
+        };
+        let mut solution = None;
+        dlx.search(&mut accept, &mut |rows| {
+            solution = Some(rows.to_vec());
+            true
+        });
+
+        let Some(solution) = solution else {
+            return Ok(None);
+        };
+        let mut placed: Vec<_> = solution
+            .into_iter()
+            .filter_map(|row| match choices[row] {
+                Choice::Present {
+                    shape, placement, ..
+                } => Some(Placement {
+                    shape,
+                    cells: placements[shape][placement].1.clone(),
+                }),
+                Choice::Hole => None,
+            })
+            .collect();
+        placed.sort_unstable_by_key(|p| (p.cells[0].y, p.cells[0].x));
+        Ok(Some(Layout {
+            width: region.width,
+            height: region.height,
+            placements: placed,
+        }))
+    }
+
+    /// Packs the presents, trying the checks and the block layout before the
+    /// exact search.
+    ///
+    /// # Errors
+    /// as [`Packer::pack_exact`]
+    pub fn pack(&self, region: &Region) -> Result<Option<Layout>, PackError> {
+        self.check(region)?;
+        if !self.area_fits(region) {
+            return Ok(None);
+        }
+        match self.block_layout(region) {
+            Some(layout) => Ok(Some(layout)),
+            None => self.pack_exact(region),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solutions::y2024::helpers::rng::SplitMix64;
+
+    const SHAPES: [&str; 6] = [
+        "###\n##.\n##.",
+        "###\n##.\n.##",
+        ".##\n###\n##.",
+        "###\n.##\n..#",
+        "###\n#..\n###",
+        "###\n.#.\n###",
+    ];
+
+    fn shapes() -> Vec<Shape> {
+        SHAPES.iter().map(|s| Shape::parse(s)).collect()
+    }
+
+    fn region(width: usize, height: usize, counts: &[usize]) -> Region {
+        Region {
+            width,
+            height,
+            counts: counts.to_vec(),
+        }
+    }
+
+    /// Checks the layout places the region's presents without overlap.
+    fn check(shapes: &[Shape], region: &Region, layout: &Layout) {
+        let mut used = BitVec::zeros(region.cells());
+        let mut counts = vec![0; region.counts.len()];
+        for placement in &layout.placements {
+            counts[placement.shape] += 1;
+            let cells = normalize(placement.cells.clone());
+            assert!(shapes[placement.shape].orientations.contains(&cells));
+            for c in &placement.cells {
+                assert!((0..region.width).contains(&usize::try_from(c.x).unwrap()));
+                assert!((0..region.height).contains(&usize::try_from(c.y).unwrap()));
+                let cell =
+                    usize::try_from(c.y).unwrap() * region.width + usize::try_from(c.x).unwrap();
+                assert!(!used.get(cell), "overlap at {c:?}");
+                used.set(cell, true);
+            }
+        }
+        assert_eq!(counts, region.counts);
+    }
+
+    #[test]
+    fn test_shape() {
+        let shapes = shapes();
+        assert_eq!(shapes[4].area(), 7);
+        assert_eq!(shapes[4].orientations().len(), 4);
+        assert_eq!(shapes[5].orientations().len(), 2);
+        assert_eq!(shapes[0].orientations().len(), 8);
+        assert_eq!(Shape::parse("##\n##").orientations().len(), 1);
+        assert_eq!(Shape::parse("#.\n##").imbalance(), 1);
+    }
+
+    #[test]
+    fn test_example() {
+        let shapes = shapes();
+        let packer = Packer::new(&shapes);
+
+        let small = region(4, 4, &[0, 0, 0, 0, 2]);
+        let layout = packer.pack(&small).unwrap().unwrap();
+        check(&shapes, &small, &layout);
+        assert_eq!(layout.to_string().matches('.').count(), 2);
+
+        let fits = region(12, 5, &[1, 0, 1, 0, 2, 2]);
+        assert!(!packer.block_fits(&fits));
+        check(&shapes, &fits, &packer.pack(&fits).unwrap().unwrap());
+
+        let crowded = region(12, 5, &[1, 0, 1, 0, 3, 2]);
+        assert!(packer.area_fits(&crowded));
+        assert_eq!(packer.pack(&crowded), Ok(None));
+    }
+
+    #[test]
+    fn test_pruning() {
+        // A T covers three cells of one colour and one of the other, so
+        // three of them cannot split a 3x4 board evenly.
+        let tees = [Shape::parse("###\n.#.")];
+        let packer = Packer::new(&tees);
+        assert!(packer.area_fits(&region(3, 4, &[3])));
+        assert!(!packer.parity_fits(&region(3, 4, &[3])));
+        assert_eq!(packer.pack_exact(&region(3, 4, &[3])), Ok(None));
+        assert!(packer.parity_fits(&region(4, 4, &[4])));
+        check(
+            &tees,
+            &region(4, 4, &[4]),
+            &packer.pack_exact(&region(4, 4, &[4])).unwrap().unwrap(),
+        );
+
+        // A region narrower than the shape has no placements at all.
+        let bar = [Shape::parse("####")];
+        assert_eq!(Packer::new(&bar).pack(&region(3, 3, &[1])), Ok(None));
+        assert!(Packer::new(&bar)
+            .pack(&region(3, 3, &[0]))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_errors() {
+        let shapes = shapes();
+        let packer = Packer::new(&shapes);
+        assert_eq!(
+            packer.pack(&region(4, 4, &[0, 0, 0, 0, 0, 0, 1])),
+            Err(PackError::UnknownShapes {
+                counted: 7,
+                known: 6
+            })
+        );
+
+        // Too tight for blocks, and every copy gets a row per position.
+        let crowded = region(30, 30, &[30, 30, 30, 30, 0, 0]);
+        assert!(!packer.block_fits(&crowded));
+        assert!(matches!(
+            packer.pack(&crowded),
+            Err(PackError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_heuristic_against_exact() {
+        let shapes = shapes();
+        let packer = Packer::new(&shapes);
+        let mut rng = SplitMix64::new(12);
+        let (mut blocks, mut exact) = (0, 0);
+        for _ in 0..60 {
+            let (width, height) = (3 + rng.below(5) as usize, 3 + rng.below(4) as usize);
+            let mut counts = vec![0; 6];
+            for _ in 0..rng.below(4) {
+                counts[rng.below(6) as usize] += 1;
+            }
+            let region = region(width, height, &counts);
+
+            let packed = packer.pack_exact(&region).unwrap();
+            if let Some(layout) = &packed {
+                check(&shapes, &region, layout);
+                exact += 1;
+            }
+            if packer.block_fits(&region) {
+                blocks += 1;
+                assert!(packed.is_some(), "{region:?}");
+                check(&shapes, &region, &packer.block_layout(&region).unwrap());
+            }
+            if !packer.area_fits(&region) || !packer.parity_fits(&region) {
+                assert!(packed.is_none(), "{region:?}");
+            }
+        }
+        // The heuristic misses some packings the exact search finds.
+        assert!(blocks < exact);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// A tile position.
+pub type Tile = (i64, i64);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    /// Two consecutive vertices share neither their row nor their column.
+    Diagonal { from: Tile, to: Tile },
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Diagonal { from, to } => write!(
+                f,
+                "edge from {},{} to {},{} is not horizontal or vertical",
+                from.0, from.1, to.0, to.1
+            ),
+        }
+    }
+}
+
+/// An axis-aligned rectangle of tiles, bounds included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub min: Tile,
+    pub max: Tile,
+}
+
+impl Rect {
+    #[must_use]
+    pub fn spanning(a: Tile, b: Tile) -> Self {
+        Self {
+            min: (a.0.min(b.0), a.1.min(b.1)),
+            max: (a.0.max(b.0), a.1.max(b.1)),
+        }
+    }
+
+    #[must_use]
+    pub const fn area(&self) -> u64 {
+        (self.max.0.abs_diff(self.min.0) + 1) * (self.max.1.abs_diff(self.min.1) + 1)
+    }
+}
+
+/// Coordinates of one axis compressed into bands: each vertex coordinate on
+/// its own, the gaps between them, and the outside on both ends.
+#[derive(Clone, Debug)]
+struct Axis {
+    starts: Vec<i64>,
+}
+
+impl Axis {
+    fn new(coords: impl Iterator<Item = i64>) -> Self {
+        let mut starts = vec![i64::MIN];
+        for c in coords {
+            starts.extend([c, c + 1]);
+        }
+        starts.sort_unstable();
+        starts.dedup();
+        Self { starts }
+    }
+
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    fn band(&self, c: i64) -> usize {
+        self.starts.partition_point(|&s| s <= c) - 1
+    }
+
+    /// Tiles in a band, which is finite for every band but the last.
+    fn width(&self, band: usize) -> u64 {
+        self.starts[band + 1].abs_diff(self.starts[band])
+    }
+}
+
+/// A rectilinear polygon through tile centers, covering its boundary and
+/// inside tiles.
+///
+/// Tiles are compressed into bands so that every compressed cell is either
+/// wholly inside or wholly outside, with prefix sums of the inside cells.
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    vertices: Vec<Tile>,
+    xs: Axis,
+    ys: Axis,
+    inside: Vec<bool>,
+    /// Inside cells above and left of each cell, one extra row and column.
+    prefix: Vec<usize>,
+}
+
+impl Polygon {
+    /// # Errors
+    /// if two consecutive vertices are not aligned
+    pub fn new(vertices: Vec<Tile>) -> Result<Self, PolygonError> {
+        let n = vertices.len();
+        for i in 0..n {
+            let (from, to) = (vertices[i], vertices[(i + 1) % n]);
+            if from.0 != to.0 && from.1 != to.1 {
+                return Err(PolygonError::Diagonal { from, to });
+            }
+        }
+
+        let xs = Axis::new(vertices.iter().map(|v| v.0));
+        let ys = Axis::new(vertices.iter().map(|v| v.1));
+        let mut polygon = Self {
+            inside: vec![false; xs.len() * ys.len()],
+            prefix: vec![0; (xs.len() + 1) * (ys.len() + 1)],
+            vertices,
+            xs,
+            ys,
+        };
+        polygon.fill();
+        Ok(polygon)
+    }
+
+    /// # Errors
+    /// if two consecutive vertices are not aligned
+    pub fn parse(input: &str) -> Result<Self, PolygonError> {
+        let vertices = input
+            .lines()
+            .filter_map(|line| {
+                let (x, y) = line.trim().split_once(',')?;
+                Some((x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect();
+        Self::new(vertices)
+    }
+
+    #[must_use]
+    pub fn vertices(&self) -> &[Tile] {
+        &self.vertices
+    }
+
+    /// Marks the boundary cells, floods the outside from the corner and
+    /// counts what is left.
+    fn fill(&mut self) {
+        let (w, h) = (self.xs.len(), self.ys.len());
+        let mut boundary = vec![false; w * h];
+        let n = self.vertices.len();
+        for i in 0..n {
+            let edge = Rect::spanning(self.vertices[i], self.vertices[(i + 1) % n]);
+            for y in self.ys.band(edge.min.1)..=self.ys.band(edge.max.1) {
+                for x in self.xs.band(edge.min.0)..=self.xs.band(edge.max.0) {
+                    boundary[y * w + x] = true;
+                }
+            }
+        }
+
+        let mut outside = vec![false; w * h];
+        let mut todo = VecDeque::from([0]);
+        outside[0] = true;
+        while let Some(cell) = todo.pop_front() {
+            let (x, y) = (cell % w, cell / w);
+            let neighbours = [
+                (x > 0).then(|| cell - 1),
+                (x + 1 < w).then(|| cell + 1),
+                (y > 0).then(|| cell - w),
+                (y + 1 < h).then(|| cell + w),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if !boundary[next] && !outside[next] {
+                    outside[next] = true;
+                    todo.push_back(next);
+                }
+            }
+        }
+
+        for y in 0..h {
+            for x in 0..w {
+                let inside = n > 0 && !outside[y * w + x];
+                self.inside[y * w + x] = inside;
+                self.prefix[(y + 1) * (w + 1) + x + 1] = usize::from(inside)
+                    + self.prefix[y * (w + 1) + x + 1]
+                    + self.prefix[(y + 1) * (w + 1) + x]
+                    - self.prefix[y * (w + 1) + x];
+            }
+        }
+    }
+
+    /// Returns true if the tile is on the boundary or inside.
+    #[must_use]
+    pub fn contains(&self, tile: Tile) -> bool {
+        self.inside[self.ys.band(tile.1) * self.xs.len() + self.xs.band(tile.0)]
+    }
+
+    /// Returns true if every tile of the rectangle is covered.
+    #[must_use]
+    pub fn contains_rect(&self, rect: Rect) -> bool {
+        let (x1, x2) = (self.xs.band(rect.min.0), self.xs.band(rect.max.0) + 1);
+        let (y1, y2) = (self.ys.band(rect.min.1), self.ys.band(rect.max.1) + 1);
+        let w = self.xs.len() + 1;
+        let covered = self.prefix[y2 * w + x2] + self.prefix[y1 * w + x1]
+            - self.prefix[y1 * w + x2]
+            - self.prefix[y2 * w + x1];
+        covered == (x2 - x1) * (y2 - y1)
+    }
+
+    /// Number of tiles covered.
+    #[must_use]
+    pub fn area(&self) -> u64 {
+        let w = self.xs.len();
+        (0..self.inside.len())
+            .filter(|&cell| self.inside[cell])
+            .map(|cell| self.xs.width(cell % w) * self.ys.width(cell / w))
+            .sum()
+    }
+
+    /// Finds the largest rectangle with opposite corners on two vertices,
+    /// only among those covered by the polygon if `covered`.
+    #[must_use]
+    pub fn largest_rectangle(&self, covered: bool) -> Option<Rect> {
+        let mut best: Option<Rect> = None;
+        for (i, &a) in self.vertices.iter().enumerate() {
+            for &b in &self.vertices[i + 1..] {
+                let rect = Rect::spanning(a, b);
+                if best.is_none_or(|best| rect.area() > best.area())
+                    && (!covered || self.contains_rect(rect))
+                {
+                    best = Some(rect);
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "7,1
+11,1
+11,7
+9,7
+9,5
+2,5
+2,3
+7,3";
+
+    #[test]
+    fn test_polygon() {
+        let polygon = Polygon::parse(EXAMPLE).unwrap();
+        assert_eq!(polygon.area(), 46);
+        assert!(polygon.contains((2, 4)));
+        assert!(polygon.contains((8, 2)));
+        assert!(!polygon.contains((5, 2)));
+        assert!(!polygon.contains((-100, 3)));
+        assert_eq!(
+            polygon.largest_rectangle(true),
+            Some(Rect {
+                min: (2, 3),
+                max: (9, 5)
+            })
+        );
+        assert!(!polygon.contains_rect(Rect::spanning((2, 5), (11, 1))));
+    }
+
+    #[test]
+    fn test_gap_between_vertices() {
+        // A U shape whose arms leave a one tile wide gap.
+        let polygon = Polygon::parse("0,0\n2,0\n2,5\n4,5\n4,0\n6,0\n6,6\n0,6").unwrap();
+        assert!(!polygon.contains((3, 0)));
+        assert!(polygon.contains((3, 5)));
+        assert_eq!(polygon.area(), 7 * 7 - 5);
+    }
+
+    #[test]
+    fn test_l_shape() {
+        // The same L drawn both ways round, and with a vertex mid-edge.
+        for input in [
+            "0,0\n4,0\n4,2\n2,2\n2,4\n0,4",
+            "0,4\n2,4\n2,2\n4,2\n4,0\n0,0",
+            "0,0\n2,0\n4,0\n4,2\n2,2\n2,4\n0,4",
+        ] {
+            let polygon = Polygon::parse(input).unwrap();
+            assert_eq!(polygon.area(), 25 - 4, "{input}");
+            // Tiles on the inner corner's edges are covered, beyond it not.
+            assert!(polygon.contains((2, 3)));
+            assert!(polygon.contains((3, 2)));
+            assert!(!polygon.contains((3, 3)));
+            assert!(polygon.contains_rect(Rect::spanning((0, 0), (2, 4))));
+            assert!(!polygon.contains_rect(Rect::spanning((0, 0), (4, 4))));
+            // Either arm is a largest covered rectangle.
+            let arm = polygon.largest_rectangle(true).unwrap();
+            assert_eq!(arm.area(), 15);
+            assert_eq!(
+                polygon.largest_rectangle(false),
+                Some(Rect::spanning((0, 0), (4, 4)))
+            );
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+
+use crate::solutions::y2024::helpers::grid::{Coord, Grid};
+
+const DIRECTIONS: [Coord; 4] = [Coord::UP, Coord::RIGHT, Coord::DOWN, Coord::LEFT];
+
+/// A group of cells with the same label, connected through their edges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub label: char,
+    /// Cells in row order.
+    pub cells: Vec<Coord>,
+    /// Cell edges shared with other regions or the outside.
+    pub perimeter: usize,
+    /// Straight runs of the perimeter, one per corner.
+    pub sides: usize,
+    /// Top-left and bottom-right cells of the bounding box.
+    pub min: Coord,
+    pub max: Coord,
+}
+
+impl Region {
+    #[must_use]
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// The regions of a grid, with the region of every cell.
+#[derive(Clone, Debug)]
+pub struct Regions {
+    width: i32,
+    height: i32,
+    ids: Vec<usize>,
+    regions: Vec<Region>,
+    /// Regions sharing an edge with each region.
+    neighbours: Vec<Vec<usize>>,
+    /// Whether each region touches the grid border.
+    border: Vec<bool>,
+}
+
+impl Regions {
+    /// Splits the grid into regions, numbered in the row order of their
+    /// first cell.
+    #[must_use]
+    pub fn new(grid: &Grid) -> Self {
+        let mut regions = Self {
+            width: grid.width(),
+            height: grid.height(),
+            ids: vec![usize::MAX; grid.iter().count()],
+            regions: Vec::new(),
+            neighbours: Vec::new(),
+            border: Vec::new(),
+        };
+        for (pos, &label) in grid.iter() {
+            if regions.id(pos) == Some(usize::MAX) {
+                regions.fill(grid, pos, label);
+            }
+        }
+        regions.neighbours = vec![Vec::new(); regions.regions.len()];
+        regions.border = vec![false; regions.regions.len()];
+        for id in 0..regions.regions.len() {
+            regions.measure(id);
+        }
+        regions
+    }
+
+    #[must_use]
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Returns the region covering `pos`, if inside the grid.
+    #[must_use]
+    pub fn region_at(&self, pos: Coord) -> Option<&Region> {
+        self.id(pos).map(|id| &self.regions[id])
+    }
+
+    fn id(&self, pos: Coord) -> Option<usize> {
+        ((0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y))
+            .then(|| self.ids[usize::try_from(pos.y * self.width + pos.x).unwrap()])
+    }
+
+    fn set_id(&mut self, pos: Coord, id: usize) {
+        self.ids[usize::try_from(pos.y * self.width + pos.x).unwrap()] = id;
+    }
+
+    fn fill(&mut self, grid: &Grid, start: Coord, label: char) {
+        let id = self.regions.len();
+        let mut cells = vec![start];
+        let mut queue = VecDeque::from([start]);
+        self.set_id(start, id);
+
+        while let Some(pos) = queue.pop_front() {
+            for next in grid.iter_directions(pos) {
+                if grid[next] == label && self.id(next) == Some(usize::MAX) {
+                    self.set_id(next, id);
+                    cells.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        cells.sort_unstable_by_key(|pos| (pos.y, pos.x));
+        self.regions.push(Region {
+            label,
+            min: Coord::new(cells.iter().map(|pos| pos.x).min().unwrap(), cells[0].y),
+            max: Coord::new(
+                cells.iter().map(|pos| pos.x).max().unwrap(),
+                cells[cells.len() - 1].y,
+            ),
+            cells,
+            perimeter: 0,
+            sides: 0,
+        });
+    }
+
+    /// Counts the perimeter edges and the corners, convex or concave, and
+    /// notes the neighbouring regions.
+    fn measure(&mut self, id: usize) {
+        let (mut perimeter, mut sides) = (0, 0);
+        let (mut neighbours, mut border) = (Vec::new(), false);
+        for &pos in &self.regions[id].cells {
+            let same = |d: Coord| self.id(pos + d) == Some(id);
+            for (i, &a) in DIRECTIONS.iter().enumerate() {
+                let b = DIRECTIONS[(i + 1) % 4];
+                perimeter += usize::from(!same(a));
+                if (!same(a) && !same(b)) || (same(a) && same(b) && !same(a + b)) {
+                    sides += 1;
+                }
+                match self.id(pos + a) {
+                    None => border = true,
+                    Some(other) if other != id => neighbours.push(other),
+                    Some(_) => (),
+                }
+            }
+        }
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        self.regions[id].perimeter = perimeter;
+        self.regions[id].sides = sides;
+        self.neighbours[id] = neighbours;
+        self.border[id] = border;
+    }
+
+    /// Marks the regions reachable from `stack` without entering a region
+    /// already marked.
+    fn flood(&self, reached: &mut [bool], mut stack: Vec<usize>) {
+        while let Some(r) = stack.pop() {
+            for &n in &self.neighbours[r] {
+                if !std::mem::replace(&mut reached[n], true) {
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    /// Regions cut off from the grid border by region `id`, nested ones
+    /// included, with the regions not reached from the border marked.
+    fn cut_off(&self, id: usize) -> (Vec<usize>, Vec<bool>) {
+        let mut reached = vec![false; self.regions.len()];
+        reached[id] = true;
+        let stack: Vec<usize> = (0..self.regions.len())
+            .filter(|&r| self.border[r] && r != id)
+            .collect();
+        stack.iter().for_each(|&r| reached[r] = true);
+        self.flood(&mut reached, stack);
+        let enclosed = (0..self.regions.len()).filter(|&r| !reached[r]).collect();
+        (enclosed, reached)
+    }
+
+    /// Indices of the regions inside the holes of region `id`, nested ones
+    /// included. Takes time linear in the number of regions.
+    #[must_use]
+    pub fn enclosed(&self, id: usize) -> Vec<usize> {
+        self.cut_off(id).0
+    }
+
+    /// Separate areas surrounded by region `id`. Takes time linear in the
+    /// number of regions.
+    #[must_use]
+    pub fn holes(&self, id: usize) -> usize {
+        let (enclosed, mut reached) = self.cut_off(id);
+        let mut holes = 0;
+        for start in enclosed {
+            if !std::mem::replace(&mut reached[start], true) {
+                holes += 1;
+                self.flood(&mut reached, vec![start]);
+            }
+        }
+        holes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input: &str) -> Regions {
+        Regions::new(&Grid::parse(input))
+    }
+
+    #[test]
+    fn test_measures() {
+        let regions = parse("AAAA\nBBCD\nBBCC\nEEEC");
+        let summary: Vec<_> = regions
+            .regions()
+            .iter()
+            .map(|r| (r.label, r.area(), r.perimeter, r.sides))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ('A', 4, 10, 4),
+                ('B', 4, 8, 4),
+                ('C', 4, 10, 8),
+                ('D', 1, 4, 4),
+                ('E', 3, 8, 4),
+            ]
+        );
+
+        let c = regions.region_at(Coord::new(2, 1)).unwrap();
+        assert_eq!((c.min, c.max), (Coord::new(2, 1), Coord::new(3, 3)));
+        assert_eq!(regions.region_at(Coord::new(4, 0)), None);
+    }
+
+    #[test]
+    fn test_holes() {
+        let regions = parse("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO");
+        let outer = &regions.regions()[0];
+        assert_eq!((outer.area(), outer.perimeter, outer.sides), (21, 36, 20));
+        assert_eq!(regions.holes(0), 4);
+        assert_eq!(regions.enclosed(0), vec![1, 2, 3, 4]);
+        assert!(regions.enclosed(1).is_empty());
+    }
+
+    #[test]
+    fn test_nested_holes() {
+        let regions = parse("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA\nDDDDD");
+        assert_eq!(regions.regions().len(), 4);
+        assert_eq!((regions.holes(0), regions.enclosed(0)), (1, vec![1, 2]));
+        assert_eq!((regions.holes(1), regions.enclosed(1)), (1, vec![2]));
+        assert_eq!(regions.holes(2), 0);
+        assert!(regions.enclosed(3).is_empty());
+
+        // A cell surrounded by two regions is a hole of neither.
+        let corner = parse("XX.\nX.X\n.XX");
+        assert_eq!(corner.regions().len(), 5);
+        assert_eq!(corner.holes(0), 0);
+    }
+}
@@ -0,0 +1,73 @@
+/// `SplitMix64` generator, for reproducible randomized checks.
+///
+/// Day 24 samples adder inputs with it at run time. Otherwise it only feeds
+/// the property tests of the ILP, GF(2), packing and k-d tree helpers; other
+/// solvers test targeted cases instead.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`, with a negligible bias for small `n`.
+    ///
+    /// # Panics
+    /// if `n` is zero
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Empty range");
+        self.next_u64() % n
+    }
+
+    /// Returns a value in `lo..=hi`.
+    ///
+    /// # Panics
+    /// if `lo > hi`
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        assert!(lo <= hi, "Empty range");
+        let span = hi.abs_diff(lo);
+        let offset = if span == u64::MAX {
+            self.next_u64()
+        } else {
+            self.below(span + 1)
+        };
+        lo.wrapping_add_unsigned(offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = SplitMix64::new(42);
+        let mut b = SplitMix64::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(SplitMix64::new(1).next_u64(), SplitMix64::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rng = SplitMix64::new(7);
+        for _ in 0..1000 {
+            assert!(rng.below(6) < 6);
+            assert!((-3..=3).contains(&rng.range(-3, 3)));
+        }
+        assert_eq!(rng.range(5, 5), 5);
+    }
+}
@@ -0,0 +1,111 @@
+/// Byte-wise prefix tree used to match a set of patterns against the start of a string.
+#[derive(Debug)]
+pub struct Trie {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: Vec<(u8, usize)>,
+    terminal: bool,
+}
+
+impl Trie {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node::default()],
+        }
+    }
+
+    pub fn insert(&mut self, pattern: &str) {
+        let mut node = 0;
+        for b in pattern.bytes() {
+            node = match self.child(node, b) {
+                Some(next) => next,
+                None => {
+                    let next = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.push((b, next));
+                    next
+                }
+            };
+        }
+        self.nodes[node].terminal = true;
+    }
+
+    #[must_use]
+    pub fn contains(&self, pattern: &str) -> bool {
+        let mut node = 0;
+        for b in pattern.bytes() {
+            match self.child(node, b) {
+                Some(next) => node = next,
+                None => return false,
+            }
+        }
+        self.nodes[node].terminal
+    }
+
+    /// Returns the lengths of all patterns that are a prefix of `text`, shortest first.
+    pub fn prefix_lengths<'a>(&'a self, text: &'a str) -> impl Iterator<Item = usize> + 'a {
+        text.bytes()
+            .scan(0, move |node, b| {
+                *node = self.child(*node, b)?;
+                Some(*node)
+            })
+            .enumerate()
+            .filter(|&(_, node)| self.nodes[node].terminal)
+            .map(|(i, _)| i + 1)
+    }
+
+    fn child(&self, node: usize, b: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|&&(c, _)| c == b)
+            .map(|&(_, next)| next)
+    }
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for Trie {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut trie = Self::new();
+        for pattern in iter {
+            trie.insert(pattern);
+        }
+        trie
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let trie: Trie = ["r", "wr", "bwu"].into_iter().collect();
+
+        assert!(trie.contains("r"));
+        assert!(trie.contains("bwu"));
+        assert!(!trie.contains("bw"));
+        assert!(!trie.contains("w"));
+        assert!(!trie.contains("rr"));
+    }
+
+    #[test]
+    fn test_prefix_lengths() {
+        let trie: Trie = ["b", "br", "bwu", "brw", "g"].into_iter().collect();
+
+        let lengths: Vec<_> = trie.prefix_lengths("brwrr").collect();
+        assert_eq!(lengths, vec![1, 2, 3]);
+
+        assert_eq!(trie.prefix_lengths("ubwu").count(), 0);
+        assert_eq!(trie.prefix_lengths("").count(), 0);
+    }
+}
@@ -1,90 +1,132 @@
-pub fn count_zero_visits(input: &str) -> String {
-    let mut position = 50; // Dial starts at 50
-    let mut count = 0; // Count how many times we visit 0
-
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+use crate::params::{ParamSpec, Params};
 
-        // Parse the rotation: first character is direction, rest is distance
-        let direction = line.chars().next().unwrap();
-        let distance: i32 = line[1..].parse().unwrap();
-
-        // Apply rotation
-        match direction {
-            'L' => {
-                // Rotate left (decrease)
-                position = (position - distance).rem_euclid(100);
-            }
-            'R' => {
-                // Rotate right (increase)
-                position = (position + distance).rem_euclid(100);
-            }
-            _ => panic!("Invalid direction: {}", direction),
-        }
+const SIZE: ParamSpec =
+    ParamSpec::new("size", "100", "Number of positions on the dial").number::<u64>();
+const START: ParamSpec =
+    ParamSpec::new("start", "50", "Position the dial starts at").number::<u64>();
+const TARGET: ParamSpec =
+    ParamSpec::new("target", "0", "Position whose visits are counted").number::<u64>();
+
+pub const PARAMS: &[ParamSpec] = &[SIZE, START, TARGET];
 
-        // Check if we're at 0
-        if position == 0 {
-            count += 1;
+/// A turn of the dial by some clicks, clockwise towards higher numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    pub clockwise: bool,
+    pub distance: u64,
+}
+
+impl Rotation {
+    /// # Panics
+    /// if the line is not `L` or `R` followed by a distance
+    #[must_use]
+    pub fn parse(line: &str) -> Self {
+        let (direction, distance) = line.split_at(1);
+        let clockwise = match direction {
+            "L" => false,
+            "R" => true,
+            _ => panic!("Invalid direction: {direction}"),
+        };
+        Self {
+            clockwise,
+            distance: distance.parse().expect("Invalid distance"),
         }
     }
-
-    count.to_string()
 }
 
-pub fn count_zero_visits_during_rotations(input: &str) -> String {
-    let mut position = 50i32; // Dial starts at 50
-    let mut count = 0u64; // Count how many times we visit 0 (during rotations and at end)
+/// A circular dial numbered `0..size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dial {
+    size: u64,
+    position: u64,
+}
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+impl Dial {
+    /// # Panics
+    /// if `size` is zero
+    #[must_use]
+    pub fn new(size: u64, start: u64) -> Self {
+        assert!(size > 0, "Dial size must be positive");
+        Self {
+            size,
+            position: start % size,
         }
+    }
 
-        // Parse the rotation: first character is direction, rest is distance
-        let direction = line.chars().next().unwrap();
-        let distance: i32 = line[1..].parse().unwrap();
-
-        // Count zeros during rotation
-        match direction {
-            'L' => {
-                // Rotate left (decrease)
-                // Simulate the rotation step by step to count zeros
-                for _ in 0..distance {
-                    position = (position - 1).rem_euclid(100);
-                    if position == 0 {
-                        count += 1;
-                    }
-                }
-            }
-            'R' => {
-                // Rotate right (increase)
-                // We pass through 0 when crossing from 99 to 0
-                // Simulate the rotation step by step to count zeros
-                for _ in 0..distance {
-                    position = (position + 1).rem_euclid(100);
-                    if position == 0 {
-                        count += 1;
-                    }
-                }
-            }
-            _ => panic!("Invalid direction: {}", direction),
-        }
+    #[must_use]
+    pub const fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Turns the dial and returns how many clicks land on `target`.
+    pub fn rotate(&mut self, rotation: Rotation, target: u64) -> u64 {
+        let (n, target) = (self.size, target % self.size);
+        // Clicks until the first landing on the target, a full turn if already there.
+        let gap = if rotation.clockwise {
+            (target + n - self.position) % n
+        } else {
+            (self.position + n - target) % n
+        };
+        let first = if gap == 0 { n } else { gap };
+
+        let step = rotation.distance % n;
+        self.position = if rotation.clockwise {
+            (self.position + step) % n
+        } else {
+            (self.position + n - step) % n
+        };
+
+        rotation
+            .distance
+            .checked_sub(first)
+            .map_or(0, |rest| rest / n + 1)
     }
+}
 
-    count.to_string()
+fn parse_input(input: &str) -> Vec<Rotation> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(Rotation::parse)
+        .collect()
+}
+
+fn dial(params: &Params) -> (Dial, u64) {
+    (
+        Dial::new(params.value(&SIZE), params.value(&START)),
+        params.value(&TARGET),
+    )
+}
+
+/// Counts the rotations that leave the dial on the target.
+pub fn count_zero_visits(input: &str, params: &Params) -> String {
+    let (mut dial, target) = dial(params);
+    parse_input(input)
+        .into_iter()
+        .filter(|&rotation| {
+            dial.rotate(rotation, target);
+            dial.position() == target % dial.size
+        })
+        .count()
+        .to_string()
+}
+
+/// Counts every click that lands on the target, during or at the end of a rotation.
+pub fn count_zero_visits_during_rotations(input: &str, params: &Params) -> String {
+    let (mut dial, target) = dial(params);
+    parse_input(input)
+        .into_iter()
+        .map(|rotation| dial.rotate(rotation, target))
+        .sum::<u64>()
+        .to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_example() {
-        let input = "L68
+    const EXAMPLE: &str = "L68
 L30
 R48
 L5
@@ -94,21 +136,55 @@ L1
 L99
 R14
 L82";
-        assert_eq!(count_zero_visits(input), "3");
+
+    #[test]
+    fn test_example() {
+        assert_eq!(count_zero_visits(EXAMPLE, &Params::default()), "3");
     }
 
     #[test]
     fn test_example_part2() {
-        let input = "L68
-L30
-R48
-L5
-R60
-L55
-L1
-L99
-R14
-L82";
-        assert_eq!(count_zero_visits_during_rotations(input), "6");
+        assert_eq!(
+            count_zero_visits_during_rotations(EXAMPLE, &Params::default()),
+            "6"
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let params = Params::new().with("target", 82);
+        assert_eq!(count_zero_visits(EXAMPLE, &params), "1");
+
+        // A large rotation passes the target once per full turn.
+        let params = Params::new().with("size", 10).with("start", 3);
+        assert_eq!(count_zero_visits_during_rotations("R1000", &params), "100");
+        assert_eq!(count_zero_visits_during_rotations("L3\nL10", &params), "2");
+    }
+
+    #[test]
+    fn test_rotate_edges() {
+        let turn = |clockwise, distance| Rotation {
+            clockwise,
+            distance,
+        };
+        let mut dial = Dial::new(10, 0);
+
+        // Starting on the target takes a full turn to land on it again.
+        assert_eq!(dial.rotate(turn(true, 0), 0), 0);
+        assert_eq!(dial.rotate(turn(true, 10), 0), 1);
+        assert_eq!(dial.rotate(turn(true, 9), 0), 0);
+        assert_eq!(dial.position(), 9);
+        // One click short of the target, then exactly onto it.
+        assert_eq!(dial.rotate(turn(true, 1), 0), 1);
+        // Counter-clockwise, wrapping past zero twice.
+        assert_eq!(dial.rotate(turn(false, 25), 0), 2);
+        assert_eq!(dial.position(), 5);
+
+        // A target beyond the dial wraps around it.
+        assert_eq!(dial.rotate(turn(false, 2), 13), 1);
+        assert_eq!(dial.position(), 3);
+
+        // On a single position dial every click lands on the target.
+        assert_eq!(Dial::new(1, 0).rotate(turn(true, 7), 0), 7);
     }
 }
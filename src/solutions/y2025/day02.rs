@@ -1,157 +1,261 @@
-pub fn sum_invalid_ids(input: &str) -> String {
-    let mut total = 0u64;
+use std::ops::RangeInclusive;
 
-    // Parse ranges from input (comma-separated)
-    let ranges: Vec<(u64, u64)> = input
-        .trim()
-        .split(',')
-        .filter_map(|range_str| {
-            let parts: Vec<&str> = range_str.split('-').collect();
-            if parts.len() == 2 {
-                if let (Ok(start), Ok(end)) = (parts[0].parse::<u64>(), parts[1].parse::<u64>()) {
-                    Some((start, end))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .collect();
+use crate::params::{ParamSpec, Params};
 
-    // Check each range
-    for (start, end) in ranges {
-        for id in start..=end {
-            if is_invalid_id(id) {
-                total += id;
-            }
-        }
-    }
+const SHOW: ParamSpec =
+    ParamSpec::new("show", "sum", "What to print: sum or ids").choices(&["sum", "ids"]);
+
+pub const PARAMS: &[ParamSpec] = &[SHOW];
 
-    total.to_string()
+/// Which digit patterns make an ID invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeats {
+    /// A block of digits written twice, like `6464`.
+    Twice,
+    /// A block of digits written two or more times, like `121212`.
+    AtLeastTwice,
 }
 
-pub fn sum_invalid_ids_part2(input: &str) -> String {
-    let mut total = 0u64;
+/// `10^n`, wide enough for the 20 digits of `u64::MAX`.
+const fn pow10(n: u32) -> u128 {
+    10_u128.pow(n)
+}
 
-    // Parse ranges from input (comma-separated)
-    let ranges: Vec<(u64, u64)> = input
-        .trim()
-        .split(',')
-        .filter_map(|range_str| {
-            let parts: Vec<&str> = range_str.split('-').collect();
-            if parts.len() == 2 {
-                if let (Ok(start), Ok(end)) = (parts[0].parse::<u64>(), parts[1].parse::<u64>()) {
-                    Some((start, end))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .collect();
+fn digits(n: u64) -> u32 {
+    n.checked_ilog10().map_or(1, |log| log + 1)
+}
 
-    // Check each range
-    for (start, end) in ranges {
-        for id in start..=end {
-            if is_invalid_id_part2(id) {
-                total += id;
+fn prime_factors(mut n: u32) -> Vec<u32> {
+    let mut primes = Vec::new();
+    let mut p = 2;
+    while n > 1 {
+        if n.is_multiple_of(p) {
+            primes.push(p);
+            while n.is_multiple_of(p) {
+                n /= p;
             }
         }
+        p += 1;
     }
-
-    total.to_string()
+    primes
 }
 
-fn is_invalid_id(id: u64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
+/// Numbers of `len` digits made of a `block` digit pattern are the multiples
+/// of `11`, `101`, `111`, `1001`, `10101`, ... by that pattern. Returns the
+/// multiplier and the patterns that land in `range`.
+fn patterns(len: u32, block: u32, range: &RangeInclusive<u64>) -> (u128, RangeInclusive<u128>) {
+    let multiplier = (pow10(len) - 1) / (pow10(block) - 1);
+    let first = pow10(block - 1).max(u128::from(*range.start()).div_ceil(multiplier));
+    let last = (pow10(block) - 1).min(u128::from(*range.end()) / multiplier);
+    (multiplier, first..=last)
+}
 
-    // An invalid ID must have even length (since it's a sequence repeated twice)
-    if !len.is_multiple_of(2) {
-        return false;
+/// Sum of the `len` digit numbers in `range` repeating a `block` digit pattern.
+fn pattern_sum(len: u32, block: u32, range: &RangeInclusive<u64>) -> u128 {
+    let (multiplier, patterns) = patterns(len, block, range);
+    if patterns.is_empty() {
+        return 0;
     }
-
-    let half_len = len / 2;
-    let first_half = &id_str[..half_len];
-    let second_half = &id_str[half_len..];
-
-    // Check if the second half equals the first half
-    first_half == second_half
+    let (first, last) = (*patterns.start(), *patterns.end());
+    multiplier * ((first + last) * (last - first + 1) / 2)
 }
 
-fn is_invalid_id_part2(id: u64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
-
-    // An invalid ID must be made of a sequence repeated at least twice
-    // Try all possible sequence lengths (divisors of len where we have at least 2 repetitions)
-    for seq_len in 1..=len / 2 {
-        if !len.is_multiple_of(seq_len) {
-            continue; // Can't divide evenly
+/// Repeat counts to try for `len` digit IDs, with the sign of their term.
+///
+/// For `AtLeastTwice`, a pattern repeated `r` times is also repeated `p` times
+/// for every prime `p` dividing `r`. Counting over products of distinct primes
+/// with alternating signs counts each ID once.
+fn repeat_terms(len: u32, repeats: Repeats) -> Vec<(u32, bool)> {
+    match repeats {
+        Repeats::Twice => {
+            if len.is_multiple_of(2) {
+                vec![(2, true)]
+            } else {
+                Vec::new()
+            }
         }
-
-        let num_repetitions = len / seq_len;
-        if num_repetitions < 2 {
-            continue; // Need at least 2 repetitions
+        Repeats::AtLeastTwice => {
+            let primes = prime_factors(len);
+            (1..1_u32 << primes.len())
+                .map(|mask| {
+                    let product = (0..primes.len())
+                        .filter(|&i| mask & (1 << i) != 0)
+                        .map(|i| primes[i])
+                        .product();
+                    (product, mask.count_ones() % 2 == 1)
+                })
+                .collect()
         }
+    }
+}
 
-        // Get the first sequence
-        let first_seq = &id_str[..seq_len];
-
-        // Check if all repetitions match
-        let mut all_match = true;
-        for i in 1..num_repetitions {
-            let start = i * seq_len;
-            let end = start + seq_len;
-            if &id_str[start..end] != first_seq {
-                all_match = false;
-                break;
+/// Sums the invalid IDs of `range` without visiting the valid ones.
+#[must_use]
+pub fn invalid_sum(range: &RangeInclusive<u64>, repeats: Repeats) -> u128 {
+    let (mut added, mut removed) = (0, 0);
+    for len in digits(*range.start())..=digits(*range.end()) {
+        for (times, add) in repeat_terms(len, repeats) {
+            let sum = pattern_sum(len, len / times, range);
+            if add {
+                added += sum;
+            } else {
+                removed += sum;
             }
         }
+    }
+    added - removed
+}
 
-        if all_match {
-            return true;
+/// Lists the invalid IDs of `range` in increasing order.
+#[must_use]
+pub fn invalid_ids(range: &RangeInclusive<u64>, repeats: Repeats) -> Vec<u64> {
+    let mut ids = Vec::new();
+    for len in digits(*range.start())..=digits(*range.end()) {
+        let terms = repeat_terms(len, repeats);
+        // Single primes already generate every ID, the other terms only correct the sum.
+        for (times, _) in terms.into_iter().filter(|&(t, _)| prime_factors(t) == [t]) {
+            let (multiplier, patterns) = patterns(len, len / times, range);
+            ids.extend(patterns.map(|p| u64::try_from(p * multiplier).unwrap()));
         }
     }
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+fn parse_ranges(input: &str) -> Vec<RangeInclusive<u64>> {
+    input
+        .trim()
+        .split(',')
+        .filter_map(|range| {
+            let (start, end) = range.trim().split_once('-')?;
+            Some(start.parse().ok()?..=end.parse().ok()?)
+        })
+        .collect()
+}
+
+fn solve(input: &str, params: &Params, repeats: Repeats) -> String {
+    let ranges = parse_ranges(input);
+
+    if params.value::<String>(&SHOW) == "ids" {
+        return ranges
+            .iter()
+            .map(|range| {
+                let ids: Vec<_> = invalid_ids(range, repeats)
+                    .iter()
+                    .map(u64::to_string)
+                    .collect();
+                format!("{}-{}: {}", range.start(), range.end(), ids.join(","))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    ranges
+        .iter()
+        .map(|range| invalid_sum(range, repeats))
+        .sum::<u128>()
+        .to_string()
+}
+
+pub fn sum_invalid_ids(input: &str, params: &Params) -> String {
+    solve(input, params, Repeats::Twice)
+}
 
-    false
+pub fn sum_invalid_ids_part2(input: &str, params: &Params) -> String {
+    solve(input, params, Repeats::AtLeastTwice)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
+
     #[test]
     fn test_invalid_id() {
-        assert!(is_invalid_id(11));
-        assert!(is_invalid_id(6464));
-        assert!(is_invalid_id(123123));
-        assert!(!is_invalid_id(101));
-        assert!(!is_invalid_id(123));
-        assert!(!is_invalid_id(1234));
+        for (id, twice, repeated) in [
+            (11, true, true),
+            (6464, true, true),
+            (123_123, true, true),
+            (101, false, false),
+            (123, false, false),
+            (1234, false, false),
+            (12_341_234, true, true),
+            (123_123_123, false, true),
+            (1_212_121_212, false, true),
+            (1_111_111, false, true),
+        ] {
+            let range = id..=id;
+            assert_eq!(invalid_ids(&range, Repeats::Twice) == [id], twice, "{id}");
+            assert_eq!(
+                invalid_ids(&range, Repeats::AtLeastTwice) == [id],
+                repeated,
+                "{id}"
+            );
+        }
     }
 
     #[test]
     fn test_example() {
-        let input = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
-        assert_eq!(sum_invalid_ids(input), "1227775554");
+        assert_eq!(sum_invalid_ids(EXAMPLE, &Params::default()), "1227775554");
     }
 
     #[test]
-    fn test_invalid_id_part2() {
-        assert!(is_invalid_id_part2(12341234)); // 1234 repeated 2 times
-        assert!(is_invalid_id_part2(123123123)); // 123 repeated 3 times
-        assert!(is_invalid_id_part2(1212121212)); // 12 repeated 5 times
-        assert!(is_invalid_id_part2(1111111)); // 1 repeated 7 times
-        assert!(is_invalid_id_part2(11)); // Still valid (1 repeated 2 times)
-        assert!(!is_invalid_id_part2(123)); // Not a repetition
+    fn test_example_part2() {
+        assert_eq!(
+            sum_invalid_ids_part2(EXAMPLE, &Params::default()),
+            "4174379265"
+        );
     }
 
     #[test]
-    fn test_example_part2() {
-        let input = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
-        assert_eq!(sum_invalid_ids_part2(input), "4174379265");
+    fn test_ids_per_range() {
+        let params = Params::new().with("show", "ids");
+        assert_eq!(
+            sum_invalid_ids_part2("11-22,95-115,998-1012,1698522-1698528", &params),
+            "11-22: 11,22\n95-115: 99,111\n998-1012: 999,1010\n1698522-1698528: "
+        );
+    }
+
+    #[test]
+    fn test_edge_ranges() {
+        // Ranges crossing from 2 to 4 and from 4 to 6 digits.
+        assert_eq!(invalid_ids(&(95..=1015), Repeats::Twice), [99, 1010]);
+        assert_eq!(
+            invalid_ids(&(95..=1015), Repeats::AtLeastTwice),
+            [99, 111, 222, 333, 444, 555, 666, 777, 888, 999, 1010]
+        );
+        assert_eq!(invalid_sum(&(95..=1015), Repeats::AtLeastTwice), 6104);
+        assert_eq!(
+            invalid_ids(&(9990..=100_100), Repeats::Twice),
+            [9999, 100_100]
+        );
+        assert_eq!(
+            invalid_sum(&(9990..=100_100), Repeats::AtLeastTwice),
+            9999 + 11111 * 45 + 100_100
+        );
+
+        // 1111 and 222222 repeat in more than one way but count once.
+        assert_eq!(invalid_ids(&(1111..=1111), Repeats::AtLeastTwice), [1111]);
+        assert_eq!(
+            invalid_sum(&(222_222..=222_222), Repeats::AtLeastTwice),
+            222_222
+        );
+
+        // Single digits never repeat, nor do the IDs just below u64::MAX.
+        assert!(invalid_ids(&(5..=9), Repeats::AtLeastTwice).is_empty());
+        assert_eq!(
+            invalid_sum(&(u64::MAX - 615..=u64::MAX), Repeats::AtLeastTwice),
+            0
+        );
+    }
+
+    #[test]
+    fn test_huge_range() {
+        let range = 1..=u64::MAX;
+        let twice = invalid_sum(&range, Repeats::Twice);
+        assert!(invalid_sum(&range, Repeats::AtLeastTwice) > twice);
+        // Every 2 digit ID 11..=99 with the same digit twice.
+        assert_eq!(invalid_sum(&(1..=99), Repeats::Twice), 495);
     }
 }
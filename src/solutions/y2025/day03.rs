@@ -1,170 +1,259 @@
-pub fn sum_max_joltage(input: &str) -> String {
-    let mut total = 0u64;
+use std::fmt;
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+use crate::params::{ParamSpec, Params};
 
-        // Find the maximum 2-digit number we can form by selecting exactly 2 digits
-        let max_joltage = find_max_two_digit_number(line);
-        total += max_joltage;
-    }
+const DIGITS_PART1: ParamSpec = ParamSpec::new("digits", "2", "Batteries turned on in each bank")
+    .part(1)
+    .number::<usize>();
+const DIGITS_PART2: ParamSpec = ParamSpec::new("digits", "12", "Batteries turned on in each bank")
+    .part(2)
+    .number::<usize>();
+const SELECT: ParamSpec =
+    ParamSpec::new("select", "max", "Joltage to pick: max or min").choices(&["max", "min"]);
 
-    total.to_string()
-}
+pub const PARAMS: &[ParamSpec] = &[DIGITS_PART1, DIGITS_PART2, SELECT];
 
-pub fn sum_max_joltage_part2(input: &str) -> String {
-    let mut total = 0u64;
+/// Digits a `u64` can always hold.
+const SMALL_DIGITS: usize = 19;
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+/// A non-negative integer kept as decimal digits, most significant first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decimal {
+    digits: Vec<u8>,
+}
 
-        // Find the maximum 12-digit number we can form by selecting exactly 12 digits
-        let max_joltage = find_max_twelve_digit_number_v2(line);
-        total += max_joltage;
+impl Decimal {
+    #[must_use]
+    pub fn from_digits(digits: &[u8]) -> Self {
+        let start = digits.iter().position(|&d| d != 0).unwrap_or(digits.len());
+        Self {
+            digits: digits[start..].to_vec(),
+        }
     }
 
-    total.to_string()
+    pub fn add(&mut self, other: &Self) {
+        let len = self.digits.len().max(other.digits.len()) + 1;
+        let mut sum = vec![0; len];
+        let mut carry = 0;
+        for (i, slot) in sum.iter_mut().rev().enumerate() {
+            let digit = |digits: &[u8]| digits.len().checked_sub(i + 1).map_or(0, |j| digits[j]);
+            let total = digit(&self.digits) + digit(&other.digits) + carry;
+            *slot = total % 10;
+            carry = total / 10;
+        }
+        *self = Self::from_digits(&sum);
+    }
 }
 
-fn find_max_two_digit_number(line: &str) -> u64 {
-    let digits: Vec<u8> = line
-        .chars()
-        .filter_map(|c| c.to_digit(10).map(|d| d as u8))
-        .collect();
+impl From<u64> for Decimal {
+    fn from(n: u64) -> Self {
+        Self::from_digits(&n.to_string().bytes().map(|b| b - b'0').collect::<Vec<_>>())
+    }
+}
 
-    if digits.len() < 2 {
-        return 0;
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+        self.digits.iter().try_for_each(|d| write!(f, "{d}"))
     }
+}
 
-    let mut max_value = 0u64;
+/// The number formed by selected digits, as a `u64` when it surely fits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Small(u64),
+    Big(Decimal),
+}
 
-    // Try all pairs of digits (i < j to maintain order)
-    for i in 0..digits.len() {
-        for j in (i + 1)..digits.len() {
-            let two_digit = (digits[i] as u64) * 10 + (digits[j] as u64);
-            max_value = max_value.max(two_digit);
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Small(n) => write!(f, "{n}"),
+            Self::Big(n) => write!(f, "{n}"),
         }
     }
+}
 
-    max_value
+/// Digits picked from a bank, keeping their order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub value: Value,
 }
 
-// Select 12 digits greedily to maximize the number
-fn find_max_twelve_digit_number_v2(line: &str) -> u64 {
-    let digits: Vec<u8> = line
-        .chars()
-        .filter_map(|c| c.to_digit(10).map(|d| d as u8))
-        .collect();
+impl Selection {
+    fn new(digits: &[u8], indices: Vec<usize>) -> Self {
+        let chosen: Vec<u8> = indices.iter().map(|&i| digits[i]).collect();
+        let value = if chosen.len() <= SMALL_DIGITS {
+            Value::Small(chosen.iter().fold(0, |n, &d| n * 10 + u64::from(d)))
+        } else {
+            Value::Big(Decimal::from_digits(&chosen))
+        };
+        Self { indices, value }
+    }
+}
 
-    if digits.len() < 12 {
-        return 0;
+/// Picks `k` digits with a monotonic stack, popping the kept digits for
+/// which `replace(kept, new)` holds while there are digits left to drop.
+fn select(digits: &[u8], k: usize, replace: impl Fn(u8, u8) -> bool) -> Option<Selection> {
+    let mut drop = digits.len().checked_sub(k)?;
+    let mut stack: Vec<usize> = Vec::with_capacity(digits.len());
+    for (i, &digit) in digits.iter().enumerate() {
+        while drop > 0 && stack.last().is_some_and(|&top| replace(digits[top], digit)) {
+            stack.pop();
+            drop -= 1;
+        }
+        stack.push(i);
     }
+    stack.truncate(k);
+    Some(Selection::new(digits, stack))
+}
 
-    // We need to select exactly 12 digits
-    // Use greedy selection: at each of the 12 positions, select the best possible digit
-    // that still allows us to complete the selection
+/// Picks the `k` digits forming the largest number, or `None` if there are
+/// fewer than `k` digits.
+#[must_use]
+pub fn max_subsequence(digits: &[u8], k: usize) -> Option<Selection> {
+    select(digits, k, |kept, new| kept < new)
+}
 
-    let n = digits.len();
-    let k = 12;
-    let mut selected_indices = Vec::new();
+/// Picks the `k` digits forming the smallest number, or `None` if there are
+/// fewer than `k` digits.
+#[must_use]
+pub fn min_subsequence(digits: &[u8], k: usize) -> Option<Selection> {
+    select(digits, k, |kept, new| kept > new)
+}
 
-    // Greedy: for each of the 12 positions, select the best possible digit
-    for pos in 0..k {
-        // We need to select (k - pos) more digits after this one
-        // We can choose from digits starting at some index
-        let start_idx = if selected_indices.is_empty() {
-            0
-        } else {
-            selected_indices.last().unwrap() + 1
-        };
-        let remaining_digits = n - start_idx;
-        let needed_digits = k - pos;
-
-        // We can look ahead, but must leave enough digits for the remaining selections
-        let max_look_ahead = remaining_digits - needed_digits;
-        let end_idx = start_idx + max_look_ahead + 1;
-
-        // Find the best digit we can select in this range
-        let mut best_idx = start_idx;
-        let mut best_digit = digits[start_idx];
-
-        for (i, &digit) in digits
-            .iter()
-            .enumerate()
-            .skip(start_idx)
-            .take(end_idx.min(n) - start_idx)
-        {
-            if digit > best_digit {
-                best_digit = digit;
-                best_idx = i;
-            }
-        }
+fn parse_bank(line: &str) -> Vec<u8> {
+    line.chars()
+        .filter_map(|c| c.to_digit(10).map(|d| d as u8))
+        .collect()
+}
 
-        selected_indices.push(best_idx);
-    }
+fn solve(input: &str, params: &Params, digits: &ParamSpec) -> String {
+    let k = params.value(digits);
+    let pick = match params.value::<String>(&SELECT).as_str() {
+        "min" => min_subsequence,
+        _ => max_subsequence,
+    };
 
-    // Convert to number
-    let mut result = 0u64;
-    for &idx in &selected_indices {
-        result = result * 10 + digits[idx] as u64;
+    let mut total = Decimal::default();
+    for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        // Banks with too few batteries give no joltage.
+        match pick(&parse_bank(line), k).map(|selection| selection.value) {
+            Some(Value::Small(n)) => total.add(&Decimal::from(n)),
+            Some(Value::Big(n)) => total.add(&n),
+            None => (),
+        }
     }
+    total.to_string()
+}
 
-    result
+pub fn sum_max_joltage(input: &str, params: &Params) -> String {
+    solve(input, params, &DIGITS_PART1)
+}
+
+pub fn sum_max_joltage_part2(input: &str, params: &Params) -> String {
+    solve(input, params, &DIGITS_PART2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "987654321111111
+811111111111119
+234234234234278
+818181911112111";
+
+    fn value(digits: &str, k: usize) -> Value {
+        max_subsequence(&parse_bank(digits), k).unwrap().value
+    }
+
     #[test]
     fn test_find_max_two_digit() {
-        assert_eq!(find_max_two_digit_number("987654321111111"), 98);
-        assert_eq!(find_max_two_digit_number("811111111111119"), 89);
-        assert_eq!(find_max_two_digit_number("234234234234278"), 78);
-        assert_eq!(find_max_two_digit_number("818181911112111"), 92);
+        assert_eq!(value("987654321111111", 2), Value::Small(98));
+        assert_eq!(value("811111111111119", 2), Value::Small(89));
+        assert_eq!(value("234234234234278", 2), Value::Small(78));
+        assert_eq!(value("818181911112111", 2), Value::Small(92));
     }
 
     #[test]
     fn test_example() {
-        let input = "987654321111111
-811111111111119
-234234234234278
-818181911112111";
-        assert_eq!(sum_max_joltage(input), "357");
+        assert_eq!(sum_max_joltage(EXAMPLE, &Params::default()), "357");
     }
 
     #[test]
     fn test_find_max_twelve_digit() {
+        assert_eq!(value("987654321111111", 12), Value::Small(987_654_321_111));
+        assert_eq!(value("811111111111119", 12), Value::Small(811_111_111_119));
+        assert_eq!(value("234234234234278", 12), Value::Small(434_234_234_278));
+        assert_eq!(value("818181911112111", 12), Value::Small(888_911_112_111));
+    }
+
+    #[test]
+    fn test_example_part2() {
         assert_eq!(
-            find_max_twelve_digit_number_v2("987654321111111"),
-            987654321111
-        );
-        assert_eq!(
-            find_max_twelve_digit_number_v2("811111111111119"),
-            811111111119
-        );
-        assert_eq!(
-            find_max_twelve_digit_number_v2("234234234234278"),
-            434234234278
+            sum_max_joltage_part2(EXAMPLE, &Params::default()),
+            "3121910778619"
         );
+    }
+
+    #[test]
+    fn test_indices() {
+        let digits = parse_bank("818181911112111");
+        assert_eq!(max_subsequence(&digits, 2).unwrap().indices, vec![6, 11]);
+        let min = min_subsequence(&digits, 3).unwrap();
+        assert_eq!(min.indices, vec![1, 3, 5]);
+        assert_eq!(min.value, Value::Small(111));
+        assert_eq!(max_subsequence(&digits, 16), None);
+    }
+
+    #[test]
+    fn test_big_values() {
+        let line = "9".repeat(30);
+        let Value::Big(big) = value(&line, 25) else {
+            panic!("Expected a big value");
+        };
+        assert_eq!(big.to_string(), "9".repeat(25));
+
+        let params = Params::new().with("digits", 20);
+        let input = format!("{line}\n{line}\n");
         assert_eq!(
-            find_max_twelve_digit_number_v2("818181911112111"),
-            888911112111
+            sum_max_joltage(&input, &params),
+            format!("1{}8", "9".repeat(19))
         );
+        let params = Params::new().with("digits", 3).with("select", "min");
+        assert_eq!(sum_max_joltage(EXAMPLE, &params), "555");
     }
 
     #[test]
-    fn test_example_part2() {
-        let input = "987654321111111
-811111111111119
-234234234234278
-818181911112111";
-        assert_eq!(sum_max_joltage_part2(input), "3121910778619");
+    fn test_edge_selections() {
+        let pick = |digits: &str, k: usize, max: bool| {
+            let digits = parse_bank(digits);
+            let selection = if max {
+                max_subsequence(&digits, k)
+            } else {
+                min_subsequence(&digits, k)
+            };
+            let selection = selection.unwrap();
+            (selection.indices, selection.value)
+        };
+
+        // Every digit, or none.
+        assert_eq!(
+            pick("12345", 5, true),
+            (vec![0, 1, 2, 3, 4], Value::Small(12345))
+        );
+        assert_eq!(pick("12345", 0, false), (vec![], Value::Small(0)));
+        // Ties take the leftmost digits.
+        assert_eq!(pick("5555", 2, true), (vec![0, 1], Value::Small(55)));
+        // The best digit may come after a worse one.
+        assert_eq!(pick("19191", 3, true), (vec![1, 3, 4], Value::Small(991)));
+        assert_eq!(pick("90009", 3, true), (vec![0, 1, 4], Value::Small(909)));
+        // Leading zeros make the smallest values.
+        assert_eq!(pick("3021", 2, false), (vec![1, 3], Value::Small(1)));
+        assert_eq!(pick("90009", 3, false), (vec![1, 2, 3], Value::Small(0)));
     }
 }
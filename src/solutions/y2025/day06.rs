@@ -1,204 +1,341 @@
-pub fn calculate_grand_total(input: &str) -> String {
-    let lines: Vec<&str> = input.lines().collect();
-    if lines.is_empty() {
-        return "0".to_string();
-    }
+use std::fmt;
+use std::str::FromStr;
 
-    // Find the operation line (last line with + or *)
-    let mut op_line_idx = 0;
-    for (i, line) in lines.iter().enumerate().rev() {
-        if line.contains('+') || line.contains('*') {
-            op_line_idx = i;
-            break;
-        }
-    }
+use crate::params::{ParamSpec, Params};
 
-    let op_line = lines[op_line_idx];
-    let num_lines = &lines[..op_line_idx];
+const READING_PART1: ParamSpec = ParamSpec::new(
+    "reading",
+    "rows",
+    "How numbers are written: rows or columns",
+)
+.part(1)
+.choices(&["rows", "columns"]);
+const READING_PART2: ParamSpec = ParamSpec::new(
+    "reading",
+    "columns",
+    "How numbers are written: rows or columns",
+)
+.part(2)
+.choices(&["rows", "columns"]);
 
-    // Find all columns (positions where there's a + or *)
-    // Numbers are right-aligned, so the operation is at the right edge
-    let mut columns: Vec<(usize, char)> = Vec::new();
-    for (i, ch) in op_line.char_indices() {
-        if ch == '+' || ch == '*' {
-            columns.push((i, ch));
-        }
-    }
+pub const PARAMS: &[ParamSpec] = &[READING_PART1, READING_PART2];
 
-    let mut grand_total = 0u64;
-
-    // Process each column
-    for (col_pos, op) in columns {
-        let mut numbers = Vec::new();
-
-        // Extract numbers from this column position
-        // Numbers are right-aligned, so we look left from the operation position
-        for line in num_lines {
-            let line_chars: Vec<char> = line.chars().collect();
-            if col_pos < line_chars.len() {
-                // Check if there's a digit at or near this position
-                // Look for digits going left from col_pos
-                let mut pos = col_pos;
-
-                // Skip spaces to find the rightmost digit
-                while pos < line_chars.len() && line_chars[pos] == ' ' {
-                    pos += 1;
-                }
-
-                // If we found a digit, extract the full number
-                if pos < line_chars.len() && line_chars[pos].is_ascii_digit() {
-                    // Find the end of the number (rightmost digit)
-                    let mut num_end = pos + 1;
-                    while num_end < line_chars.len() && line_chars[num_end].is_ascii_digit() {
-                        num_end += 1;
-                    }
-
-                    // Find the start of the number (leftmost digit)
-                    let mut num_start = pos;
-                    while num_start > 0 && line_chars[num_start - 1].is_ascii_digit() {
-                        num_start -= 1;
-                    }
-
-                    if num_start < num_end {
-                        let num_str: String = line_chars[num_start..num_end].iter().collect();
-                        if let Ok(num) = num_str.parse::<u64>() {
-                            numbers.push(num);
-                        }
-                    }
-                }
-            }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Max,
+    Min,
+}
+
+impl FromStr for Operator {
+    type Err = WorksheetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Self::Add),
+            "-" => Ok(Self::Sub),
+            "*" => Ok(Self::Mul),
+            "/" => Ok(Self::Div),
+            "max" => Ok(Self::Max),
+            "min" => Ok(Self::Min),
+            _ => Err(WorksheetError::UnknownOperator(s.to_string())),
         }
+    }
+}
 
-        if !numbers.is_empty() {
-            let result: u64 = if op == '+' {
-                numbers.iter().sum()
-            } else {
-                numbers.iter().product()
-            };
-            grand_total += result;
+impl Operator {
+    /// Combines two numbers, or `None` on overflow or division by zero.
+    const fn apply(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            Self::Add => a.checked_add(b),
+            Self::Sub => a.checked_sub(b),
+            Self::Mul => a.checked_mul(b),
+            Self::Div => a.checked_div(b),
+            Self::Max => Some(if a > b { a } else { b }),
+            Self::Min => Some(if a < b { a } else { b }),
         }
     }
+}
 
-    grand_total.to_string()
+/// How the numbers of a problem are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reading {
+    /// One number per row, read left to right.
+    Rows,
+    /// One number per column, digits top to bottom, columns right to left.
+    Columns,
 }
 
-pub fn calculate_grand_total_part2(input: &str) -> String {
-    let lines: Vec<&str> = input.lines().collect();
-    if lines.is_empty() {
-        return "0".to_string();
-    }
+impl FromStr for Reading {
+    type Err = String;
 
-    // Find the operation line (last line with + or *)
-    let mut op_line_idx = 0;
-    for (i, line) in lines.iter().enumerate().rev() {
-        if line.contains('+') || line.contains('*') {
-            op_line_idx = i;
-            break;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rows" => Ok(Self::Rows),
+            "columns" => Ok(Self::Columns),
+            _ => Err(format!("unknown reading '{s}', expected rows or columns")),
         }
     }
+}
 
-    let op_line = lines[op_line_idx];
-    let num_lines = &lines[..op_line_idx];
-
-    // Find problem ranges by identifying space columns (separators)
-    let max_col = lines.iter().map(|l| l.len()).max().unwrap_or(0);
-    let mut problem_ranges: Vec<(usize, usize)> = Vec::new();
-    let mut current_start: Option<usize> = None;
-
-    for col in 0..=max_col {
-        // Check if this column is all spaces (across all lines)
-        let mut is_space = true;
-        for line in &lines {
-            let line_chars: Vec<char> = line.chars().collect();
-            if col < line_chars.len() && line_chars[col] != ' ' {
-                is_space = false;
-                break;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorksheetError {
+    UnknownOperator(String),
+    MissingOperator { column: usize },
+    NoNumbers { column: usize },
+    BadNumber { column: usize, text: String },
+    Overflow { column: usize },
+    DivisionByZero { column: usize },
+}
+
+impl fmt::Display for WorksheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOperator(op) => write!(f, "unknown operator '{op}'"),
+            Self::MissingOperator { column } => {
+                write!(f, "problem at column {column} has no operator")
+            }
+            Self::NoNumbers { column } => write!(f, "problem at column {column} has no numbers"),
+            Self::BadNumber { column, text } => {
+                write!(f, "problem at column {column} has a bad number '{text}'")
+            }
+            Self::Overflow { column } => write!(f, "problem at column {column} overflows"),
+            Self::DivisionByZero { column } => {
+                write!(f, "problem at column {column} divides by zero")
             }
         }
+    }
+}
+
+/// A block of columns of the worksheet, between two blank columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// First column of the block.
+    pub column: usize,
+    pub operator: Operator,
+    /// The text of the block on each row above the operators.
+    pub rows: Vec<String>,
+}
 
-        if is_space {
-            if let Some(start) = current_start {
-                problem_ranges.push((start, col.saturating_sub(1)));
-                current_start = None;
+impl Problem {
+    /// # Errors
+    /// if a number does not parse
+    pub fn numbers(&self, reading: Reading) -> Result<Vec<i64>, WorksheetError> {
+        let texts: Vec<String> = match reading {
+            Reading::Rows => self.rows.iter().map(|row| row.trim().to_string()).collect(),
+            Reading::Columns => {
+                let width = self.rows.iter().map(|row| row.chars().count()).max();
+                (0..width.unwrap_or(0))
+                    .rev()
+                    .map(|col| {
+                        self.rows
+                            .iter()
+                            .filter_map(|row| row.chars().nth(col))
+                            .filter(|c| !c.is_whitespace())
+                            .collect()
+                    })
+                    .collect()
             }
-        } else if current_start.is_none() {
-            current_start = Some(col);
-        }
+        };
+
+        texts
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .map(|text| {
+                text.parse().map_err(|_| WorksheetError::BadNumber {
+                    column: self.column,
+                    text,
+                })
+            })
+            .collect()
     }
-    if let Some(start) = current_start {
-        problem_ranges.push((start, max_col.saturating_sub(1)));
+
+    /// Applies the operator to the numbers in reading order.
+    ///
+    /// # Errors
+    /// if a number does not parse, or the arithmetic overflows or divides by zero
+    pub fn solve(&self, reading: Reading) -> Result<i64, WorksheetError> {
+        let column = self.column;
+        let numbers = self.numbers(reading)?;
+        let (&first, rest) = numbers
+            .split_first()
+            .ok_or(WorksheetError::NoNumbers { column })?;
+
+        rest.iter().try_fold(first, |acc, &n| {
+            self.operator
+                .apply(acc, n)
+                .ok_or(if self.operator == Operator::Div && n == 0 {
+                    WorksheetError::DivisionByZero { column }
+                } else {
+                    WorksheetError::Overflow { column }
+                })
+        })
     }
+}
 
-    let mut grand_total = 0u64;
-
-    // Process each problem range (reading right-to-left)
-    for (start_col, end_col) in problem_ranges {
-        let mut numbers = Vec::new();
-        let mut op = ' ';
-
-        // Read columns right-to-left
-        for col in (start_col..=end_col).rev() {
-            // Extract number from this column (digits top to bottom)
-            let mut digits = Vec::new();
-            for line in num_lines {
-                let line_chars: Vec<char> = line.chars().collect();
-                if col < line_chars.len() {
-                    let ch = line_chars[col];
-                    if ch.is_ascii_digit() {
-                        digits.push(ch);
-                    }
-                }
-            }
+/// Splits the worksheet into problems, the operators being on the last line.
+///
+/// # Errors
+/// if a block has no operator or an unknown one
+pub fn parse_worksheet(input: &str) -> Result<Vec<Problem>, WorksheetError> {
+    let lines: Vec<Vec<char>> = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let Some((operators, rows)) = lines.split_last() else {
+        return Ok(Vec::new());
+    };
 
-            if !digits.is_empty() {
-                let num_str: String = digits.iter().collect();
-                if let Ok(num) = num_str.parse::<u64>() {
-                    numbers.push(num);
-                }
-            }
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let blank = |col: usize| {
+        lines
+            .iter()
+            .all(|line| line.get(col).is_none_or(|c| c.is_whitespace()))
+    };
+    let text = |line: &[char], cols: std::ops::Range<usize>| -> String {
+        cols.filter_map(|col| line.get(col)).collect()
+    };
 
-            // Get operation from this column (if present)
-            let op_line_chars: Vec<char> = op_line.chars().collect();
-            if col < op_line_chars.len() {
-                let ch = op_line_chars[col];
-                if ch == '+' || ch == '*' {
-                    op = ch;
-                }
-            }
+    let mut problems = Vec::new();
+    let mut col = 0;
+    while col < width {
+        if blank(col) {
+            col += 1;
+            continue;
+        }
+        let start = col;
+        while col < width && !blank(col) {
+            col += 1;
         }
 
-        if !numbers.is_empty() && (op == '+' || op == '*') {
-            let result: u64 = if op == '+' {
-                numbers.iter().sum()
-            } else {
-                numbers.iter().product()
-            };
-            grand_total += result;
+        let operator = text(operators, start..col);
+        let operator = operator.trim();
+        if operator.is_empty() {
+            return Err(WorksheetError::MissingOperator { column: start });
         }
+        problems.push(Problem {
+            column: start,
+            operator: operator.parse()?,
+            rows: rows.iter().map(|row| text(row, start..col)).collect(),
+        });
     }
+    Ok(problems)
+}
+
+/// Sums the answers of every problem.
+///
+/// # Errors
+/// if the worksheet does not parse or a problem cannot be solved
+pub fn grand_total(input: &str, reading: Reading) -> Result<i64, WorksheetError> {
+    parse_worksheet(input)?
+        .iter()
+        .try_fold(0_i64, |total, problem| {
+            let answer = problem.solve(reading)?;
+            total.checked_add(answer).ok_or(WorksheetError::Overflow {
+                column: problem.column,
+            })
+        })
+}
+
+fn solve(input: &str, params: &Params, reading: &ParamSpec) -> String {
+    let reading: Reading = match params.value::<String>(reading).parse() {
+        Ok(reading) => reading,
+        Err(e) => return e,
+    };
+    grand_total(input, reading).map_or_else(|e| e.to_string(), |total| total.to_string())
+}
 
-    grand_total.to_string()
+pub fn calculate_grand_total(input: &str, params: &Params) -> String {
+    solve(input, params, &READING_PART1)
+}
+
+pub fn calculate_grand_total_part2(input: &str, params: &Params) -> String {
+    solve(input, params, &READING_PART2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_example() {
-        let input = "123 328  51 64
+    const EXAMPLE: &str = "123 328  51 64
  45 64  387 23
   6 98  215 314
 *   +   *   +  ";
-        assert_eq!(calculate_grand_total(input), "4277556");
+
+    #[test]
+    fn test_example() {
+        assert_eq!(
+            calculate_grand_total(EXAMPLE, &Params::default()),
+            "4277556"
+        );
     }
 
     #[test]
     fn test_example_part2() {
-        let input = "123 328  51 64
- 45 64  387 23
-  6 98  215 314
-*   +   *   +  ";
-        assert_eq!(calculate_grand_total_part2(input), "3263827");
+        assert_eq!(
+            calculate_grand_total_part2(EXAMPLE, &Params::default()),
+            "3263827"
+        );
+    }
+
+    #[test]
+    fn test_unknown_reading() {
+        let params = Params::new().with("reading", "diagonals");
+        assert_eq!(
+            calculate_grand_total(EXAMPLE, &params),
+            "unknown reading 'diagonals', expected rows or columns"
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        let problems = parse_worksheet(EXAMPLE).unwrap();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(problems[1].column, 4);
+        assert_eq!(problems[1].rows, vec!["328", "64 ", "98 "]);
+        assert_eq!(problems[1].numbers(Reading::Rows), Ok(vec![328, 64, 98]));
+        assert_eq!(problems[1].numbers(Reading::Columns), Ok(vec![8, 248, 369]));
+        assert_eq!(problems[3].numbers(Reading::Columns), Ok(vec![4, 431, 623]));
+    }
+
+    #[test]
+    fn test_operators() {
+        let input = "100  7  20   3\n 40  9   5  12\n  8  2  60   4\n-    /  max min";
+        let answers: Vec<_> = parse_worksheet(input)
+            .unwrap()
+            .iter()
+            .map(|p| p.solve(Reading::Rows).unwrap())
+            .collect();
+        assert_eq!(answers, vec![52, 0, 60, 3]);
+        assert_eq!(grand_total(input, Reading::Rows), Ok(115));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |input| grand_total(input, Reading::Rows).unwrap_err();
+        assert_eq!(
+            err("4 1\n0 2\n/ %"),
+            WorksheetError::UnknownOperator("%".into())
+        );
+        assert_eq!(
+            err("4 1\n0 2\n/ +"),
+            WorksheetError::DivisionByZero { column: 0 }
+        );
+        assert_eq!(
+            err("1 2\n3 4\n+  "),
+            WorksheetError::MissingOperator { column: 2 }
+        );
+        assert_eq!(
+            err("9999999999\n9999999999\n*"),
+            WorksheetError::Overflow { column: 0 }
+        );
+        assert_eq!(
+            calculate_grand_total("12\n3x\n+ ", &Params::default()),
+            "problem at column 0 has a bad number '3x'"
+        );
     }
 }
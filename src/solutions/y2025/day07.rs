@@ -1,140 +1,247 @@
-pub fn count_beam_splits(input: &str) -> String {
-    let grid: Vec<Vec<char>> = input
-        .lines()
-        .map(|line| line.trim().chars().collect())
-        .filter(|row: &Vec<char>| !row.is_empty())
-        .collect();
-
-    if grid.is_empty() {
-        return "0".to_string();
-    }
-
-    // Find starting position (S)
-    let mut start_col = 0;
-    for row in &grid {
-        for (c, &ch) in row.iter().enumerate() {
-            if ch == 'S' {
-                start_col = c;
-                break;
-            }
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::grid::{Coord, Grid};
+use crate::viz::{NoFrames, Recorder};
+
+const SHOW: ParamSpec = ParamSpec::new(
+    "show",
+    "answer",
+    "What to print: answer, unhit splitters or paths",
+)
+.choices(&["answer", "unhit", "paths"]);
+
+pub const PARAMS: &[ParamSpec] = &[SHOW];
+
+/// Most paths listed before giving up.
+const PATH_LIMIT: usize = 10_000;
+
+/// What a beam does when it moves down onto a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    /// Empty space or a source: the beam goes on down.
+    Pass,
+    /// `^` sends a beam down each side.
+    Split,
+    /// `/` and `\` send the beam down the column on their left or right.
+    Mirror(isize),
+    /// Anything else stops the beam.
+    Absorb,
+}
+
+impl From<char> for Cell {
+    fn from(c: char) -> Self {
+        match c {
+            '.' | 'S' => Self::Pass,
+            '^' => Self::Split,
+            '/' => Self::Mirror(-1),
+            '\\' => Self::Mirror(1),
+            _ => Self::Absorb,
         }
     }
+}
 
-    let cols = grid[0].len();
-    let mut split_count = 0u64;
+/// Outcome of sending the beams of every source down the manifold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Run {
+    /// Splitters hit by at least one beam.
+    pub splits: usize,
+    /// Beams leaving through the bottom row, counting every way to get there.
+    pub timelines: u64,
+    /// Beams stopped by absorbers, counted the same way.
+    pub absorbed: u64,
+    /// Timelines leaving through each column of the bottom row.
+    pub exits: Vec<u64>,
+    /// Splitters no beam reaches, in row order.
+    pub unhit: Vec<Coord>,
+}
 
-    // Track active beam positions (column indices)
-    let mut active_beams: std::collections::HashSet<usize> = std::collections::HashSet::new();
-    active_beams.insert(start_col);
+/// A tachyon manifold, where beams start at every `S` and move down.
+pub struct Manifold {
+    rows: Vec<Vec<char>>,
+    width: usize,
+}
 
-    // Process row by row
-    for row_data in grid.iter().skip(1) {
-        let mut next_beams: std::collections::HashSet<usize> = std::collections::HashSet::new();
+impl Manifold {
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let rows: Vec<Vec<char>> = input
+            .lines()
+            .map(|line| line.trim().chars().collect())
+            .filter(|row: &Vec<char>| !row.is_empty())
+            .collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        Self { rows, width }
+    }
 
-        for &beam_col in &active_beams {
-            if beam_col >= cols {
-                continue;
-            }
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        self.rows[row]
+            .get(col)
+            .map_or(Cell::Pass, |&c| Cell::from(c))
+    }
 
-            let cell = row_data[beam_col];
-            match cell {
-                '.' => {
-                    // Beam continues downward
-                    next_beams.insert(beam_col);
-                }
-                '^' => {
-                    // Beam is split: remove current beam, add beams at left and right
-                    split_count += 1;
-                    if beam_col > 0 {
-                        next_beams.insert(beam_col - 1);
+    /// Columns a beam continues down from after moving onto a cell.
+    fn targets(&self, row: usize, col: usize) -> Vec<usize> {
+        let beside = |d: isize| col.checked_add_signed(d).filter(|&c| c < self.width);
+        match self.cell(row, col) {
+            Cell::Pass => vec![col],
+            Cell::Split => [beside(-1), beside(1)].into_iter().flatten().collect(),
+            Cell::Mirror(d) => beside(d).into_iter().collect(),
+            Cell::Absorb => Vec::new(),
+        }
+    }
+
+    fn sources(&self, row: usize) -> impl Iterator<Item = usize> + '_ {
+        self.rows[row]
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c == 'S')
+            .map(|(col, _)| col)
+    }
+
+    /// Moves the beams down one row at a time, keeping how many timelines
+    /// each column carries. Records a frame per row.
+    pub fn simulate(&self, recorder: &mut dyn Recorder) -> Run {
+        let mut beams = vec![0_u64; self.width];
+        let mut hit = vec![vec![false; self.width]; self.rows.len()];
+        let (mut splits, mut absorbed) = (0, 0);
+        let mut frame = Grid::from_rows(&self.rows);
+
+        for (row, cells) in self.rows.iter().enumerate() {
+            if row > 0 {
+                let mut next = vec![0; self.width];
+                for (col, &count) in beams.iter().enumerate().filter(|&(_, &n)| n > 0) {
+                    match self.cell(row, col) {
+                        Cell::Split if !hit[row][col] => {
+                            hit[row][col] = true;
+                            splits += 1;
+                        }
+                        Cell::Absorb => absorbed += count,
+                        _ => (),
                     }
-                    if beam_col + 1 < cols {
-                        next_beams.insert(beam_col + 1);
+                    for target in self.targets(row, col) {
+                        next[target] += count;
                     }
                 }
-                _ => {
-                    // Unknown cell, beam stops
+                beams = next;
+            }
+            for col in self.sources(row) {
+                beams[col] += 1;
+            }
+
+            if recorder.is_active() {
+                for col in (0..self.width).filter(|&col| beams[col] > 0) {
+                    let pos = Coord::new(col as i32, row as i32);
+                    if cells.get(col) == Some(&'.') {
+                        frame[pos] = '|';
+                    }
                 }
+                recorder.record(&format!("row {row}: {splits} splits"), &frame);
             }
         }
 
-        active_beams = next_beams;
-    }
+        let unhit = (0..self.rows.len())
+            .flat_map(|row| (0..self.rows[row].len()).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.rows[row][col] == '^' && !hit[row][col])
+            .map(|(row, col)| Coord::new(col as i32, row as i32))
+            .collect();
 
-    split_count.to_string()
-}
+        Run {
+            splits,
+            timelines: beams.iter().sum(),
+            absorbed,
+            exits: beams,
+            unhit,
+        }
+    }
 
-pub fn count_beam_splits_part2(input: &str) -> String {
-    let grid: Vec<Vec<char>> = input
-        .lines()
-        .map(|line| line.trim().chars().collect())
-        .filter(|row: &Vec<char>| !row.is_empty())
-        .collect();
+    #[must_use]
+    pub fn run(&self) -> Run {
+        self.simulate(&mut NoFrames)
+    }
 
-    if grid.is_empty() {
-        return "0".to_string();
+    /// Lists every timeline leaving through the bottom as the column of the
+    /// beam on each row from its source, or `None` past `limit` timelines.
+    #[must_use]
+    pub fn paths(&self, limit: usize) -> Option<Vec<Vec<usize>>> {
+        let mut paths = Vec::new();
+        for row in 0..self.rows.len() {
+            for col in self.sources(row) {
+                self.extend_path(&mut vec![col], row, &mut paths, limit)?;
+            }
+        }
+        Some(paths)
     }
 
-    // Find starting position (S)
-    let mut start_col = 0;
-    for row in &grid {
-        for (c, &ch) in row.iter().enumerate() {
-            if ch == 'S' {
-                start_col = c;
-                break;
+    fn extend_path(
+        &self,
+        path: &mut Vec<usize>,
+        row: usize,
+        paths: &mut Vec<Vec<usize>>,
+        limit: usize,
+    ) -> Option<()> {
+        if row + 1 == self.rows.len() {
+            if paths.len() == limit {
+                return None;
             }
+            paths.push(path.clone());
+            return Some(());
         }
+        for target in self.targets(row + 1, *path.last().unwrap()) {
+            path.push(target);
+            let done = self.extend_path(path, row + 1, paths, limit);
+            path.pop();
+            done?;
+        }
+        Some(())
     }
+}
 
-    let rows = grid.len();
-    let cols = grid[0].len();
+fn solve(input: &str, params: &Params, answer: fn(&Run) -> String) -> String {
+    let manifold = Manifold::parse(input);
 
-    // Use DP: track number of ways (timelines) to reach each position
-    // dp[row][col] = number of timelines that reach position (row, col)
-    let mut dp: Vec<Vec<u64>> = vec![vec![0; cols]; rows];
-    dp[0][start_col] = 1;
+    match params.value::<String>(&SHOW).as_str() {
+        "unhit" => manifold
+            .run()
+            .unhit
+            .iter()
+            .map(|pos| format!("{},{}", pos.x, pos.y))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "paths" => manifold.paths(PATH_LIMIT).map_or_else(
+            || format!("more than {PATH_LIMIT} paths"),
+            |paths| {
+                paths
+                    .iter()
+                    .map(|path| {
+                        let cols: Vec<_> = path.iter().map(usize::to_string).collect();
+                        cols.join(",")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            },
+        ),
+        _ => answer(&manifold.run()),
+    }
+}
 
-    // Process row by row
-    for (row_idx, row_data) in grid.iter().enumerate().skip(1) {
-        for col in 0..cols {
-            if dp[row_idx - 1][col] == 0 {
-                continue;
-            }
+pub fn count_beam_splits(input: &str, params: &Params) -> String {
+    solve(input, params, |run| run.splits.to_string())
+}
 
-            let cell = row_data[col];
-            match cell {
-                '.' => {
-                    // Particle continues downward: one timeline continues
-                    dp[row_idx][col] += dp[row_idx - 1][col];
-                }
-                '^' => {
-                    // Particle splits: creates two timelines (left and right)
-                    if col > 0 {
-                        dp[row_idx][col - 1] += dp[row_idx - 1][col];
-                    }
-                    if col + 1 < cols {
-                        dp[row_idx][col + 1] += dp[row_idx - 1][col];
-                    }
-                }
-                _ => {
-                    // Unknown cell, particle stops
-                }
-            }
-        }
-    }
+/// Traces the beams row by row, recording a frame for each row reached.
+pub fn visualize(input: &str, recorder: &mut dyn Recorder) -> String {
+    Manifold::parse(input).simulate(recorder).splits.to_string()
+}
 
-    // Count all timelines that reach the final row
-    let total_timelines: u64 = dp[rows - 1].iter().sum();
-    total_timelines.to_string()
+pub fn count_beam_splits_part2(input: &str, params: &Params) -> String {
+    solve(input, params, |run| run.timelines.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::viz::FrameLog;
 
-    #[test]
-    fn test_example() {
-        let input = ".......S.......
+    const EXAMPLE: &str = ".......S.......
 ...............
 .......^.......
 ...............
@@ -150,27 +257,65 @@ mod tests {
 ...............
 .^.^.^.^.^...^.
 ...............";
-        assert_eq!(count_beam_splits(input), "21");
+
+    #[test]
+    fn test_example() {
+        assert_eq!(count_beam_splits(EXAMPLE, &Params::default()), "21");
+    }
+
+    #[test]
+    fn test_visualize() {
+        let input = "..S..\n.....\n..^..\n.....\n.^...\n.....";
+        let mut log = FrameLog::default();
+
+        assert_eq!(visualize(input, &mut log), "2");
+        assert_eq!(log.frames.len(), 6);
+        assert_eq!(
+            log.frames[5].grid.to_string(),
+            "..S..\n..|..\n.|^|.\n.|.|.\n|^||.\n|.||.\n"
+        );
+        assert_eq!(log.frames[5].label, "row 5: 2 splits");
     }
 
     #[test]
     fn test_example_part2() {
-        let input = ".......S.......
-...............
-.......^.......
-...............
-......^.^......
-...............
-.....^.^.^.....
-...............
-....^.^...^....
-...............
-...^.^...^.^...
-...............
-..^...^.....^..
-...............
-.^.^.^.^.^...^.
-...............";
-        assert_eq!(count_beam_splits_part2(input), "40");
+        assert_eq!(count_beam_splits_part2(EXAMPLE, &Params::default()), "40");
+    }
+
+    #[test]
+    fn test_run() {
+        let run = Manifold::parse(EXAMPLE).run();
+        assert_eq!((run.splits, run.timelines, run.absorbed), (21, 40, 0));
+        assert_eq!(run.exits.iter().sum::<u64>(), 40);
+        assert_eq!(run.unhit, vec![Coord::new(9, 14)]);
+
+        let params = Params::new().with("show", "unhit");
+        assert_eq!(count_beam_splits(EXAMPLE, &params), "9,14");
+    }
+
+    #[test]
+    fn test_cells() {
+        // Two sources, a mirror each way and an absorber.
+        let input = ".S.S.\n.\\./.\n..S..\n..^..\n.#...\n.....";
+        let run = Manifold::parse(input).run();
+        assert_eq!(run.splits, 1);
+        assert_eq!(run.absorbed, 3);
+        assert_eq!(run.exits, vec![0, 0, 0, 3, 0]);
+        assert!(run.unhit.is_empty());
+    }
+
+    #[test]
+    fn test_paths() {
+        let manifold = Manifold::parse(EXAMPLE);
+        let paths = manifold.paths(100).unwrap();
+        assert_eq!(paths.len(), 40);
+        assert!(paths.iter().all(|path| path.len() == 16 && path[0] == 7));
+        assert_eq!(manifold.paths(39), None);
+
+        let params = Params::new().with("show", "paths");
+        assert_eq!(
+            count_beam_splits_part2("..S..\n..^..\n.....", &params),
+            "2,1,1\n2,3,3"
+        );
     }
 }
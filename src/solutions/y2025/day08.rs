@@ -1,4 +1,19 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::params::{ParamSpec, Params};
+
+const CONNECTIONS: ParamSpec =
+    ParamSpec::new("connections", "1000", "Closest pairs of boxes to connect")
+        .part(1)
+        .number::<usize>();
+const LARGEST: ParamSpec = ParamSpec::new("largest", "3", "Number of largest circuits multiplied")
+    .part(1)
+    .number::<usize>();
+
+pub const PARAMS: &[ParamSpec] = &[CONNECTIONS, LARGEST];
+
+pub type Point = [i64; 3];
 
 struct UnionFind {
     parent: Vec<usize>,
@@ -38,151 +53,251 @@ impl UnionFind {
     }
 }
 
-fn distance(p1: (i64, i64, i64), p2: (i64, i64, i64)) -> i64 {
-    let dx = p1.0 - p2.0;
-    let dy = p1.1 - p2.1;
-    let dz = p1.2 - p2.2;
-    dx * dx + dy * dy + dz * dz // Squared distance (no need to sqrt for comparison)
+fn distance(p1: Point, p2: Point) -> i64 {
+    (0..3).map(|axis| (p1[axis] - p2[axis]).pow(2)).sum() // Squared distance (no need to sqrt for comparison)
 }
 
-pub fn multiply_largest_circuits(input: &str) -> String {
-    // Parse junction box positions
-    let mut boxes: Vec<(i64, i64, i64)> = Vec::new();
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        let parts: Vec<&str> = line.split(',').collect();
-        if parts.len() == 3 {
-            if let (Ok(x), Ok(y), Ok(z)) = (
-                parts[0].parse::<i64>(),
-                parts[1].parse::<i64>(),
-                parts[2].parse::<i64>(),
-            ) {
-                boxes.push((x, y, z));
-            }
+/// A 3D k-d tree over points, stored implicitly: each range of `order` has
+/// its splitting point in the middle.
+pub struct KdTree {
+    points: Vec<Point>,
+    order: Vec<usize>,
+}
+
+impl KdTree {
+    #[must_use]
+    pub fn new(points: Vec<Point>) -> Self {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        Self::build(&points, &mut order, 0);
+        Self { points, order }
+    }
+
+    fn build(points: &[Point], order: &mut [usize], axis: usize) {
+        if order.len() <= 1 {
+            return;
         }
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by_key(mid, |&i| points[i][axis]);
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(points, left, (axis + 1) % 3);
+        Self::build(points, &mut right[1..], (axis + 1) % 3);
     }
 
-    if boxes.len() < 2 {
-        return "0".to_string();
+    #[must_use]
+    pub fn points(&self) -> &[Point] {
+        &self.points
     }
 
-    // Calculate all pairwise distances
-    let mut edges: Vec<(i64, usize, usize)> = Vec::new();
-    for i in 0..boxes.len() {
-        for j in (i + 1)..boxes.len() {
-            let dist = distance(boxes[i], boxes[j]);
-            edges.push((dist, i, j));
+    /// Returns up to `k` points accepted by `keep`, nearest to `target` first,
+    /// as squared distance and index, ties broken by index.
+    pub fn nearest(
+        &self,
+        target: Point,
+        k: usize,
+        keep: impl Fn(usize) -> bool,
+    ) -> Vec<(i64, usize)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(&self.order, 0, target, k, &keep, &mut best);
         }
+        best.into_sorted_vec()
     }
 
-    // Sort by distance
-    edges.sort_by_key(|&(dist, _, _)| dist);
+    fn search(
+        &self,
+        order: &[usize],
+        axis: usize,
+        target: Point,
+        k: usize,
+        keep: &impl Fn(usize) -> bool,
+        best: &mut BinaryHeap<(i64, usize)>,
+    ) {
+        if order.is_empty() {
+            return;
+        }
+        let mid = order.len() / 2;
+        let index = order[mid];
+        let point = self.points[index];
 
-    // Union-Find to track circuits
-    let mut uf = UnionFind::new(boxes.len());
+        if keep(index) {
+            best.push((distance(point, target), index));
+            if best.len() > k {
+                best.pop();
+            }
+        }
 
-    // Process the 1000 closest pairs (connect those that aren't already connected)
-    let pairs_to_process = edges.len().min(1000);
-    for (_, i, j) in edges.iter().take(pairs_to_process) {
-        uf.union(*i, *j); // Try to connect (may already be connected)
+        let diff = target[axis] - point[axis];
+        let (near, far) = if diff < 0 {
+            (&order[..mid], &order[mid + 1..])
+        } else {
+            (&order[mid + 1..], &order[..mid])
+        };
+        let next = (axis + 1) % 3;
+        self.search(near, next, target, k, keep, best);
+        // Points on the far side are at least `diff` away along this axis.
+        if best.len() < k || diff * diff <= best.peek().map_or(i64::MAX, |&(d, _)| d) {
+            self.search(far, next, target, k, keep, best);
+        }
     }
+}
 
-    // Count circuit sizes
-    let mut circuit_sizes: HashMap<usize, usize> = HashMap::new();
-    for i in 0..boxes.len() {
-        let root = uf.find(i);
-        *circuit_sizes.entry(root).or_insert(0) += 1;
+/// Two boxes and their squared distance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pair {
+    pub distance: i64,
+    pub a: usize,
+    pub b: usize,
+}
+
+/// Every pair of points, closest first, found a few neighbours at a time.
+///
+/// Each point streams its neighbours of higher index from the tree, fetching
+/// twice as many whenever it runs out, and a heap merges the streams.
+pub struct Pairs<'a> {
+    tree: &'a KdTree,
+    neighbours: Vec<Vec<(i64, usize)>>,
+    next: Vec<usize>,
+    heap: BinaryHeap<Reverse<(i64, usize, usize)>>,
+}
+
+impl<'a> Pairs<'a> {
+    fn new(tree: &'a KdTree) -> Self {
+        let n = tree.points.len();
+        let mut pairs = Self {
+            tree,
+            neighbours: vec![Vec::new(); n],
+            next: vec![0; n],
+            heap: BinaryHeap::new(),
+        };
+        for a in 0..n {
+            pairs.push_next(a);
+        }
+        pairs
     }
 
-    // Get the three largest circuits
-    let mut sizes: Vec<usize> = circuit_sizes.values().copied().collect();
-    sizes.sort_by(|a, b| b.cmp(a)); // Sort descending
+    /// Queues the next neighbour of `a`, fetching more if needed.
+    fn push_next(&mut self, a: usize) {
+        let fetched = self.neighbours[a].len();
+        if self.next[a] == fetched {
+            let k = (2 * fetched).max(4);
+            let found = self.tree.nearest(self.tree.points[a], k, |b| b > a);
+            if found.len() == fetched {
+                return;
+            }
+            self.neighbours[a] = found;
+        }
+        if let Some(&(distance, b)) = self.neighbours[a].get(self.next[a]) {
+            self.next[a] += 1;
+            self.heap.push(Reverse((distance, a, b)));
+        }
+    }
+}
 
-    if sizes.len() < 3 {
-        return "0".to_string();
+impl Iterator for Pairs<'_> {
+    type Item = Pair;
+
+    fn next(&mut self) -> Option<Pair> {
+        let Reverse((distance, a, b)) = self.heap.pop()?;
+        self.push_next(a);
+        Some(Pair { distance, a, b })
     }
+}
 
-    let result = sizes[0] * sizes[1] * sizes[2];
-    result.to_string()
+/// Junction boxes, with a tree to find close pairs.
+pub struct Playground {
+    tree: KdTree,
 }
 
-pub fn multiply_largest_circuits_part2(input: &str) -> String {
-    // Parse junction box positions
-    let mut boxes: Vec<(i64, i64, i64)> = Vec::new();
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        let parts: Vec<&str> = line.split(',').collect();
-        if parts.len() == 3 {
-            if let (Ok(x), Ok(y), Ok(z)) = (
-                parts[0].parse::<i64>(),
-                parts[1].parse::<i64>(),
-                parts[2].parse::<i64>(),
-            ) {
-                boxes.push((x, y, z));
-            }
+impl Playground {
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let boxes = input
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.trim().split(',').map(|n| n.parse::<i64>().ok());
+                Some([parts.next()??, parts.next()??, parts.next()??])
+            })
+            .collect();
+        Self {
+            tree: KdTree::new(boxes),
         }
     }
 
-    if boxes.len() < 2 {
-        return "0".to_string();
+    #[must_use]
+    pub fn boxes(&self) -> &[Point] {
+        self.tree.points()
     }
 
-    // Calculate all pairwise distances
-    let mut edges: Vec<(i64, usize, usize)> = Vec::new();
-    for i in 0..boxes.len() {
-        for j in (i + 1)..boxes.len() {
-            let dist = distance(boxes[i], boxes[j]);
-            edges.push((dist, i, j));
-        }
+    /// Pairs of boxes, closest first, ties in index order.
+    #[must_use]
+    pub fn pairs(&self) -> Pairs<'_> {
+        Pairs::new(&self.tree)
     }
 
-    // Sort by distance
-    edges.sort_by_key(|&(dist, _, _)| dist);
+    /// Connects the `count` closest pairs and returns the circuit sizes,
+    /// largest first.
+    #[must_use]
+    pub fn circuits(&self, count: usize) -> Vec<usize> {
+        let mut uf = UnionFind::new(self.boxes().len());
+        for pair in self.pairs().take(count) {
+            uf.union(pair.a, pair.b); // Try to connect (may already be connected)
+        }
 
-    // Union-Find to track circuits
-    let mut uf = UnionFind::new(boxes.len());
-    let mut last_connection: Option<(usize, usize)> = None;
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        for i in 0..self.boxes().len() {
+            *sizes.entry(uf.find(i)).or_insert(0) += 1;
+        }
+        let mut sizes: Vec<usize> = sizes.into_values().collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
 
-    // Connect pairs until all boxes are in one circuit
-    for (_, i, j) in edges {
-        if uf.union(i, j) {
-            // Check if all boxes are now in one circuit
-            let root = uf.find(0);
-            let mut all_connected = true;
-            for k in 1..boxes.len() {
-                if uf.find(k) != root {
-                    all_connected = false;
-                    break;
-                }
-            }
-            if all_connected {
-                last_connection = Some((i, j));
+    /// Kruskal's minimum spanning tree: the pairs that join two circuits, in
+    /// the order they are connected. The last one connects every box.
+    #[must_use]
+    pub fn spanning_tree(&self) -> Vec<Pair> {
+        let n = self.boxes().len();
+        let mut uf = UnionFind::new(n);
+        let mut edges = Vec::with_capacity(n.saturating_sub(1));
+        for pair in self.pairs() {
+            if edges.len() + 1 >= n {
                 break;
             }
+            if uf.union(pair.a, pair.b) {
+                edges.push(pair);
+            }
         }
+        edges
     }
+}
+
+pub fn multiply_largest_circuits(input: &str, params: &Params) -> String {
+    let largest: usize = params.value(&LARGEST);
+    let sizes = Playground::parse(input).circuits(params.value(&CONNECTIONS));
 
-    if let Some((i, j)) = last_connection {
-        let result = boxes[i].0 * boxes[j].0;
-        result.to_string()
-    } else {
-        "0".to_string()
+    if sizes.len() < largest {
+        return "0".to_string();
     }
+    sizes[..largest].iter().product::<usize>().to_string()
+}
+
+pub fn multiply_largest_circuits_part2(input: &str) -> String {
+    let playground = Playground::parse(input);
+    playground
+        .spanning_tree()
+        .last()
+        .map_or(0, |pair| {
+            playground.boxes()[pair.a][0] * playground.boxes()[pair.b][0]
+        })
+        .to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solutions::y2024::helpers::rng::SplitMix64;
 
-    #[test]
-    fn test_example() {
-        let input = "162,817,812
+    const EXAMPLE: &str = "162,817,812
 57,618,57
 906,360,560
 592,479,940
@@ -202,40 +317,81 @@ mod tests {
 862,61,35
 984,92,344
 425,690,689";
-        // With 20 boxes, max connections is 20*19/2 = 190
-        // We try to connect 1000 pairs, but only 190 exist
-        // After connecting all 190, all boxes will be in one circuit
-        // So we'll have fewer than 3 circuits, returning 0
-        let result = multiply_largest_circuits(input);
-        // Result should be 0 (fewer than 3 circuits) or a valid number
-        let parsed = result.parse::<u64>().unwrap();
-        assert!(parsed == 0 || parsed >= 1);
+
+    /// Every pair sorted by distance, as a reference.
+    fn all_pairs(boxes: &[Point]) -> Vec<Pair> {
+        let mut pairs = Vec::new();
+        for a in 0..boxes.len() {
+            for b in a + 1..boxes.len() {
+                pairs.push(Pair {
+                    distance: distance(boxes[a], boxes[b]),
+                    a,
+                    b,
+                });
+            }
+        }
+        pairs.sort_by_key(|pair| (pair.distance, pair.a, pair.b));
+        pairs
+    }
+
+    fn random_boxes(rng: &mut SplitMix64, n: usize, range: i64) -> Vec<Point> {
+        (0..n)
+            .map(|_| [0; 3].map(|_: i64| rng.range(0, range)))
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let params = Params::new().with("connections", 10);
+        assert_eq!(multiply_largest_circuits(EXAMPLE, &params), "40");
+        // Connecting every pair leaves a single circuit.
+        assert_eq!(multiply_largest_circuits(EXAMPLE, &Params::default()), "0");
     }
 
     #[test]
     fn test_example_part2() {
-        let input = "162,817,812
-57,618,57
-906,360,560
-592,479,940
-352,342,300
-466,668,158
-542,29,236
-431,825,988
-739,650,466
-52,470,668
-216,146,977
-819,987,18
-117,168,530
-805,96,715
-346,949,466
-970,615,88
-941,993,340
-862,61,35
-984,92,344
-425,690,689";
         // Last connection is between 216,146,977 and 117,168,530
         // X coordinates: 216 * 117 = 25272
-        assert_eq!(multiply_largest_circuits_part2(input), "25272");
+        assert_eq!(multiply_largest_circuits_part2(EXAMPLE), "25272");
+    }
+
+    #[test]
+    fn test_circuits() {
+        let playground = Playground::parse(EXAMPLE);
+        assert_eq!(
+            playground.circuits(10),
+            vec![5, 4, 2, 2, 1, 1, 1, 1, 1, 1, 1]
+        );
+        let tree = playground.spanning_tree();
+        assert_eq!(tree.len(), 19);
+        assert_eq!((tree[18].a, tree[18].b), (10, 12));
+    }
+
+    #[test]
+    fn test_pairs_in_order() {
+        let mut rng = SplitMix64::new(8);
+        for (n, range) in [(0, 10), (1, 10), (60, 1000), (80, 4)] {
+            let boxes = random_boxes(&mut rng, n, range);
+            let tree = KdTree::new(boxes.clone());
+            let pairs: Vec<Pair> = Pairs::new(&tree).collect();
+            assert_eq!(pairs, all_pairs(&boxes), "{n} boxes within {range}");
+        }
+    }
+
+    #[test]
+    fn test_many_boxes() {
+        let mut rng = SplitMix64::new(5);
+        let boxes = random_boxes(&mut rng, 3000, 100_000);
+        let input: String = boxes
+            .iter()
+            .map(|[x, y, z]| format!("{x},{y},{z}\n"))
+            .collect();
+        let playground = Playground::parse(&input);
+
+        let tree = playground.spanning_tree();
+        assert_eq!(tree.len(), 2999);
+        let longest = tree.iter().map(|pair| pair.distance).max();
+        assert_eq!(tree.last().map(|pair| pair.distance), longest);
+        assert_eq!(playground.circuits(5000).iter().sum::<usize>(), 3000);
     }
 }
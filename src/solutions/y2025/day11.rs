@@ -1,50 +1,48 @@
-type Input = Vec<Vec<usize>>;
+use crate::solutions::y2024::helpers::memo::{ArrayMemo, Interner};
 
-fn to_index(s: &str) -> usize {
-    s.bytes()
-        .take(3)
-        .fold(0, |acc, b| 26 * acc + usize::from(b - b'a'))
+/// Devices with dense ids, and the outputs of each.
+struct Graph {
+    names: Interner,
+    outputs: Vec<Vec<usize>>,
 }
 
-fn parse_graph(input: &str) -> Input {
-    let mut graph = vec![vec![]; 26 * 26 * 26];
+fn parse_graph(input: &str) -> Graph {
+    let mut names = Interner::new();
+    let mut outputs: Vec<Vec<usize>> = Vec::new();
 
     for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
+        let Some((from, to)) = line.trim().split_once(':') else {
             continue;
-        }
-
-        let parts: Vec<&str> = line.split(':').collect();
-        if parts.len() == 2 {
-            let from = parts[0].trim();
-            let from_idx = to_index(from);
-            let outputs: Vec<&str> = parts[1].split_whitespace().collect();
-            graph[from_idx].extend(outputs.iter().map(|&s| to_index(s)));
-        }
+        };
+        let from = names.intern(from.trim());
+        let to: Vec<usize> = to.split_whitespace().map(|s| names.intern(s)).collect();
+        outputs.resize(names.len(), Vec::new());
+        outputs[from].extend(to);
     }
 
-    graph
+    Graph { names, outputs }
 }
 
-fn paths(input: &Input, from: &str, to: &str) -> u64 {
-    let mut cache = vec![u64::MAX; input.len()];
-    dfs(input, &mut cache, to_index(from), to_index(to))
+/// Counts the paths between two devices, none if either is missing.
+fn paths(graph: &Graph, from: &str, to: &str) -> u64 {
+    let (Some(from), Some(to)) = (graph.names.get(from), graph.names.get(to)) else {
+        return 0;
+    };
+    let mut cache = ArrayMemo::new(graph.outputs.len());
+    dfs(&graph.outputs, &mut cache, from, to)
 }
 
-fn dfs(input: &Input, cache: &mut [u64], node: usize, end: usize) -> u64 {
+fn dfs(outputs: &[Vec<usize>], cache: &mut ArrayMemo<u64>, node: usize, end: usize) -> u64 {
     if node == end {
-        1
-    } else if cache[node] == u64::MAX {
-        let result = input[node]
-            .iter()
-            .map(|&next| dfs(input, cache, next, end))
-            .sum();
-        cache[node] = result;
-        result
-    } else {
-        cache[node]
+        return 1;
     }
+
+    cache.get_or_compute(node, |cache| {
+        outputs[node]
+            .iter()
+            .map(|&next| dfs(outputs, cache, next, end))
+            .sum()
+    })
 }
 
 pub fn count_paths_to_out(input: &str) -> String {
@@ -102,4 +100,13 @@ ggg: out
 hhh: out";
         assert_eq!(count_paths_to_out_part2(input), "2");
     }
+
+    #[test]
+    fn test_long_names() {
+        // Names are not limited to three letters, and a missing device has
+        // no paths.
+        let input = "you: middle\nmiddle: out\nother: out";
+        assert_eq!(count_paths_to_out(input), "1");
+        assert_eq!(count_paths_to_out_part2(input), "0");
+    }
 }
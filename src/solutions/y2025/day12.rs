@@ -1,408 +1,103 @@
-// Extract all unsigned integers from a string
-fn extract_unsigned<T: std::str::FromStr>(s: &str) -> Vec<T> {
-    let mut numbers = Vec::new();
-    let mut current = String::new();
-
-    for ch in s.chars() {
-        if ch.is_ascii_digit() {
-            current.push(ch);
-        } else if !current.is_empty() {
-            if let Ok(num) = current.parse::<T>() {
-                numbers.push(num);
-            }
-            current.clear();
-        }
-    }
-
-    // Handle number at end of string
-    if !current.is_empty() {
-        if let Ok(num) = current.parse::<T>() {
-            numbers.push(num);
-        }
-    }
-
-    numbers
-}
-
-pub fn count_fittable_regions(input: &str) -> String {
-    // Extract all unsigned integers from input
-    let numbers: Vec<u32> = extract_unsigned(input);
-
-    // Skip first 6 (shape indices: 0, 1, 2, 3, 4, 5)
-    // Then process in chunks of 8: [w, h, p0, p1, p2, p3, p4, p5]
-    numbers
-        .iter()
-        .skip(6)
-        .collect::<Vec<_>>()
-        .chunks(8)
-        .filter(|chunk| {
-            if chunk.len() < 8 {
-                return false;
-            }
-            let w = *chunk[0];
-            let h = *chunk[1];
-            let presents_sum: u32 = chunk[2..8].iter().copied().sum();
-            (w / 3) * (h / 3) >= presents_sum
-        })
-        .count()
-        .to_string()
+use crate::params::{ParamSpec, Params};
+use crate::solutions::y2024::helpers::packing::{Packer, Region, Shape};
+
+const SHOW: ParamSpec = ParamSpec::new("show", "count", "What to print: count or layout")
+    .part(2)
+    .choices(&["count", "layout"]);
+
+pub const PARAMS: &[ParamSpec] = &[SHOW];
+
+/// Reads a region line such as `12x5: 1 0 1 0 2 2`.
+fn parse_region(line: &str) -> Option<Region> {
+    let (size, counts) = line.split_once(':')?;
+    let (width, height) = size.split_once('x')?;
+    Some(Region {
+        width: width.trim().parse().ok()?,
+        height: height.trim().parse().ok()?,
+        counts: counts
+            .split_whitespace()
+            .map(|count| count.parse().ok())
+            .collect::<Option<_>>()?,
+    })
 }
 
-// Parse present shapes from input
-fn parse_shapes(input: &str) -> Vec<Vec<Vec<char>>> {
+/// Reads the present shapes, drawn under their index, and the regions.
+///
+/// # Panics
+/// if a line is neither part of a shape nor a region
+fn parse(input: &str) -> (Vec<Shape>, Vec<Region>) {
     let mut shapes = Vec::new();
-    let mut current_shape: Option<Vec<Vec<char>>> = None;
-
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            if let Some(shape) = current_shape.take() {
-                shapes.push(shape);
-            }
-            continue;
-        }
-
-        // Check if this is a shape definition (format: "0:")
-        if line.ends_with(':') {
-            if let Some(shape) = current_shape.take() {
-                shapes.push(shape);
-            }
-            current_shape = Some(Vec::new());
-        } else if current_shape.is_some() {
-            // This is a line of the shape
-            current_shape.as_mut().unwrap().push(line.chars().collect());
-        }
-    }
-
-    if let Some(shape) = current_shape {
-        shapes.push(shape);
-    }
-
-    shapes
-}
-
-// Parse regions from input
-fn parse_regions(input: &str) -> Vec<(usize, usize, Vec<usize>)> {
     let mut regions = Vec::new();
+    let mut drawing: Option<Vec<&str>> = None;
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        // Check if this is a region (format: "43x45: 35 25 41 42 28 38")
-        if let Some((size_str, counts_str)) = line.split_once(':') {
-            if let Some((width_str, height_str)) = size_str.split_once('x') {
-                if let (Ok(width), Ok(height)) = (
-                    width_str.trim().parse::<usize>(),
-                    height_str.trim().parse::<usize>(),
-                ) {
-                    let counts: Vec<usize> = counts_str
-                        .split_whitespace()
-                        .filter_map(|s| s.parse().ok())
-                        .collect();
-                    if counts.len() == 6 {
-                        regions.push((width, height, counts));
-                    }
-                }
-            }
+    let mut finish = |drawing: &mut Option<Vec<&str>>| {
+        if let Some(lines) = drawing.take() {
+            shapes.push(Shape::parse(&lines.join("\n")));
         }
-    }
-
-    regions
-}
-
-// Normalize shape (remove empty rows/columns from top and left)
-fn normalize_shape(shape: &[Vec<char>]) -> Vec<Vec<char>> {
-    if shape.is_empty() {
-        return Vec::new();
-    }
-
-    let mut min_row = shape.len();
-    let mut max_row = 0;
-    let mut min_col = shape[0].len();
-    let mut max_col = 0;
-
-    for (r, row) in shape.iter().enumerate() {
-        for (c, &ch) in row.iter().enumerate() {
-            if ch == '#' {
-                min_row = min_row.min(r);
-                max_row = max_row.max(r);
-                min_col = min_col.min(c);
-                max_col = max_col.max(c);
-            }
-        }
-    }
-
-    if min_row > max_row {
-        return Vec::new();
-    }
-
-    let mut normalized = Vec::new();
-    for row in shape.iter().take(max_row + 1).skip(min_row) {
-        let mut new_row = Vec::new();
-        for cell in row.iter().take(max_col + 1).skip(min_col) {
-            new_row.push(*cell);
-        }
-        normalized.push(new_row);
-    }
-
-    normalized
+    };
+    for line in input.lines().map(str::trim) {
+        if line.is_empty() {
+            finish(&mut drawing);
+        } else if line
+            .strip_suffix(':')
+            .is_some_and(|i| i.parse::<usize>().is_ok())
+        {
+            finish(&mut drawing);
+            drawing = Some(Vec::new());
+        } else if let Some(region) = parse_region(line) {
+            finish(&mut drawing);
+            regions.push(region);
+        } else if let Some(lines) = drawing.as_mut() {
+            lines.push(line);
+        } else {
+            panic!("Unexpected line '{line}'");
+        }
+    }
+    finish(&mut drawing);
+    (shapes, regions)
 }
 
-// Rotate shape 90 degrees clockwise
-fn rotate_90(shape: &[Vec<char>]) -> Vec<Vec<char>> {
-    if shape.is_empty() || shape[0].is_empty() {
-        return shape.to_vec();
-    }
-
-    let rows = shape.len();
-    let cols = shape[0].len();
-    let mut rotated = vec![vec!['.'; rows]; cols];
-
-    for r in 0..rows {
-        for c in 0..cols {
-            rotated[c][rows - 1 - r] = shape[r][c];
+/// Counts the regions where every present gets a block of its own, a quick
+/// bound that misses tighter packings.
+pub fn count_fittable_regions(input: &str) -> String {
+    let (shapes, regions) = parse(input);
+    let packer = Packer::new(&shapes);
+    let mut count = 0;
+    for (i, region) in regions.iter().enumerate() {
+        if let Err(e) = packer.check(region) {
+            return format!("region {}: {e}", i + 1);
         }
+        count += usize::from(packer.block_fits(region));
     }
-
-    rotated
-}
-
-// Flip shape horizontally
-fn flip_horizontal(shape: &[Vec<char>]) -> Vec<Vec<char>> {
-    shape
-        .iter()
-        .map(|row| row.iter().rev().copied().collect())
-        .collect()
+    count.to_string()
 }
 
-// Generate all unique orientations of a shape
-fn generate_variants(shape: &[Vec<char>]) -> Vec<Vec<Vec<char>>> {
-    use std::collections::HashSet;
-
-    let mut variants = HashSet::new();
-    let mut current = normalize_shape(shape);
+/// Counts the regions the presents can be packed into.
+pub fn count_fittable_regions_part2(input: &str, params: &Params) -> String {
+    let (shapes, regions) = parse(input);
+    let packer = Packer::new(&shapes);
 
-    // Generate all 4 rotations
-    for _ in 0..4 {
-        let normalized = normalize_shape(&current);
-        let key: String = normalized
+    if params.value::<String>(&SHOW) == "layout" {
+        return regions
             .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n");
-        variants.insert(key);
-
-        // Also try flipped versions
-        let flipped = flip_horizontal(&current);
-        let flipped_normalized = normalize_shape(&flipped);
-        let flipped_key: String = flipped_normalized
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n");
-        variants.insert(flipped_key);
-
-        current = rotate_90(&current);
-    }
-
-    // Convert back to shapes
-    variants
-        .into_iter()
-        .map(|key| key.lines().map(|line| line.chars().collect()).collect())
-        .collect()
-}
-
-// Get positions of '#' cells relative to top-left
-fn get_shape_positions(shape: &[Vec<char>]) -> Vec<(usize, usize)> {
-    let mut positions = Vec::new();
-    for (r, row) in shape.iter().enumerate() {
-        for (c, &ch) in row.iter().enumerate() {
-            if ch == '#' {
-                positions.push((r, c));
-            }
-        }
-    }
-    positions
-}
-
-// Check if presents can fit using optimized backtracking with pre-generated placements
-fn can_fit_presents(
-    width: usize,
-    height: usize,
-    shape_variants: &[Vec<Vec<Vec<char>>>],
-    required_counts: &[usize],
-) -> bool {
-    // Quick area check
-    let mut total_area = 0;
-    for (shape_idx, &count) in required_counts.iter().enumerate() {
-        if shape_idx < shape_variants.len() && count > 0 {
-            if let Some(variant) = shape_variants[shape_idx].first() {
-                total_area += get_shape_positions(variant).len() * count;
-            }
-        }
-    }
-    if total_area > width * height {
-        return false;
-    }
-
-    // Build list of pieces to place: (shape_id, instance_id)
-    let mut pieces: Vec<(usize, usize)> = Vec::new();
-    for (shape_id, &count) in required_counts.iter().enumerate() {
-        for instance in 0..count {
-            pieces.push((shape_id, instance));
-        }
-    }
-
-    if pieces.is_empty() {
-        return true;
-    }
-
-    // Precompute normalized positions for all shape variants
-    use std::collections::HashSet;
-    let mut shape_data: Vec<Vec<Vec<(usize, usize)>>> = Vec::new();
-    for shape_variants_list in shape_variants {
-        let mut normalized_variants = Vec::new();
-        let mut seen = HashSet::new();
-
-        for variant in shape_variants_list {
-            let positions = get_shape_positions(variant);
-            let min_r = positions.iter().map(|&(r, _)| r).min().unwrap_or(0);
-            let min_c = positions.iter().map(|&(_, c)| c).min().unwrap_or(0);
-            let normalized: Vec<(usize, usize)> = positions
-                .iter()
-                .map(|&(r, c)| (r - min_r, c - min_c))
-                .collect();
-
-            // Deduplicate orientations
-            let key = normalized.to_vec();
-            if seen.insert(key) {
-                normalized_variants.push(normalized);
-            }
-        }
-        shape_data.push(normalized_variants);
-    }
-
-    // Use backtracking with grid
-    let mut grid = vec![vec![false; width]; height];
-
-    fn backtrack(
-        grid: &mut [Vec<bool>],
-        pieces: &[(usize, usize)],
-        piece_idx: usize,
-        shape_data: &[Vec<Vec<(usize, usize)>>],
-        width: usize,
-        height: usize,
-    ) -> bool {
-        if piece_idx >= pieces.len() {
-            return true; // All pieces placed
-        }
-
-        let (shape_id, _instance_id) = pieces[piece_idx];
-        if shape_id >= shape_data.len() {
-            return false;
-        }
-
-        // Find first empty cell
-        let mut start_r = 0;
-        let mut start_c = 0;
-        let mut found = false;
-
-        #[allow(clippy::needless_range_loop)]
-        for r in 0..height {
-            #[allow(clippy::needless_range_loop)]
-            for c in 0..width {
-                if !grid[r][c] {
-                    start_r = r;
-                    start_c = c;
-                    found = true;
-                    break;
-                }
-            }
-            if found {
-                break;
-            }
-        }
-
-        if !found {
-            return false;
-        }
-
-        // Try each variant and position for this piece
-        for normalized_positions in &shape_data[shape_id] {
-            let max_dr = normalized_positions
-                .iter()
-                .map(|&(r, _)| r)
-                .max()
-                .unwrap_or(0);
-            let max_dc = normalized_positions
-                .iter()
-                .map(|&(_, c)| c)
-                .max()
-                .unwrap_or(0);
-
-            let end_r = height.saturating_sub(max_dr + 1);
-            let end_c = width.saturating_sub(max_dc + 1);
-
-            for r in start_r..=end_r {
-                let c_start = if r == start_r { start_c } else { 0 };
-                for c in c_start..=end_c {
-                    // Check if placement is valid
-                    let mut can_place = true;
-                    for &(dr, dc) in normalized_positions {
-                        let nr = r + dr;
-                        let nc = c + dc;
-                        if grid[nr][nc] {
-                            can_place = false;
-                            break;
-                        }
-                    }
-
-                    if can_place {
-                        // Place piece
-                        for &(dr, dc) in normalized_positions {
-                            grid[r + dr][c + dc] = true;
-                        }
-
-                        if backtrack(grid, pieces, piece_idx + 1, shape_data, width, height) {
-                            return true;
-                        }
-
-                        // Remove piece
-                        for &(dr, dc) in normalized_positions {
-                            grid[r + dr][c + dc] = false;
-                        }
-                    }
+            .map(|region| {
+                let size = format!("{}x{}", region.width, region.height);
+                match packer.pack(region) {
+                    Ok(Some(layout)) => format!("{size}:\n{layout}"),
+                    Ok(None) => format!("{size}: presents do not fit"),
+                    Err(e) => format!("{size}: {e}"),
                 }
-            }
-        }
-
-        false
-    }
-
-    backtrack(&mut grid, &pieces, 0, &shape_data, width, height)
-}
-
-pub fn count_fittable_regions_part2(input: &str) -> String {
-    let shapes = parse_shapes(input);
-    let regions = parse_regions(input);
-
-    // Pre-generate all variants for each shape
-    let mut shape_variants: Vec<Vec<Vec<Vec<char>>>> = Vec::new();
-    for shape in &shapes {
-        shape_variants.push(generate_variants(shape));
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
     }
-
     let mut count = 0;
-
-    for (width, height, required_counts) in regions {
-        if can_fit_presents(width, height, &shape_variants, &required_counts) {
-            count += 1;
+    for (i, region) in regions.iter().enumerate() {
+        match packer.pack(region) {
+            Ok(layout) => count += usize::from(layout.is_some()),
+            Err(e) => return format!("region {}: {e}", i + 1),
         }
     }
-
     count.to_string()
 }
 
@@ -412,11 +107,6 @@ mod tests {
 
     #[test]
     fn test_example() {
-        // Test with a simple case that matches the reference solution logic
-        // The reference solution uses: (w / 3) * (h / 3) >= sum of presents
-        // For 12x5 with presents [1, 0, 1, 0, 2, 2]: (12/3)*(5/3) = 4*1 = 4, sum = 6, so 4 >= 6 is false
-        // But the problem description says it CAN fit, so this is a heuristic check, not exact packing
-        // The actual input works correctly (595), so this test may be from an earlier version
         let input = "0:
 ###
 ##.
@@ -449,8 +139,6 @@ mod tests {
 
 9x9: 0 0 0 0 0 0
 12x9: 0 0 0 0 0 0";
-        // 9x9: (9/3)*(9/3) = 3*3 = 9 >= 0, so valid
-        // 12x9: (12/3)*(9/3) = 4*3 = 12 >= 0, so valid
         assert_eq!(count_fittable_regions(input), "2");
     }
 
@@ -493,6 +181,59 @@ mod tests {
         // First region (4x4: 0 0 0 0 2 0) can fit 2 presents of shape 4
         // Second region (12x5: 1 0 1 0 2 2) can fit all presents
         // Third region (12x5: 1 0 1 0 3 2) cannot fit all presents
-        assert_eq!(count_fittable_regions_part2(input), "2");
+        assert_eq!(count_fittable_regions_part2(input, &Params::default()), "2");
+    }
+
+    #[test]
+    fn test_layout() {
+        let input = "0:
+###
+#..
+###
+
+1:
+###
+.#.
+###
+
+4x4: 2 0
+6x3: 1 1
+3x3: 0 2";
+        // Only the 6x3 region has a 3x3 block for each present.
+        assert_eq!(count_fittable_regions(input), "1");
+
+        let params = Params::new().with("show", "layout");
+        assert_eq!(
+            count_fittable_regions_part2(input, &params),
+            "4x4:
+AAA.
+ABBB
+AAAB
+.BBB
+
+6x3:
+AAABBB
+A...B.
+AAABBB
+
+3x3: presents do not fit"
+        );
+    }
+
+    #[test]
+    fn test_unknown_shape() {
+        let input = "0:
+##
+
+2x2: 1
+3x3: 1 1";
+        assert_eq!(
+            count_fittable_regions(input),
+            "region 2: counts 2 shapes but only 1 are known"
+        );
+        assert_eq!(
+            count_fittable_regions_part2(input, &Params::default()),
+            "region 2: counts 2 shapes but only 1 are known"
+        );
     }
 }
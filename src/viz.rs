@@ -0,0 +1,300 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use colored::{ColoredString, Colorize};
+
+use crate::solutions::y2024::helpers::grid::Grid;
+
+/// Receives the frames of a grid simulation as they happen.
+pub trait Recorder {
+    /// Returns false when frames are discarded, so simulations can skip building them.
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    fn record(&mut self, label: &str, grid: &Grid);
+}
+
+/// Discards every frame, used when solving without visualization.
+pub struct NoFrames;
+
+impl Recorder for NoFrames {
+    fn is_active(&self) -> bool {
+        false
+    }
+
+    fn record(&mut self, _label: &str, _grid: &Grid) {}
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub label: String,
+    pub grid: Grid,
+}
+
+/// Keeps every frame in memory.
+#[derive(Debug, Default)]
+pub struct FrameLog {
+    pub frames: Vec<Frame>,
+}
+
+impl Recorder for FrameLog {
+    fn record(&mut self, label: &str, grid: &Grid) {
+        self.frames.push(Frame {
+            label: label.to_string(),
+            grid: grid.clone(),
+        });
+    }
+}
+
+/// Forwards only every n-th frame to the inner recorder.
+pub struct Every<R> {
+    inner: R,
+    every: usize,
+    count: usize,
+}
+
+impl<R: Recorder> Every<R> {
+    /// # Panics
+    /// if `every` is zero
+    pub fn new(inner: R, every: usize) -> Self {
+        assert!(every > 0, "Frame interval must be positive");
+        Self {
+            inner,
+            every,
+            count: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Recorder> Recorder for Every<R> {
+    fn is_active(&self) -> bool {
+        self.inner.is_active()
+    }
+
+    fn record(&mut self, label: &str, grid: &Grid) {
+        if self.count.is_multiple_of(self.every) {
+            self.inner.record(label, grid);
+        }
+        self.count += 1;
+    }
+}
+
+/// Writes each frame to `frame_NNNNN.txt` in a directory, label first.
+pub struct TextDump {
+    dir: PathBuf,
+    count: usize,
+    error: Option<io::Error>,
+}
+
+impl TextDump {
+    /// # Errors
+    /// if the output directory cannot be created
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            count: 0,
+            error: None,
+        })
+    }
+
+    /// Returns the number of frames written.
+    ///
+    /// # Errors
+    /// the first error met while writing frames
+    pub fn finish(self) -> io::Result<usize> {
+        self.error.map_or(Ok(self.count), Err)
+    }
+}
+
+impl Recorder for TextDump {
+    fn is_active(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn record(&mut self, label: &str, grid: &Grid) {
+        let path = self.dir.join(format!("frame_{:05}.txt", self.count));
+        match fs::write(path, format!("{label}\n{grid}")) {
+            Ok(()) => self.count += 1,
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+/// Highlights the usual puzzle symbols.
+fn paint(c: char) -> ColoredString {
+    let s = c.to_string();
+    match c {
+        '#' => s.bright_black(),
+        '@' | '^' | '>' | 'v' | '<' => s.red().bold(),
+        'O' | '[' | ']' => s.yellow(),
+        'X' | '|' => s.cyan(),
+        'S' | 'E' | '1'..='9' => s.green().bold(),
+        _ => s.normal(),
+    }
+}
+
+/// Plays frames in the terminal as they are recorded.
+///
+/// Commands are read from stdin, one per line: an empty line pauses or
+/// steps one frame, `p` resumes playing, `+`/`-` change the frame rate and
+/// `q` stops drawing.
+pub struct Player {
+    delay: Duration,
+    paused: bool,
+    stopped: bool,
+    frame: usize,
+    commands: Receiver<String>,
+}
+
+impl Player {
+    /// # Panics
+    /// if `fps` is zero
+    #[must_use]
+    pub fn new(fps: u32, paused: bool) -> Self {
+        assert!(fps > 0, "Frame rate must be positive");
+
+        let (tx, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            delay: Duration::from_secs(1) / fps,
+            paused,
+            stopped: false,
+            frame: 0,
+            commands,
+        }
+    }
+
+    fn draw(&self, label: &str, grid: &Grid) {
+        let mut screen = String::from("\x1b[2J\x1b[H");
+        for (i, (_, &c)) in grid.iter().enumerate() {
+            if i > 0 && i.is_multiple_of(grid.width() as usize) {
+                screen.push('\n');
+            }
+            screen.push_str(&paint(c).to_string());
+        }
+
+        let fps = 1.0 / self.delay.as_secs_f64();
+        let state = if self.paused { "paused" } else { "playing" };
+        screen.push_str(&format!(
+            "\n\nframe {} | {label} | {fps:.1} fps | {state}\n\
+             [enter] pause/step  [p] play  [+/-] speed  [q] quit\n",
+            self.frame
+        ));
+
+        let mut out = io::stdout().lock();
+        // A closed stdout only loses the picture, the simulation still completes.
+        let _ = out.write_all(screen.as_bytes());
+        let _ = out.flush();
+    }
+
+    /// Waits for the next frame, handling the commands typed meanwhile.
+    fn wait(&mut self) {
+        loop {
+            let command = if self.paused {
+                match self.commands.recv() {
+                    Ok(command) => command,
+                    Err(_) => {
+                        self.paused = false;
+                        return;
+                    }
+                }
+            } else {
+                match self.commands.recv_timeout(self.delay) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => return,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(self.delay);
+                        return;
+                    }
+                }
+            };
+
+            match command.trim() {
+                "" if self.paused => return,
+                "" => self.paused = true,
+                "p" => {
+                    self.paused = false;
+                    return;
+                }
+                "+" => self.delay = (self.delay / 2).max(Duration::from_millis(1)),
+                "-" => self.delay *= 2,
+                "q" => {
+                    self.stopped = true;
+                    return;
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Recorder for Player {
+    fn is_active(&self) -> bool {
+        !self.stopped
+    }
+
+    fn record(&mut self, label: &str, grid: &Grid) {
+        if self.stopped {
+            return;
+        }
+
+        self.frame += 1;
+        self.draw(label, grid);
+        self.wait();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_every() {
+        let grid = Grid::parse("#.\n.#");
+        let mut every = Every::new(FrameLog::default(), 3);
+        for i in 0..7 {
+            every.record(&i.to_string(), &grid);
+        }
+
+        let labels: Vec<_> = every
+            .into_inner()
+            .frames
+            .into_iter()
+            .map(|frame| frame.label)
+            .collect();
+        assert_eq!(labels, vec!["0", "3", "6"]);
+    }
+
+    #[test]
+    fn test_text_dump() {
+        let dir = std::env::temp_dir().join(format!("aoc-viz-test-{}", std::process::id()));
+        let mut dump = TextDump::new(&dir).unwrap();
+        dump.record("step 1", &Grid::parse("#@\n.O"));
+
+        assert_eq!(dump.finish().unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.join("frame_00000.txt")).unwrap(),
+            "step 1\n#@\n.O\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}